@@ -0,0 +1,16 @@
+'use client';
+
+import { Suspense } from 'react';
+import { useParams } from 'next/navigation';
+import { AuthCallbackFallback, AuthCallbackInner } from '@/components/auth-callback';
+
+// Callback for OIDC providers configured on the server (OIDC_<NAME>_REDIRECT_URL)
+export default function ProviderAuthCallback() {
+  const { provider } = useParams<{ provider: string }>();
+
+  return (
+    <Suspense fallback={<AuthCallbackFallback />}>
+      <AuthCallbackInner provider={provider} />
+    </Suspense>
+  );
+}
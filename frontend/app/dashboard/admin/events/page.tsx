@@ -9,6 +9,7 @@ import { Calendar, Plus, Pencil, Trash2, X, Star, Search, MapPin, Clock, Users,
 import { useState, useEffect, useRef } from 'react';
 import { useRouter } from 'next/navigation';
 import { eventsAPI } from '@/lib/api';
+import { formatEventDate, formatEventTime, toLocalInput } from '@/lib/event-time';
 
 interface EventSpeaker {
   name: string;
@@ -19,9 +20,9 @@ interface EventSpeaker {
 interface EventItem {
   _id: string | { $oid: string };
   title: string;
-  date: string;
-  time: string;
-  end_date?: string;
+  starts_at: string;
+  ends_at?: string;
+  timezone: string;
   location: string;
   event_type: string;
   status: string;
@@ -38,6 +39,7 @@ interface EventItem {
 
 const EVENT_TYPES = ['Workshop', 'Competition', 'Hackathon', 'Meetup', 'Other'];
 const EVENT_STATUSES = ['Upcoming', 'Ongoing', 'Completed'];
+const BROWSER_TIMEZONE = Intl.DateTimeFormat().resolvedOptions().timeZone;
 
 export default function AdminEventsPage() {
   const { token, user } = useAuth();
@@ -66,9 +68,9 @@ export default function AdminEventsPage() {
 
   const emptyForm = {
     title: '',
-    date: '',
-    time: '',
-    end_date: '',
+    starts_at: '',
+    ends_at: '',
+    timezone: BROWSER_TIMEZONE,
     location: '',
     event_type: 'Workshop',
     status: 'Upcoming',
@@ -150,15 +152,15 @@ export default function AdminEventsPage() {
     try {
       const payload: any = {
         title: formData.title,
-        date: formData.date,
-        time: formData.time,
+        starts_at: formData.starts_at,
+        timezone: formData.timezone,
         location: formData.location,
         event_type: formData.event_type,
         status: formData.status,
         description: formData.description,
         featured: formData.featured,
       };
-      if (formData.end_date) payload.end_date = formData.end_date;
+      if (formData.ends_at) payload.ends_at = formData.ends_at;
       if (formData.image) payload.image = formData.image;
       if (formData.register_link) payload.register_link = formData.register_link;
       if (formData.recap_link) payload.recap_link = formData.recap_link;
@@ -184,15 +186,15 @@ export default function AdminEventsPage() {
       const payload: any = {
         id: extractId(selectedEvent._id),
         title: formData.title,
-        date: formData.date,
-        time: formData.time,
+        starts_at: formData.starts_at,
+        timezone: formData.timezone,
         location: formData.location,
         event_type: formData.event_type,
         status: formData.status,
         description: formData.description,
         featured: formData.featured,
       };
-      if (formData.end_date) payload.end_date = formData.end_date;
+      if (formData.ends_at) payload.ends_at = formData.ends_at;
       if (formData.image) payload.image = formData.image;
       if (formData.register_link) payload.register_link = formData.register_link;
       if (formData.recap_link) payload.recap_link = formData.recap_link;
@@ -233,9 +235,9 @@ export default function AdminEventsPage() {
     setSelectedEvent(event);
     setFormData({
       title: event.title,
-      date: event.date,
-      time: event.time,
-      end_date: event.end_date || '',
+      starts_at: toLocalInput(event.starts_at, event.timezone),
+      ends_at: event.ends_at ? toLocalInput(event.ends_at, event.timezone) : '',
+      timezone: event.timezone,
       location: event.location,
       event_type: event.event_type,
       status: event.status,
@@ -301,14 +303,6 @@ export default function AdminEventsPage() {
     return colors[type] || colors.Other;
   };
 
-  const formatDate = (dateStr: string) => {
-    try {
-      return new Date(dateStr).toLocaleDateString('en-US', { year: 'numeric', month: 'short', day: 'numeric' });
-    } catch {
-      return dateStr;
-    }
-  };
-
   const renderFormModal = (isEdit: boolean) => (
     <motion.div
       key={isEdit ? 'edit-modal' : 'create-modal'}
@@ -350,36 +344,37 @@ export default function AdminEventsPage() {
 
           <div className="grid grid-cols-1 sm:grid-cols-2 gap-4">
             <div>
-              <label className="block text-sm font-medium text-gray-300 mb-1">Date *</label>
+              <label className="block text-sm font-medium text-gray-300 mb-1">Starts *</label>
               <input
-                type="date"
+                type="datetime-local"
                 required
-                value={formData.date}
-                onChange={e => setFormData(prev => ({ ...prev, date: e.target.value }))}
+                value={formData.starts_at}
+                onChange={e => setFormData(prev => ({ ...prev, starts_at: e.target.value }))}
                 className="w-full px-4 py-2 bg-dark-300/50 border border-primary/10 rounded-lg focus:border-primary/30 outline-none text-white"
               />
             </div>
             <div>
-              <label className="block text-sm font-medium text-gray-300 mb-1">Time *</label>
+              <label className="block text-sm font-medium text-gray-300 mb-1">Ends (optional)</label>
               <input
-                type="text"
-                required
-                value={formData.time}
-                onChange={e => setFormData(prev => ({ ...prev, time: e.target.value }))}
+                type="datetime-local"
+                min={formData.starts_at}
+                value={formData.ends_at}
+                onChange={e => setFormData(prev => ({ ...prev, ends_at: e.target.value }))}
                 className="w-full px-4 py-2 bg-dark-300/50 border border-primary/10 rounded-lg focus:border-primary/30 outline-none text-white"
-                placeholder="e.g., 02:00 PM - 05:00 PM"
               />
             </div>
           </div>
 
           <div className="grid grid-cols-1 sm:grid-cols-2 gap-4">
             <div>
-              <label className="block text-sm font-medium text-gray-300 mb-1">End Date (optional)</label>
+              <label className="block text-sm font-medium text-gray-300 mb-1">Time Zone *</label>
               <input
-                type="date"
-                value={formData.end_date}
-                onChange={e => setFormData(prev => ({ ...prev, end_date: e.target.value }))}
+                type="text"
+                required
+                value={formData.timezone}
+                onChange={e => setFormData(prev => ({ ...prev, timezone: e.target.value }))}
                 className="w-full px-4 py-2 bg-dark-300/50 border border-primary/10 rounded-lg focus:border-primary/30 outline-none text-white"
+                placeholder="e.g., Asia/Kolkata"
               />
             </div>
             <div>
@@ -710,11 +705,11 @@ export default function AdminEventsPage() {
                     <div className="flex flex-wrap gap-4 text-xs text-gray-400">
                       <div className="flex items-center gap-1">
                         <Calendar className="w-3.5 h-3.5 text-cyan-400" />
-                        <span>{formatDate(event.date)}</span>
+                        <span>{formatEventDate(event.starts_at, event.timezone)}</span>
                       </div>
                       <div className="flex items-center gap-1">
                         <Clock className="w-3.5 h-3.5 text-cyan-400" />
-                        <span>{event.time}</span>
+                        <span>{formatEventTime(event.starts_at, event.ends_at, event.timezone)}</span>
                       </div>
                       <div className="flex items-center gap-1">
                         <MapPin className="w-3.5 h-3.5 text-cyan-400" />
@@ -8,6 +8,7 @@ import { Mail, Users, User, Clock } from 'lucide-react';
 // @ts-ignore - Icons exist at runtime but TypeScript hasn't updated
 import { Send, FolderKanban, Inbox } from 'lucide-react';
 import { useState, useEffect } from 'react';
+import { messagesAPI, projectsAPI, usersAPI } from '@/lib/api';
 
 type MessageType = 'individual' | 'project_team' | 'broadcast';
 
@@ -77,19 +78,8 @@ export default function MessagesPage() {
 
   const fetchProjects = async () => {
     try {
-      const response = await fetch('http://localhost:5657/projects', {
-        method: 'GET',
-        headers: {
-          'Authorization': `Bearer ${token}`,
-        },
-      });
-      if (response.ok) {
-        const data = await response.json();
-        console.log('Fetched projects:', data);
-        setProjects(data);
-      } else {
-        console.error('Failed to fetch projects, status:', response.status);
-      }
+      const data = await projectsAPI.getAll(token!);
+      setProjects(data);
     } catch (error) {
       console.error('Failed to fetch projects:', error);
     }
@@ -97,15 +87,8 @@ export default function MessagesPage() {
 
   const fetchMembers = async () => {
     try {
-      const response = await fetch('http://localhost:5657/users', {
-        headers: {
-          'Authorization': `Bearer ${token}`,
-        },
-      });
-      if (response.ok) {
-        const data = await response.json();
-        setMembers(data);
-      }
+      const data = await usersAPI.getAll(token!);
+      setMembers(data);
     } catch (error) {
       console.error('Failed to fetch members:', error);
     }
@@ -117,33 +100,22 @@ export default function MessagesPage() {
       const userId = user?._id || user?.id;
       if (!userId) return;
       
-      const response = await fetch('http://localhost:5657/messages/user', {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-          'Authorization': `Bearer ${token}`,
-        },
-        body: JSON.stringify({ user_id: extractId(userId) }),
-      });
-
-      if (response.ok) {
-        const data = await response.json();
-        // Sort: unread first, then by date descending
-        const sortedData = data.sort((a: Message, b: Message) => {
-          if (a.read === b.read) {
-            return new Date(b.created_at).getTime() - new Date(a.created_at).getTime();
-          }
-          return a.read ? 1 : -1;
-        });
-        setMessages(sortedData);
-        
-        // Fetch sender details
-        const uniqueSenderIds = [...new Set(sortedData.map((msg: Message) => extractId(msg.sender_id)))] as string[];
-        await fetchSenders(uniqueSenderIds);
-        
-        if (sortedData.length > 0) {
-          setSelectedMessage(sortedData[0]);
+      const data = await messagesAPI.getUser(token!);
+      // Sort: unread first, then by date descending
+      const sortedData = data.sort((a: Message, b: Message) => {
+        if (a.read === b.read) {
+          return new Date(b.created_at).getTime() - new Date(a.created_at).getTime();
         }
+        return a.read ? 1 : -1;
+      });
+      setMessages(sortedData);
+      
+      // Fetch sender details
+      const uniqueSenderIds = [...new Set(sortedData.map((msg: Message) => extractId(msg.sender_id)))] as string[];
+      await fetchSenders(uniqueSenderIds);
+      
+      if (sortedData.length > 0) {
+        setSelectedMessage(sortedData[0]);
       }
     } catch (error) {
       console.error('Failed to load messages:', error);
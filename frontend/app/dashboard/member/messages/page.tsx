@@ -8,6 +8,7 @@ import { Mail, Clock, User, Users } from 'lucide-react';
 import { Inbox, FolderKanban, Send } from 'lucide-react';
 import { useState, useEffect } from 'react';
 import { useToast } from '@/contexts/ToastContext';
+import { messagesAPI } from '@/lib/api';
 
 interface Message {
   _id: string;
@@ -49,34 +50,23 @@ export default function MessagesPage() {
       const userId = user?._id || user?.id;
       if (!userId) return;
       
-      const response = await fetch('http://localhost:5657/messages/user', {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-          'Authorization': `Bearer ${token}`,
-        },
-        body: JSON.stringify({ user_id: userId }),
-      });
-
-      if (response.ok) {
-        const data = await response.json();
-        // Sort: unread first, then by date descending
-        const sortedData = data.sort((a: Message, b: Message) => {
-          if (a.read === b.read) {
-            return new Date(b.created_at).getTime() - new Date(a.created_at).getTime();
-          }
-          return a.read ? 1 : -1;
-        });
-        setMessages(sortedData);
-        
-        // Fetch sender details - extract string IDs from ObjectIds
-        const uniqueSenderIds = [...new Set(sortedData.map((msg: Message) => extractId(msg.sender_id)))] as string[];
-        await fetchSenders(uniqueSenderIds);
-        
-        // Auto-select first message if available
-        if (sortedData.length > 0) {
-          setSelectedMessage(sortedData[0]);
+      const data = await messagesAPI.getUser(token!);
+      // Sort: unread first, then by date descending
+      const sortedData = data.sort((a: Message, b: Message) => {
+        if (a.read === b.read) {
+          return new Date(b.created_at).getTime() - new Date(a.created_at).getTime();
         }
+        return a.read ? 1 : -1;
+      });
+      setMessages(sortedData);
+      
+      // Fetch sender details - extract string IDs from ObjectIds
+      const uniqueSenderIds = [...new Set(sortedData.map((msg: Message) => extractId(msg.sender_id)))] as string[];
+      await fetchSenders(uniqueSenderIds);
+      
+      // Auto-select first message if available
+      if (sortedData.length > 0) {
+        setSelectedMessage(sortedData[0]);
       }
     } catch (error) {
       console.error('Failed to load messages:', error);
@@ -26,16 +26,9 @@ export default function MemberDashboard() {
 
   const loadStats = async () => {
     try {
-      const userId = user?._id || user?.id;
-      if (!userId) {
-        console.error('No user ID found');
-        setLoading(false);
-        return;
-      }
-
       const [projectsData, messages] = await Promise.all([
-        projectsAPI.getUser(token!, userId),
-        messagesAPI.getUser(token!, userId),
+        projectsAPI.getUser(token!),
+        messagesAPI.getUser(token!),
       ]);
 
       setProjects(projectsData.slice(0, 3)); // Get only first 3 for recent projects
@@ -0,0 +1,75 @@
+"use client";
+
+import { useAuth } from '@/contexts/AuthContext';
+import { useRouter } from 'next/navigation';
+import { useState } from 'react';
+import { motion } from 'framer-motion';
+import { authAPI } from '@/lib/api';
+
+// Second login step for accounts with two-factor authentication enabled
+export default function TwoFactorPage() {
+  const { login } = useAuth();
+  const router = useRouter();
+  const [code, setCode] = useState('');
+  const [error, setError] = useState('');
+  const [submitting, setSubmitting] = useState(false);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    const challenge = sessionStorage.getItem('two_factor_challenge');
+    if (!challenge) {
+      router.push('/login?error=auth_failed');
+      return;
+    }
+
+    setSubmitting(true);
+    setError('');
+    try {
+      const data = await authAPI.verifyTwoFactor(challenge, code);
+      sessionStorage.removeItem('two_factor_challenge');
+      login(data.user, data.token);
+      router.push(data.user.role === 'Admin' ? '/dashboard/admin' : '/dashboard/member');
+    } catch {
+      setError('Invalid code, try again');
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <div className="min-h-screen bg-gradient-to-br from-dark-100 via-dark-200 to-dark-100 flex items-center justify-center p-4">
+      <motion.div
+        initial={{ opacity: 0, y: 20 }}
+        animate={{ opacity: 1, y: 0 }}
+        transition={{ duration: 0.5 }}
+        className="max-w-md w-full"
+      >
+        <form onSubmit={handleSubmit} className="glass-card p-8 rounded-2xl border border-primary/10 space-y-6">
+          <div className="text-center">
+            <h2 className="text-2xl font-bold mb-2">Two-Factor Authentication</h2>
+            <p className="text-gray-400 text-sm">Enter the code from your authenticator app or a recovery code</p>
+          </div>
+
+          <input
+            value={code}
+            onChange={(e) => setCode(e.target.value)}
+            autoComplete="one-time-code"
+            autoFocus
+            className="w-full px-4 py-3 bg-dark-300 border border-gray-700 rounded-xl text-center tracking-widest"
+            placeholder="123456"
+          />
+
+          {error && <p className="text-red-400 text-sm text-center">{error}</p>}
+
+          <button
+            type="submit"
+            disabled={submitting || !code}
+            className="w-full px-6 py-4 bg-gradient-to-r from-gray-800 to-gray-900 hover:from-gray-700 hover:to-gray-800 rounded-xl font-medium transition-all duration-300 border border-gray-700 hover:border-gray-600 disabled:opacity-50"
+          >
+            {submitting ? 'Verifying...' : 'Verify'}
+          </button>
+        </form>
+      </motion.div>
+    </div>
+  );
+}
@@ -15,7 +15,8 @@ export default function TestLogin() {
       // Clear any existing tokens first
       localStorage.clear();
       
-      // Generate a test JWT token by calling backend
+      // Generate a test JWT token by calling backend. Only available when the
+      // server is built with `--features dev-login` and runs with DEV_MODE=true
       const response = await fetch('http://localhost:5657/auth/test-login', {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
@@ -11,6 +11,7 @@ import { ScrollTrigger } from "gsap/ScrollTrigger";
 import { ScrollToPlugin } from "gsap/ScrollToPlugin";
 
 import { eventsAPI } from "@/lib/api";
+import { formatEventDate, formatEventTime } from "@/lib/event-time";
 import { useAuth } from "@/contexts/AuthContext";
 
 // Types (matching the static data types but now populated from API)
@@ -29,6 +30,7 @@ export interface EventData {
   date: string;
   time: string;
   endDate?: string;
+  timezone: string;
   location: string;
   type: EventType;
   status: EventStatus;
@@ -123,9 +125,10 @@ export default function EventsPage() {
         const mapped: EventData[] = data.map((event: any) => ({
           id: typeof event._id === 'string' ? event._id : event._id?.$oid || '',
           title: event.title,
-          date: event.date,
-          time: event.time,
-          endDate: event.end_date,
+          date: event.starts_at,
+          time: formatEventTime(event.starts_at, event.ends_at, event.timezone),
+          endDate: event.ends_at,
+          timezone: event.timezone,
           location: event.location,
           type: (event.event_type || 'Other') as EventType,
           status: (event.status || 'Upcoming') as EventStatus,
@@ -442,14 +445,11 @@ export default function EventsPage() {
     }
   };
   
-  const formatDate = (dateString: string) => {
-    const date = new Date(dateString);
-    return date.toLocaleDateString('en-US', {
-      month: 'long',
-      day: 'numeric',
-      year: 'numeric'
-    });
-  };
+  const formatDate = (dateString: string, timeZone?: string) => formatEventDate(dateString, timeZone);
+
+  // Same-day events show their end in the time range instead
+  const endsOnLaterDay = (event: EventData) =>
+    !!event.endDate && formatDate(event.endDate, event.timezone) !== formatDate(event.date, event.timezone);
 
   // Get featured events
   const featuredEvents = allEvents.filter(event => event.featured);
@@ -873,7 +873,7 @@ export default function EventsPage() {
                     
                     <div className="flex items-center text-sm text-gray-400 mb-4">
                       <Calendar className="w-4 h-4 mr-2" />
-                      <span>{formatDate(event.date)}</span>
+                      <span>{formatDate(event.date, event.timezone)}</span>
                     </div>
                     
                     <p className="text-gray-400 mb-4 line-clamp-2">
@@ -1047,9 +1047,9 @@ export default function EventsPage() {
                               
                               <div className="flex items-center text-sm text-gray-400 mb-2">
                                 <Calendar className="w-4 h-4 mr-2" />
-                                <span>{formatDate(event.date)}</span>
-                                {event.endDate && (
-                                  <span className="ml-2">- {formatDate(event.endDate)}</span>
+                                <span>{formatDate(event.date, event.timezone)}</span>
+                                {endsOnLaterDay(event) && (
+                                  <span className="ml-2">- {formatDate(event.endDate!, event.timezone)}</span>
                                 )}
                               </div>
                               
@@ -1091,7 +1091,7 @@ export default function EventsPage() {
                             transition={{ duration: 0.5, delay: 0.3 }}
                           >
                             <div className={`${eventIndex % 2 === 0 ? 'ml-8' : 'mr-8 text-right'}`}>
-                              <div className="text-xl font-bold text-glow-cyan">{formatDate(event.date).split(',')[0]}</div>
+                              <div className="text-xl font-bold text-glow-cyan">{formatDate(event.date, event.timezone).split(',')[0]}</div>
                               <div className="text-gray-400 text-sm">{event.time}</div>
                             </div>
                           </motion.div>
@@ -1153,9 +1153,9 @@ export default function EventsPage() {
                     
                     <div className="flex items-center text-sm text-gray-400 mb-4">
                       <Calendar className="w-4 h-4 mr-2" />
-                      <span>{formatDate(event.date)}</span>
-                      {event.endDate && (
-                        <span className="ml-2">- {formatDate(event.endDate)}</span>
+                      <span>{formatDate(event.date, event.timezone)}</span>
+                      {endsOnLaterDay(event) && (
+                        <span className="ml-2">- {formatDate(event.endDate!, event.timezone)}</span>
                       )}
                     </div>
                     
@@ -1259,9 +1259,9 @@ export default function EventsPage() {
                     <div>
                       <div className="text-sm text-gray-400">Date</div>
                       <div className="font-medium">
-                        {formatDate(eventDetail.event.date)}
-                        {eventDetail.event.endDate && (
-                          <span> - {formatDate(eventDetail.event.endDate)}</span>
+                        {formatDate(eventDetail.event.date, eventDetail.event.timezone)}
+                        {endsOnLaterDay(eventDetail.event) && (
+                          <span> - {formatDate(eventDetail.event.endDate!, eventDetail.event.timezone)}</span>
                         )}
                       </div>
                     </div>
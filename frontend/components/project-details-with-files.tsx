@@ -4,6 +4,7 @@ import { useState, useEffect } from 'react';
 import { useAuth } from '@/contexts/AuthContext';
 import { ProjectFiles } from '@/components/project-files';
 import { useToast } from '@/contexts/ToastContext';
+import { projectsAPI } from '@/lib/api';
 
 interface ProjectDetailsPageProps {
   projectId: string;
@@ -25,15 +26,7 @@ export function ProjectDetailsWithFiles({ projectId }: ProjectDetailsPageProps)
 
   const loadProject = async () => {
     try {
-      const response = await fetch('http://localhost:5657/projects', {
-        headers: {
-          Authorization: `Bearer ${token}`,
-        },
-      });
-
-      if (!response.ok) throw new Error('Failed to load projects');
-
-      const projects = await response.json();
+      const projects = await projectsAPI.getAll(token!);
       const foundProject = projects.find((p: any) => extractId(p._id) === projectId);
 
       if (foundProject) {
@@ -4,6 +4,7 @@ import { useEffect, useRef, useState } from "react";
 import { motion } from "framer-motion";
 import { Calendar, Clock, MapPin } from "lucide-react";
 import { eventsAPI } from "@/lib/api";
+import { formatEventDate, formatEventTime } from "@/lib/event-time";
 
 interface HomeEvent {
   id: string;
@@ -27,13 +28,13 @@ export default function Events() {
         const data = await eventsAPI.getAll();
         // Map API events to the display format, show latest 4
         const mapped: HomeEvent[] = data
-          .sort((a: any, b: any) => new Date(b.date).getTime() - new Date(a.date).getTime())
+          .sort((a: any, b: any) => new Date(b.starts_at).getTime() - new Date(a.starts_at).getTime())
           .slice(0, 4)
           .map((event: any) => ({
             id: typeof event._id === 'string' ? event._id : event._id?.$oid || event.id || '',
             title: event.title,
-            date: new Date(event.date).toLocaleDateString('en-US', { year: 'numeric', month: 'long', day: 'numeric' }),
-            time: event.time,
+            date: formatEventDate(event.starts_at, event.timezone),
+            time: formatEventTime(event.starts_at, event.ends_at, event.timezone),
             location: event.location,
             description: event.description,
             isUpcoming: event.status === 'Upcoming' || event.status === 'Ongoing',
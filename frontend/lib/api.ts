@@ -42,15 +42,36 @@ export async function apiFetch(endpoint: string, options: FetchOptions = {}) {
   return response.json();
 }
 
+// List endpoints return `{ items, next_cursor, total }` a page at a time;
+// follow the cursors and return every item
+export async function apiFetchAll(endpoint: string, options: FetchOptions = {}) {
+  const items: any[] = [];
+  let cursor: string | null = null;
+  do {
+    const separator = endpoint.includes('?') ? '&' : '?';
+    const page = cursor
+      ? `${endpoint}${separator}limit=100&cursor=${encodeURIComponent(cursor)}`
+      : `${endpoint}${separator}limit=100`;
+    const data = await apiFetch(page, options);
+    items.push(...data.items);
+    cursor = data.next_cursor;
+  } while (cursor);
+  return items;
+}
+
 // Auth API
 export const authAPI = {
   githubLogin: () => `${API_BASE_URL}/auth/github`,
+  providerLogin: (provider: string) => `${API_BASE_URL}/auth/${provider}`,
+  getProviders: () => apiFetch('/auth/providers'),
+  verifyTwoFactor: (challengeToken: string, code: string) =>
+    apiFetch('/auth/2fa/verify', { method: 'POST', body: JSON.stringify({ challenge_token: challengeToken, code }) }),
 };
 
 // Users API
 export const usersAPI = {
-  getAll: (token: string) => apiFetch('/users', { token }),
-  getMembers: (token: string) => apiFetch('/members', { token }),
+  getAll: (token: string) => apiFetchAll('/users', { token }),
+  getMembers: (token: string) => apiFetchAll('/members', { token }),
   create: (token: string, data: any) => apiFetch('/users', { method: 'POST', token, body: JSON.stringify(data) }),
   updateRole: (token: string, data: any) => apiFetch('/users/role', { method: 'POST', token, body: JSON.stringify(data) }),
   delete: (token: string, data: any) => apiFetch('/users', { method: 'DELETE', token, body: JSON.stringify(data) }),
@@ -58,8 +79,8 @@ export const usersAPI = {
 
 // Projects API
 export const projectsAPI = {
-  getAll: (token?: string) => apiFetch('/projects', token ? { token } : {}),
-  getUser: (token: string, userId: string) => apiFetch('/projects/user', { method: 'POST', token, body: JSON.stringify(userId) }),
+  getAll: (token?: string) => apiFetchAll('/projects', token ? { token } : {}),
+  getUser: (token: string) => apiFetch('/projects/user', { token }),
   create: (token: string, data: any) => apiFetch('/projects/admin', { method: 'POST', token, body: JSON.stringify(data) }),
   update: (token: string, data: any) => apiFetch('/projects/admin', { method: 'PATCH', token, body: JSON.stringify(data) }),
   assign: (token: string, data: any) => apiFetch('/projects/assign', { method: 'POST', token, body: JSON.stringify(data) }),
@@ -78,7 +99,7 @@ export const projectsAPI = {
 // Coins API
 export const coinsAPI = {
   manage: (token: string, data: any) => apiFetch('/coins/manage', { method: 'POST', token, body: JSON.stringify(data) }),
-  getTransactions: (token: string, userId: string) => apiFetch('/coins/transactions', { method: 'POST', token, body: JSON.stringify(userId) }),
+  getTransactions: (token: string) => apiFetchAll('/coins/transactions', { token }),
   getLeaderboard: () => apiFetch('/coins/leaderboard', {}),
   saveLeaderboard: (token: string) => apiFetch('/coins/leaderboard/save', { method: 'POST', token }),
 };
@@ -86,13 +107,13 @@ export const coinsAPI = {
 // Messages API
 export const messagesAPI = {
   send: (token: string, data: any) => apiFetch('/messages/send', { method: 'POST', token, body: JSON.stringify(data) }),
-  getUser: (token: string, userId: string) => apiFetch('/messages/user', { method: 'POST', token, body: JSON.stringify({ user_id: userId }) }),
-  getAll: (token: string) => apiFetch('/messages', { token }),
+  getUser: (token: string) => apiFetchAll('/messages/user', { token }),
+  getAll: (token: string) => apiFetchAll('/messages', { token }),
 };
 
 // Gallery API
 export const galleryAPI = {
-  getAll: () => apiFetch('/gallery', {}),
+  getAll: () => apiFetchAll('/gallery', {}),
   create: (token: string, data: any) => apiFetch('/gallery/admin', { method: 'POST', token, body: JSON.stringify(data) }),
   update: (token: string, data: any) => apiFetch('/gallery/admin', { method: 'PATCH', token, body: JSON.stringify(data) }),
   delete: (token: string, data: { id: string }) => apiFetch('/gallery/admin', { method: 'DELETE', token, body: JSON.stringify(data) }),
@@ -100,7 +121,7 @@ export const galleryAPI = {
 
 // Events API
 export const eventsAPI = {
-  getAll: () => apiFetch('/events', {}),
+  getAll: () => apiFetchAll('/events', {}),
   create: (token: string, data: any) => apiFetch('/events/admin', { method: 'POST', token, body: JSON.stringify(data) }),
   update: (token: string, data: any) => apiFetch('/events/admin', { method: 'PATCH', token, body: JSON.stringify(data) }),
   delete: (token: string, data: { id: string }) => apiFetch('/events/admin', { method: 'DELETE', token, body: JSON.stringify(data) }),
@@ -115,7 +136,7 @@ export const statsAPI = {
 
 // Blogs API
 export const blogsAPI = {
-  getAll: () => apiFetch('/blogs', {}),
+  getAll: () => apiFetchAll('/blogs', {}),
   getBySlug: (slug: string) => apiFetch(`/blogs/${slug}`, {}),
   create: (token: string, data: { title: string; description: string; content: string; image_url?: string; category?: string }) =>
     apiFetch('/blogs/create', { method: 'POST', token, body: JSON.stringify(data) }),
@@ -0,0 +1,39 @@
+// Events carry UTC instants plus the IANA zone they happen in; show them in
+// that zone so "2:00 PM" means the same thing to every visitor.
+
+export function formatEventDate(iso: string, timeZone?: string) {
+  return new Date(iso).toLocaleDateString('en-US', {
+    month: 'long',
+    day: 'numeric',
+    year: 'numeric',
+    timeZone,
+  });
+}
+
+export function formatEventTime(startsAt: string, endsAt?: string, timeZone?: string) {
+  const time = (iso: string) =>
+    new Date(iso).toLocaleTimeString('en-US', { hour: 'numeric', minute: '2-digit', timeZone });
+  const zone = new Date(startsAt)
+    .toLocaleTimeString('en-US', { timeZone, timeZoneName: 'short' })
+    .split(' ')
+    .pop();
+  return endsAt ? `${time(startsAt)} - ${time(endsAt)} ${zone}` : `${time(startsAt)} ${zone}`;
+}
+
+// "2025-03-14T14:00" for a datetime-local input, as wall-clock time in `timeZone`
+export function toLocalInput(iso: string, timeZone: string) {
+  const parts = Object.fromEntries(
+    new Intl.DateTimeFormat('en-CA', {
+      timeZone,
+      year: 'numeric',
+      month: '2-digit',
+      day: '2-digit',
+      hour: '2-digit',
+      minute: '2-digit',
+      hourCycle: 'h23',
+    })
+      .formatToParts(new Date(iso))
+      .map((part) => [part.type, part.value])
+  );
+  return `${parts.year}-${parts.month}-${parts.day}T${parts.hour}:${parts.minute}`;
+}
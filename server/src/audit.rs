@@ -0,0 +1,42 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::db::AppState;
+use crate::error::AppResult;
+use crate::models::AuditEntry;
+
+// Append to the audit log. Failures are logged but never fail the audited action.
+pub async fn record(
+    state: &AppState,
+    actor_id: ObjectId,
+    action: &str,
+    target_id: Option<ObjectId>,
+    details: serde_json::Value,
+    user_agent: Option<String>,
+) {
+    if let Err(e) = record_or_fail(state, actor_id, action, target_id, details, user_agent).await {
+        tracing::error!(action, error = ?e, "failed to write audit entry");
+    }
+}
+
+// For actions that mustn't happen unaudited: write the entry first and stop if it can't be
+pub async fn record_or_fail(
+    state: &AppState,
+    actor_id: ObjectId,
+    action: &str,
+    target_id: Option<ObjectId>,
+    details: serde_json::Value,
+    user_agent: Option<String>,
+) -> AppResult<()> {
+    let entry = AuditEntry {
+        id: None,
+        action: action.to_string(),
+        actor_id,
+        target_id,
+        details,
+        user_agent,
+        created_at: DateTime::now(),
+    };
+
+    state.audit_log.insert(&entry).await?;
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::audit;
+use crate::auth::sessions::{generate_token, hash_token};
+use crate::auth::user_agent;
+use crate::db::AppState;
+use crate::error::{found, parse_id, AppError, AppResult, ErrorBody};
+use crate::middleware::auth::AuthUser;
+use crate::models::user::Permission;
+use crate::models::{AccessToken, AuditEntry};
+use crate::repo::{AuditFilter, RepoResult};
+use crate::validate::{not_blank, ValidJson};
+
+// Lets the auth middleware (and secret scanners) tell access tokens from JWTs
+pub const TOKEN_PREFIX: &str = "iris_pat_";
+
+const DEFAULT_EXPIRY_DAYS: i64 = 30;
+const MAX_EXPIRY_DAYS: i64 = 365;
+const MAX_NAME_LENGTH: u64 = 100;
+// `last_used_at` is written at most this often per token
+const TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateAccessTokenRequest {
+    #[validate(length(max = MAX_NAME_LENGTH), custom(function = "not_blank"))]
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<Permission>,        // e.g. ["coins:grant"]; empty means member access only
+    #[validate(range(min = 1, max = MAX_EXPIRY_DAYS))]
+    pub expires_in_days: Option<i64>,   // Default 30, at most 365
+}
+
+fn token_json(token: &AccessToken) -> serde_json::Value {
+    let now = DateTime::now();
+    let status = if token.revoked_at.is_some() {
+        "revoked"
+    } else if token.expires_at <= now {
+        "expired"
+    } else {
+        "active"
+    };
+
+    serde_json::json!({
+        "id": token.id.map(|id| id.to_hex()),
+        "name": token.name,
+        "token_prefix": token.token_prefix,
+        "scopes": token.scopes,
+        "status": status,
+        "created_at": token.created_at.try_to_rfc3339_string().ok(),
+        "expires_at": token.expires_at.try_to_rfc3339_string().ok(),
+        "last_used_at": token.last_used_at.and_then(|at| at.try_to_rfc3339_string().ok()),
+        "revoked_at": token.revoked_at.and_then(|at| at.try_to_rfc3339_string().ok()),
+    })
+}
+
+// The unrevoked, unexpired token behind a presented `iris_pat_...` secret
+pub(crate) async fn find_active(state: &AppState, token: &str) -> RepoResult<Option<AccessToken>> {
+    state.access_tokens.find_active(&hash_token(token)).await
+}
+
+// Record that a token was used, skipping the write if it was recorded moments ago
+pub(crate) async fn touch(state: &AppState, token_id: ObjectId) {
+    let now = chrono::Utc::now();
+    let recent = DateTime::from_millis((now - chrono::Duration::seconds(TOUCH_INTERVAL_SECONDS)).timestamp_millis());
+    let result = state.access_tokens.touch(token_id, DateTime::from_millis(now.timestamp_millis()), recent).await;
+    if let Err(e) = result {
+        tracing::warn!(error = ?e, "failed to update access token last use");
+    }
+}
+
+// Revoke every token of a user, e.g. when an admin locks a compromised account out
+pub async fn revoke_user_tokens(state: &AppState, user_id: ObjectId) -> RepoResult<u64> {
+    state.access_tokens.revoke_all(user_id).await
+}
+
+// GET /api/v1/auth/tokens - Authenticated: the caller's personal access tokens, newest first
+#[utoipa::path(
+    get, path = "/api/v1/auth/tokens", tag = "access-tokens", security(("bearer" = [])),
+    responses(
+        (status = 200, body = Object, example = json!([
+            {
+                "id": "507f1f77bcf86cd799439011",
+                "name": "leaderboard bot",
+                "token_prefix": "iris_pat_3f9a",
+                "scopes": ["coins:grant"],
+                "status": "active",
+                "created_at": "2025-11-16T14:30:00Z",
+                "expires_at": "2025-12-16T14:30:00Z",
+                "last_used_at": null,
+                "revoked_at": null
+            }
+        ]))
+    )
+)]
+pub async fn get_access_tokens(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<serde_json::Value>>> {
+    let tokens = state.access_tokens.list_for_user(auth_user.id).await?;
+
+    Ok(Json(tokens.iter().map(token_json).collect()))
+}
+
+// POST /api/v1/auth/tokens - Authenticated: create a token. The secret is only returned here.
+#[utoipa::path(
+    post, path = "/api/v1/auth/tokens", tag = "access-tokens", security(("bearer" = [])),
+    request_body = CreateAccessTokenRequest,
+    responses(
+        (status = 201, description = "`token` is shown only this once", body = Object, example = json!({
+            "success": true,
+            "token": "iris_pat_3f9a...",
+            "access_token": {
+                "id": "507f1f77bcf86cd799439011",
+                "name": "leaderboard bot",
+                "token_prefix": "iris_pat_3f9a",
+                "scopes": ["coins:grant"],
+                "status": "active",
+                "created_at": "2025-11-16T14:30:00Z",
+                "expires_at": "2025-12-16T14:30:00Z",
+                "last_used_at": null,
+                "revoked_at": null
+            }
+        })),
+        (status = 403, description = "A scope the caller doesn't hold", body = ErrorBody)
+    )
+)]
+pub async fn create_access_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateAccessTokenRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    auth_user.require_owner()?;
+
+    let name = payload.name.trim();
+    let expires_in_days = payload.expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS);
+
+    // A token can't carry more than its owner could do in a session
+    if let Some(scope) = payload.scopes.iter().find(|scope| !auth_user.can(**scope)) {
+        return Err(AppError::Forbidden("Cannot grant a scope you don't have".to_string())
+            .with_code("scope_not_allowed")
+            .with_details(serde_json::json!({ "scope": scope })));
+    }
+
+    let mut scopes: Vec<Permission> = Vec::new();
+    for scope in payload.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+    let now = chrono::Utc::now();
+    let mut access_token = AccessToken {
+        id: None,
+        user_id: auth_user.id,
+        name: name.to_string(),
+        token_hash: hash_token(&token),
+        token_prefix: token[..TOKEN_PREFIX.len() + 8].to_string(),
+        scopes,
+        two_factor_verified: auth_user.two_factor_verified,
+        created_at: DateTime::from_millis(now.timestamp_millis()),
+        expires_at: DateTime::from_millis((now + chrono::Duration::days(expires_in_days)).timestamp_millis()),
+        last_used_at: None,
+        revoked_at: None,
+    };
+
+    let token_id = state.access_tokens.insert(&access_token).await?;
+    access_token.id = Some(token_id);
+
+    audit::record(
+        &state,
+        auth_user.id,
+        "access_token.create",
+        Some(token_id),
+        serde_json::json!({
+            "name": access_token.name,
+            "scopes": access_token.scopes,
+            "expires_at": access_token.expires_at.try_to_rfc3339_string().ok(),
+        }),
+        user_agent(&headers),
+    ).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "success": true,
+            "token": token,
+            "access_token": token_json(&access_token)
+        }))
+    ))
+}
+
+// DELETE /api/v1/auth/tokens/{id} - Authenticated: revoke one of the caller's tokens
+#[utoipa::path(
+    delete, path = "/api/v1/auth/tokens/{id}", tag = "access-tokens", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "Access token revoked"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn revoke_access_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+    let token_id = parse_id(&id, "token ID")?;
+
+    if !state.access_tokens.revoke(token_id, auth_user.id).await? {
+        return Err(AppError::NotFound("Access token not found".to_string()));
+    }
+
+    audit::record(&state, auth_user.id, "access_token.revoke", Some(token_id), serde_json::json!({}), user_agent(&headers)).await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Access token revoked"
+    })))
+}
+
+// GET /api/v1/auth/tokens/{id}/activity - Authenticated: audit trail of one of the caller's tokens
+#[utoipa::path(
+    get, path = "/api/v1/auth/tokens/{id}/activity", tag = "access-tokens", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses((status = 200, body = Vec<AuditEntry>), (status = 404, body = ErrorBody))
+)]
+pub async fn get_access_token_activity(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<Json<Vec<AuditEntry>>> {
+    let token_id = parse_id(&id, "token ID")?;
+    found(state.access_tokens.get_for_user(token_id, auth_user.id).await?, "Access token")?;
+
+    let filter = AuditFilter { target_id: Some(token_id), ..Default::default() };
+    Ok(Json(state.audit_log.list(&filter, 100).await?))
+}
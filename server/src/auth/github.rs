@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+// Provider name used for GitHub in `User::linked_identities`
+pub const PROVIDER: &str = "github";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitHubUser {
+    pub login: String,
+    pub id: u64,
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GitHubEmail {
+    pub email: String,
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Membership {
+    state: String, // "active" or "pending"
+}
+
+// The GitHub REST calls the login flow needs, called with the user's OAuth token
+#[async_trait]
+pub trait GitHubApi: Debug + Send + Sync {
+    async fn user(&self, token: &str) -> Result<GitHubUser, String>;
+    async fn emails(&self, token: &str) -> Result<Vec<GitHubEmail>, String>;
+    // Public profile of `login`, None if no such account (used by the identity backfill)
+    async fn user_by_login(&self, login: &str) -> Result<Option<GitHubUser>, String>;
+    // Active membership of the token owner in `org` (needs the read:org scope)
+    async fn is_org_member(&self, token: &str, org: &str) -> Result<bool, String>;
+    // Active membership of `login` in `org/team_slug` (needs the read:org scope)
+    async fn is_team_member(&self, token: &str, org: &str, team_slug: &str, login: &str) -> Result<bool, String>;
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpGitHubApi {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl HttpGitHubApi {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpGitHubApi {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn get(&self, token: Option<&str>, path: &str) -> Result<reqwest::Response, String> {
+        let mut request = self.client
+            .get(format!("{}{}", self.base_url, path))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "iris-server");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.send().await.map_err(|e| e.to_string())
+    }
+
+    async fn membership_active(&self, token: &str, path: &str) -> Result<bool, String> {
+        let response = self.get(Some(token), path).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => Ok(false),
+            status if status.is_success() => {
+                let membership: Membership = response.json().await.map_err(|e| e.to_string())?;
+                Ok(membership.state == "active")
+            }
+            status => Err(format!("GitHub returned {} for {}", status, path)),
+        }
+    }
+}
+
+#[async_trait]
+impl GitHubApi for HttpGitHubApi {
+    async fn user(&self, token: &str) -> Result<GitHubUser, String> {
+        self.get(Some(token), "/user")
+            .await?
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn emails(&self, token: &str) -> Result<Vec<GitHubEmail>, String> {
+        self.get(Some(token), "/user/emails")
+            .await?
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn user_by_login(&self, login: &str) -> Result<Option<GitHubUser>, String> {
+        let response = self.get(None, &format!("/users/{}", login)).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        response
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    async fn is_org_member(&self, token: &str, org: &str) -> Result<bool, String> {
+        self.membership_active(token, &format!("/user/memberships/orgs/{}", org)).await
+    }
+
+    async fn is_team_member(&self, token: &str, org: &str, team_slug: &str, login: &str) -> Result<bool, String> {
+        self.membership_active(token, &format!("/orgs/{}/teams/{}/memberships/{}", org, team_slug, login))
+            .await
+    }
+}
@@ -0,0 +1,121 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+
+use crate::db::AppState;
+use crate::error::{found, AppError, AppResult, ErrorBody};
+use crate::middleware::auth::AuthUser;
+use crate::models::{LinkedIdentity, User};
+use crate::repo::{RepoError, RepoResult};
+
+#[derive(Debug)]
+pub enum LinkError {
+    LinkedToOtherUser,      // The external account already signs in as someone else
+    ProviderAlreadyLinked,  // This user already has a different account of the provider linked
+    UserNotFound,
+    Database(String),
+}
+
+impl From<LinkError> for AppError {
+    fn from(error: LinkError) -> Self {
+        match error {
+            LinkError::LinkedToOtherUser => AppError::Conflict("This account is already linked to another user".to_string())
+                .with_code("identity_linked_to_other_user"),
+            LinkError::ProviderAlreadyLinked => AppError::Conflict("Another account of this provider is already linked, unlink it first".to_string())
+                .with_code("provider_already_linked"),
+            LinkError::UserNotFound => AppError::NotFound("User not found".to_string()),
+            LinkError::Database(e) => AppError::Internal(format!("Failed to link identity: {}", e)),
+        }
+    }
+}
+
+pub async fn find_by_identity(state: &AppState, provider: &str, subject: &str) -> RepoResult<Option<User>> {
+    state.users.find_by_identity(&LinkedIdentity::key(provider, subject)).await
+}
+
+// Attach an identity to a user; one account per provider and user
+pub async fn link_identity(state: &AppState, user_id: ObjectId, identity: LinkedIdentity) -> Result<(), LinkError> {
+    let already_ours = find_by_identity(state, &identity.provider, &identity.subject)
+        .await
+        .map_err(|e| LinkError::Database(e.to_string()))?;
+    if let Some(owner) = already_ours {
+        return if owner.id == Some(user_id) { Ok(()) } else { Err(LinkError::LinkedToOtherUser) };
+    }
+
+    match state.users.link_identity(user_id, &identity).await {
+        Ok(true) => Ok(()),
+        Ok(false) => match state.users.get(user_id).await {
+            Ok(Some(_)) => Err(LinkError::ProviderAlreadyLinked),
+            Ok(None) => Err(LinkError::UserNotFound),
+            Err(e) => Err(LinkError::Database(e.to_string())),
+        },
+        // Lost a race against another user linking the same account
+        Err(RepoError::Duplicate) => Err(LinkError::LinkedToOtherUser),
+        Err(e) => Err(LinkError::Database(e.to_string())),
+    }
+}
+
+// GET /api/v1/auth/identities - Protected: accounts linked to the current user
+#[utoipa::path(
+    get, path = "/api/v1/auth/identities", tag = "auth", security(("bearer" = [])),
+    responses(
+        (status = 200, body = Object, example = json!({
+            "identities": [
+                {
+                    "key": "github:583231",
+                    "provider": "github",
+                    "subject": "583231",
+                    "username": "johndoe"
+                }
+            ],
+            "has_password": true
+        }))
+    )
+)]
+pub async fn get_identities(State(state): State<AppState>, auth_user: AuthUser) -> AppResult<Json<serde_json::Value>> {
+    let user = found(state.users.get(auth_user.id).await?, "User")?;
+
+    Ok(Json(serde_json::json!({
+        "identities": user.linked_identities,
+        "has_password": !user.password_hash.is_empty()
+    })))
+}
+
+// DELETE /api/v1/auth/identities/{provider} - Protected: unlink a provider, keeping at least one way to sign in
+#[utoipa::path(
+    delete, path = "/api/v1/auth/identities/{provider}", tag = "auth", security(("bearer" = [])),
+    params(("provider" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "Identity unlinked"})),
+        (status = 404, body = ErrorBody),
+        (status = 409, description = "It is the only way left to sign in", body = ErrorBody)
+    )
+)]
+pub async fn unlink_identity(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(provider): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+
+    let user = found(state.users.get(auth_user.id).await?, "User")?;
+
+    if !user.linked_identities.iter().any(|identity| identity.provider == provider) {
+        return Err(AppError::NotFound("No account of this provider is linked".to_string()));
+    }
+
+    if user.password_hash.is_empty() && user.linked_identities.len() == 1 {
+        return Err(AppError::Conflict(
+            "Cannot unlink the only way to sign in, set a password or link another provider first".to_string(),
+        ).with_code("last_sign_in_method"));
+    }
+
+    state.users.unlink_identity(auth_user.id, &provider).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Identity unlinked"
+    })))
+}
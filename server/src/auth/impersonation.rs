@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::audit;
+use crate::auth::sessions::{generate_token, hash_token};
+use crate::auth::user_agent;
+use crate::db::AppState;
+use crate::error::{found, parse_id, AppError, AppResult};
+use crate::middleware::auth::{create_impersonation_jwt, Actor, AuthUser};
+use crate::models::{AccountStatus, Role, Session};
+use crate::validate::{not_blank, ValidJson};
+
+// Long enough to reproduce a problem, short enough that a leaked token is near useless
+const IMPERSONATION_TTL_MINUTES: i64 = 10;
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImpersonateRequest {
+    #[validate(length(max = 500), custom(function = "not_blank"))]
+    pub reason: String, // Support ticket or explanation, stored in the audit log
+}
+
+// POST /api/v1/users/{id}/impersonate - Admin: act as a user for support. Audited, short-lived, no refresh token.
+#[utoipa::path(
+    post, path = "/api/v1/users/{id}/impersonate", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = ImpersonateRequest,
+    responses(
+        (status = 200, description = "A short-lived access token acting as the user, without a refresh token", body = Object, example = json!({
+            "success": true,
+            "token": "eyJhbGciOiJIUzI1NiJ9...",
+            "expires_in": 600,
+            "impersonation": {
+                "session_id": "507f1f77bcf86cd799439012",
+                "admin_id": "507f1f77bcf86cd799439013",
+                "admin_username": "admin",
+                "user": {}
+            }
+        }))
+    )
+)]
+pub async fn impersonate_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Path(user_id): Path<String>,
+    ValidJson(payload): ValidJson<ImpersonateRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+
+    let reason = payload.reason.trim();
+
+    let user_id = parse_id(&user_id, "user ID")?;
+    if user_id == auth_user.id {
+        return Err(AppError::BadRequest("Cannot impersonate yourself".to_string()));
+    }
+
+    let user = found(state.users.get(user_id).await?, "User")?;
+
+    // Acting as another admin would hand out their permissions without their 2FA
+    if user.role == Role::Admin {
+        return Err(AppError::Forbidden("Admins cannot be impersonated".to_string()));
+    }
+    if user.status != AccountStatus::Active {
+        return Err(AppError::BadRequest("Only active accounts can be impersonated".to_string()));
+    }
+
+    let now = chrono::Utc::now();
+    let expires_at = DateTime::from_millis(
+        (now + chrono::Duration::minutes(IMPERSONATION_TTL_MINUTES)).timestamp_millis(),
+    );
+
+    // Audited before the session exists, so there's no impersonation without an entry
+    let session_id = ObjectId::new();
+    audit::record_or_fail(
+        &state,
+        auth_user.id,
+        "user.impersonate",
+        Some(user_id),
+        serde_json::json!({
+            "reason": reason,
+            "session_id": session_id.to_hex(),
+            "expires_at": expires_at.try_to_rfc3339_string().ok(),
+        }),
+        user_agent(&headers),
+    ).await?;
+
+    let session = Session {
+        id: Some(session_id),
+        user_id,
+        // Never handed out: impersonation sessions can't be refreshed
+        refresh_token_hash: hash_token(&generate_token()),
+        previous_token_hash: None,
+        user_agent: user_agent(&headers),
+        two_factor_verified: false,
+        impersonator_id: Some(auth_user.id),
+        created_at: DateTime::now(),
+        last_used_at: DateTime::now(),
+        expires_at,
+        revoked_at: None,
+    };
+
+    state.sessions.insert(&session).await?;
+
+    let actor = Actor {
+        sub: auth_user.id.to_hex(),
+        username: auth_user.username.clone(),
+    };
+    let token = create_impersonation_jwt(
+        &state.config.auth.jwt_secret,
+        user_id,
+        &user,
+        session_id,
+        IMPERSONATION_TTL_MINUTES,
+        actor,
+    )
+    .map_err(|e| AppError::Internal(format!("Failed to create impersonation JWT: {:?}", e)))?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "token": token,
+        "expires_in": IMPERSONATION_TTL_MINUTES * 60,
+        "impersonation": {
+            "session_id": session_id.to_hex(),
+            "admin_id": auth_user.id.to_hex(),
+            "admin_username": auth_user.username,
+            "user": {
+                "id": user_id.to_hex(),
+                "username": user.username,
+                "full_name": user.full_name,
+                "role": format!("{:?}", user.role),
+            }
+        }
+    })))
+}
@@ -1,209 +1,189 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Redirect, Response},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     Json,
 };
 use oauth2::{
-    AuthorizationCode, AuthUrl, ClientId, ClientSecret, CsrfToken, RedirectUrl,
-    Scope, TokenResponse, TokenUrl,
+    AuthorizationCode, AuthUrl, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse, TokenUrl,
     basic::BasicClient,
     reqwest::async_http_client,
 };
-use serde::{Deserialize, Serialize};
-use std::env;
+use serde::Deserialize;
+use utoipa::IntoParams;
 
+use mongodb::bson::oid::ObjectId;
+
+use crate::config::GitHubConfig;
 use crate::db::AppState;
-use crate::models::user::{User, Role};
-use crate::middleware::create_jwt;
+use crate::error::{found, AppError, AppResult};
+use crate::openapi::SignIn;
+use crate::middleware::auth::AuthUser;
+use crate::models::user::{AccountStatus, LinkedIdentity, User, Role};
+use crate::models::OAuthState;
+use crate::repo::RepoError;
+
+pub mod sessions;
+pub mod password;
+pub mod github;
+pub mod policy;
+pub mod identities;
+pub mod oidc;
+pub mod two_factor;
+pub mod impersonation;
+pub mod access_tokens;
+
+use identities::{find_by_identity, link_identity};
+use sessions::{issue_session, TokenPair};
+
+// How long a user has to complete the provider's consent screen
+const OAUTH_STATE_TTL_MINUTES: i64 = 10;
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AuthRequest {
     pub code: String,
     pub state: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitHubUser {
-    pub login: String,
-    pub id: u64,
-    pub email: Option<String>,
-    pub name: Option<String>,
-}
-
-fn get_oauth_client() -> BasicClient {
-    let github_client_id = env::var("GITHUB_CLIENT_ID")
-        .unwrap_or_else(|_| "your_github_client_id".to_string());
-    let github_client_secret = env::var("GITHUB_CLIENT_SECRET")
-        .unwrap_or_else(|_| "your_github_client_secret".to_string());
-    let redirect_url = env::var("GITHUB_REDIRECT_URL")
-        .unwrap_or_else(|_| "http://localhost:5657/auth/github/callback".to_string());
-
-    BasicClient::new(
-        ClientId::new(github_client_id),
-        Some(ClientSecret::new(github_client_secret)),
-        AuthUrl::new("https://github.com/login/oauth/authorize".to_string()).unwrap(),
-        Some(TokenUrl::new("https://github.com/login/oauth/access_token".to_string()).unwrap()),
+fn get_oauth_client(config: &GitHubConfig) -> AppResult<BasicClient> {
+    let invalid_url = |e: oauth2::url::ParseError| AppError::Internal(format!("Invalid GitHub OAuth URL: {}", e));
+    Ok(BasicClient::new(
+        ClientId::new(config.client_id.clone()),
+        Some(ClientSecret::new(config.client_secret.clone())),
+        AuthUrl::new("https://github.com/login/oauth/authorize".to_string()).map_err(invalid_url)?,
+        Some(TokenUrl::new("https://github.com/login/oauth/access_token".to_string()).map_err(invalid_url)?),
     )
-    .set_redirect_uri(RedirectUrl::new(redirect_url).unwrap())
+    .set_redirect_uri(RedirectUrl::new(config.redirect_url.clone()).map_err(invalid_url)?))
 }
 
-pub async fn github_login() -> impl IntoResponse {
-    let client = get_oauth_client();
-    
-    let (auth_url, _csrf_token) = client
-        .authorize_url(CsrfToken::new_random)
-        .add_scope(Scope::new("user:email".to_string()))
-        .url();
+// Remember a login attempt so the callback can prove it started it
+pub(crate) async fn store_oauth_state(
+    state: &AppState,
+    provider: &str,
+    csrf_token: &CsrfToken,
+    pkce_verifier: &PkceCodeVerifier,
+    nonce: Option<String>,
+    link_user_id: Option<ObjectId>,
+) -> AppResult<()> {
+    let now = chrono::Utc::now();
+    let pending = OAuthState {
+        id: None,
+        state: csrf_token.secret().clone(),
+        provider: provider.to_string(),
+        pkce_verifier: pkce_verifier.secret().clone(),
+        nonce,
+        link_user_id,
+        created_at: mongodb::bson::DateTime::from_millis(now.timestamp_millis()),
+        expires_at: mongodb::bson::DateTime::from_millis(
+            (now + chrono::Duration::minutes(OAUTH_STATE_TTL_MINUTES)).timestamp_millis(),
+        ),
+    };
 
-    Redirect::to(auth_url.as_str())
+    state.oauth_states.insert(&pending).await?;
+    Ok(())
 }
 
-pub async fn github_callback(
-    Query(query): Query<AuthRequest>,
-    State(state): State<AppState>,
-) -> Response {
-    let client = get_oauth_client();
+fn invalid_oauth_state(message: &str) -> AppError {
+    AppError::BadRequest(format!("{}, please start the login again", message)).with_code("invalid_oauth_state")
+}
 
-    let token_result = client
-        .exchange_code(AuthorizationCode::new(query.code))
-        .request_async(async_http_client)
-        .await;
-
-    let token = match token_result {
-        Ok(token) => token,
-        Err(e) => {
-            eprintln!("Failed to exchange code: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to exchange authorization code"
-                }))
-            ).into_response();
-        }
-    };
+// Consume the pending state; deleting it makes every state single-use
+pub(crate) async fn consume_oauth_state(state: &AppState, provider: &str, state_param: &str) -> AppResult<OAuthState> {
+    let pending = state.oauth_states
+        .take(state_param)
+        .await?
+        .filter(|pending| pending.provider == provider)
+        .ok_or_else(|| invalid_oauth_state("Invalid or already used OAuth state"))?;
 
-    let client = reqwest::Client::new();
-    let user_info: GitHubUser = match client
-        .get("https://api.github.com/user")
-        .header("Authorization", format!("Bearer {}", token.access_token().secret()))
-        .header("User-Agent", "iris-server")
-        .send()
-        .await
-    {
-        Ok(response) => match response.json().await {
-            Ok(info) => info,
-            Err(e) => {
-                eprintln!("Failed to parse user info: {:?}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": "Failed to parse GitHub user info"
-                    }))
-                ).into_response();
-            }
-        },
-        Err(e) => {
-            eprintln!("Failed to fetch user info: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to fetch GitHub user info"
-                }))
-            ).into_response();
-        }
-    };
+    // The TTL monitor only runs once a minute, so check expiry ourselves too
+    if pending.expires_at < mongodb::bson::DateTime::now() {
+        return Err(invalid_oauth_state("OAuth state expired"));
+    }
 
-    let email = if user_info.email.is_none() {
-        let emails: Vec<serde_json::Value> = match client
-            .get("https://api.github.com/user/emails")
-            .header("Authorization", format!("Bearer {}", token.access_token().secret()))
-            .header("User-Agent", "iris-server")
-            .send()
-            .await
-        {
-            Ok(response) => response.json().await.unwrap_or_default(),
-            Err(_) => Vec::new(),
-        };
+    Ok(pending)
+}
 
-        emails
-            .iter()
-            .find(|e| e["primary"].as_bool().unwrap_or(false))
-            .and_then(|e| e["email"].as_str().map(String::from))
-            .or_else(|| emails.first().and_then(|e| e["email"].as_str().map(String::from)))
-    } else {
-        user_info.email
-    };
+// Store a fresh OAuth state and build the GitHub consent URL for it
+async fn start_github_oauth(state: &AppState, link_user_id: Option<ObjectId>) -> AppResult<String> {
+    let client = get_oauth_client(&state.config.github)?;
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
 
-    let email = email.unwrap_or_else(|| format!("{}@github.com", user_info.login));
+    let mut request = client
+        .authorize_url(CsrfToken::new_random)
+        .add_scope(Scope::new("user:email".to_string()))
+        .set_pkce_challenge(pkce_challenge);
+    if state.signup_policy.needs_org_scope() {
+        request = request.add_scope(Scope::new("read:org".to_string()));
+    }
+    let (auth_url, csrf_token) = request.url();
 
-    let existing_user = state.users
-        .find_one(mongodb::bson::doc! { "email": &email })
-        .await;
+    store_oauth_state(state, github::PROVIDER, &csrf_token, &pkce_verifier, None, link_user_id).await?;
+    Ok(auth_url.to_string())
+}
 
-    let user = match existing_user {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            let new_user = User {
-                id: None,
-                username: user_info.login.clone(),
-                full_name: user_info.name.unwrap_or_else(|| user_info.login.clone()),
-                email: email.clone(),
-                password_hash: String::new(),
-                role: Role::Member,
-                coins: 0,
-                project_ids: Some(Vec::new()),
-                created_at: chrono::Utc::now().to_rfc3339(),
-                updated_at: chrono::Utc::now().to_rfc3339(),
-            };
+// GET /api/v1/auth/github - Public: redirect to GitHub's consent screen
+#[utoipa::path(
+    get, path = "/api/v1/auth/github", tag = "auth",
+    responses((status = 303, description = "Redirect to the provider's sign-in page"))
+)]
+pub async fn github_login(State(state): State<AppState>) -> AppResult<Response> {
+    let auth_url = start_github_oauth(&state, None).await?;
+    Ok(Redirect::to(&auth_url).into_response())
+}
 
-            match state.users.insert_one(&new_user).await {
-                Ok(result) => {
-                    let mut created_user = new_user;
-                    created_user.id = Some(result.inserted_id.as_object_id().unwrap());
-                    created_user
-                }
-                Err(e) => {
-                    eprintln!("Failed to create user: {:?}", e);
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({
-                            "error": "Failed to create user"
-                        }))
-                    ).into_response();
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Database error: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Database error"
-                }))
-            ).into_response();
-        }
-    };
+// POST /api/v1/auth/link/{provider} - Protected: start linking a GitHub or OIDC account to the current user.
+// The frontend sends the browser to `auth_url`; the usual callback then links instead of signing in.
+#[utoipa::path(
+    post, path = "/api/v1/auth/link/{provider}", tag = "auth", security(("bearer" = [])),
+    params(("provider" = String, Path, description = "`github` or a configured OIDC provider")),
+    responses(
+        (status = 200, description = "Send the browser here", body = Object, example = json!({"auth_url": "https://github.com/login/oauth/authorize?..."}))
+    )
+)]
+pub async fn link_provider(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    auth_user: AuthUser,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
 
-    let jwt_token = match create_jwt(
-        &user.id.unwrap().to_hex(),
-        &user.username,
-        &user.email,
-        &user.role,
-    ) {
-        Ok(token) => token,
-        Err(e) => {
-            eprintln!("Failed to create JWT: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to create authentication token"
-                }))
-            ).into_response();
-        }
+    let auth_url = if provider == github::PROVIDER {
+        start_github_oauth(&state, Some(auth_user.id)).await?
+    } else if let Some(oidc_provider) = state.oidc.get(&provider) {
+        oidc::start_oidc(&state, &oidc_provider, Some(auth_user.id)).await?
+    } else {
+        return Err(oidc::unknown_provider());
     };
 
+    Ok(Json(serde_json::json!({ "auth_url": auth_url })))
+}
+
+// GET /api/v1/auth/providers - Public: sign-in options for the login page
+#[utoipa::path(
+    get, path = "/api/v1/auth/providers", tag = "auth",
+    responses(
+        (status = 200, body = Object, example = json!([{"name": "github", "display_name": "GitHub", "login_url": "/api/v1/auth/github"}]))
+    )
+)]
+pub async fn get_providers(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let mut providers = vec![serde_json::json!({
+        "name": github::PROVIDER,
+        "display_name": "GitHub",
+        "login_url": "/api/v1/auth/github"
+    })];
+    providers.extend(state.oidc.providers().map(|provider| serde_json::json!({
+        "name": provider.name,
+        "display_name": provider.display_name,
+        "login_url": format!("/api/v1/auth/{}", provider.name)
+    })));
+
+    Json(serde_json::json!({ "providers": providers }))
+}
+
+// Body shared by every endpoint that logs a user in
+pub(crate) fn login_response(user: &User, tokens: TokenPair) -> Response {
     (
         StatusCode::OK,
         Json(serde_json::json!({
@@ -215,80 +195,251 @@ pub async fn github_callback(
                 "full_name": user.full_name,
                 "email": user.email,
                 "role": format!("{:?}", user.role),
+                "permissions": user.role.permissions(),
                 "coins": user.coins,
             },
-            "token": jwt_token,
+            "token": tokens.token,
+            "refresh_token": tokens.refresh_token,
+            "expires_in": tokens.expires_in,
         }))
     ).into_response()
 }
 
-// Test login endpoint (for development only)
-#[derive(Debug, Deserialize)]
-pub struct TestLoginRequest {
-    pub user_id: String,
+// Never merge into another account by email: its owner has to link the provider themselves
+fn email_taken() -> AppError {
+    AppError::Conflict(
+        "An account with this email already exists, sign in to it and link this provider from your account settings".to_string(),
+    ).with_code("email_taken")
 }
 
-pub async fn test_login(
-    State(state): State<AppState>,
-    Json(payload): Json<TestLoginRequest>,
-) -> Response {
-    use mongodb::bson::oid::ObjectId;
-    use mongodb::bson::doc;
-
-    let user_id = match ObjectId::parse_str(&payload.user_id) {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": "Invalid user ID format"
-                }))
-            ).into_response();
+// Returned instead of tokens while an admin still has to approve the account
+pub(crate) fn pending_approval() -> AppError {
+    AppError::Forbidden("An admin has to approve your account before you can sign in".to_string())
+        .with_code("pending_approval")
+}
+
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+// What an external provider told us about the person signing in
+pub(crate) struct ExternalProfile {
+    pub identity: LinkedIdentity,
+    pub username: String,
+    pub full_name: String,
+    pub email: String,                  // Verified address, or a placeholder if the provider has none
+    pub verified_emails: Vec<String>,   // Lowercase
+}
+
+// How the signup policy decides on new and pending accounts
+pub(crate) enum Admission<'a> {
+    GitHub { token: &'a str, login: &'a str },
+    VerifiedEmail,
+}
+
+// Shared tail of every external callback: link, find or create the user, apply
+// the signup policy and issue a session
+pub(crate) async fn finish_external_login(
+    state: &AppState,
+    headers: &HeaderMap,
+    link_user_id: Option<ObjectId>,
+    profile: ExternalProfile,
+    admission: Admission<'_>,
+) -> AppResult<Response> {
+    let ExternalProfile { identity, username, full_name, email, verified_emails } = profile;
+
+    // Linking flow: attach the external account to the user who started it
+    if let Some(link_user_id) = link_user_id {
+        link_identity(state, link_user_id, identity).await?;
+        let user = found(state.users.get(link_user_id).await?, "User")?;
+        return issue_session(state, &user, user_agent(headers)).await;
+    }
+
+    let existing_user = match find_by_identity(state, &identity.provider, &identity.subject).await? {
+        Some(user) => Some(user),
+        // GitHub accounts created before identities were stored that the backfill
+        // couldn't resolve: claim the one whose email GitHub has verified for this login.
+        // Accounts with no identity for any other reason are never claimed by email.
+        None if identity.provider == github::PROVIDER && !verified_emails.is_empty() => {
+            state.users.find_unlinked_github_account(&verified_emails).await?
         }
+        None => None,
     };
 
-    let user = match state.users.find_one(doc! { "_id": user_id }).await {
-        Ok(Some(user)) => user,
-        _ => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({
-                    "error": "User not found"
-                }))
-            ).into_response();
-        }
+    let unlinked_user = existing_user
+        .as_ref()
+        .filter(|user| !user.linked_identities.iter().any(|linked| linked.key == identity.key));
+    if let Some(user_id) = unlinked_user.and_then(|user| user.id) {
+        link_identity(state, user_id, identity.clone()).await?;
+    }
+
+    if existing_user.is_none() && state.users.find_by_email(&email).await?.is_some() {
+        return Err(email_taken());
+    }
+
+    // New and still-pending accounts have to pass the signup policy
+    let needs_check = existing_user
+        .as_ref()
+        .is_none_or(|user| user.status == AccountStatus::PendingApproval);
+    let admitted = if needs_check {
+        let admitted = match admission {
+            Admission::GitHub { token, login } => {
+                state.signup_policy
+                    .admits_github(state.invites.as_ref(), state.github.as_ref(), token, login, &verified_emails)
+                    .await
+            }
+            Admission::VerifiedEmail => {
+                state.signup_policy.admits_email(state.invites.as_ref(), &verified_emails).await
+            }
+        };
+        admitted.map_err(|e| AppError::Upstream(format!("Failed to evaluate signup policy: {:?}", e)))?
+    } else {
+        true
     };
+    let status = if admitted { AccountStatus::Active } else { AccountStatus::PendingApproval };
+
+    let user = match existing_user {
+        Some(mut user) => {
+            if user.status != status {
+                let activated = match user.id {
+                    Some(user_id) => state.users.set_status(user_id, None, status.clone()).await,
+                    None => Ok(false),
+                };
+                if let Err(e) = activated {
+                    tracing::warn!(error = ?e, "failed to activate user");
+                }
+                user.status = status;
+            }
+            user
+        }
+        None => {
+            let mut new_user = User {
+                id: None,
+                username,
+                full_name,
+                email,
+                password_hash: String::new(),
+                role: Role::Member,
+                status,
+                coins: 0,
+                project_ids: Some(Vec::new()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                linked_identities: vec![identity],
+                legacy_github_unresolved: false,
+                two_factor: None,
+                created_at: mongodb::bson::DateTime::now(),
+                updated_at: mongodb::bson::DateTime::now(),
+            };
 
-    let jwt_token = match create_jwt(
-        &user.id.unwrap().to_hex(),
-        &user.username,
-        &user.email,
-        &user.role
-    ) {
-        Ok(token) => token,
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to create token"
-                }))
-            ).into_response();
+            new_user.id = match state.users.insert(&new_user).await {
+                Ok(id) => Some(id),
+                Err(RepoError::Duplicate) => return Err(email_taken()),
+                Err(e) => return Err(e.into()),
+            };
+            new_user
         }
     };
 
-    (
-        StatusCode::OK,
-        Json(serde_json::json!({
-            "success": true,
-            "token": jwt_token,
-            "user": {
-                "id": user.id.map(|id| id.to_hex()),
-                "username": user.username,
-                "full_name": user.full_name,
-                "email": user.email,
-                "role": format!("{:?}", user.role),
-                "coins": user.coins,
-            }
-        }))
-    ).into_response()
+    issue_session(state, &user, user_agent(headers)).await
+}
+
+// GET /api/v1/auth/github/callback - Public: finish a GitHub sign-in or account link
+#[utoipa::path(
+    get, path = "/api/v1/auth/github/callback", tag = "auth",
+    params(AuthRequest),
+    responses((status = 200, response = SignIn))
+)]
+pub async fn github_callback(
+    Query(query): Query<AuthRequest>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let pending = consume_oauth_state(&state, github::PROVIDER, &query.state).await?;
+
+    let client = get_oauth_client(&state.config.github)?;
+
+    let token = client
+        .exchange_code(AuthorizationCode::new(query.code))
+        .set_pkce_verifier(PkceCodeVerifier::new(pending.pkce_verifier))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| AppError::Upstream(format!("Failed to exchange GitHub authorization code: {:?}", e)))?;
+
+    let access_token = token.access_token().secret();
+
+    let user_info = state.github
+        .user(access_token)
+        .await
+        .map_err(|e| AppError::Upstream(format!("Failed to fetch GitHub user info: {:?}", e)))?;
+
+    let emails = state.github.emails(access_token).await.unwrap_or_else(|e| {
+        tracing::warn!(error = ?e, "failed to fetch GitHub user emails");
+        Vec::new()
+    });
+    let verified_emails: Vec<String> = emails
+        .iter()
+        .filter(|e| e.verified)
+        .map(|e| e.email.to_lowercase())
+        .collect();
+
+    let primary_email = emails
+        .iter()
+        .find(|e| e.primary && e.verified)
+        .map(|e| e.email.to_lowercase())
+        .or_else(|| verified_emails.first().cloned());
+
+    let profile = ExternalProfile {
+        identity: LinkedIdentity::new(
+            github::PROVIDER,
+            &user_info.id.to_string(),
+            Some(user_info.login.clone()),
+            primary_email.clone(),
+        ),
+        username: user_info.login.clone(),
+        full_name: user_info.name.clone().unwrap_or_else(|| user_info.login.clone()),
+        // GitHub accounts without a verified email get GitHub's noreply address
+        email: primary_email.unwrap_or_else(|| {
+            format!("{}+{}@users.noreply.github.com", user_info.id, user_info.login)
+        }),
+        verified_emails,
+    };
+
+    let admission = Admission::GitHub { token: access_token, login: &user_info.login };
+    finish_external_login(&state, &headers, pending.link_user_id, profile, admission).await
+}
+
+// Test login endpoint: signs in as any user without credentials. Only compiled with
+// the `dev-login` feature and refused unless DEV_MODE=true.
+#[cfg(feature = "dev-login")]
+#[derive(Debug, Deserialize, utoipa::ToSchema, validator::Validate)]
+pub struct TestLoginRequest {
+    pub user_id: String,
+}
+
+#[cfg(feature = "dev-login")]
+#[utoipa::path(
+    post, path = "/api/v1/auth/test-login", tag = "auth",
+    request_body = TestLoginRequest,
+    responses(
+        (status = 200, response = SignIn),
+        (status = 404, description = "DEV_MODE is off", body = crate::error::ErrorBody)
+    )
+)]
+pub async fn test_login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    crate::validate::ValidJson(payload): crate::validate::ValidJson<TestLoginRequest>,
+) -> AppResult<Response> {
+    if !state.config.auth.dev_mode {
+        return Err(AppError::NotFound("Not found".to_string()));
+    }
+
+    let user_id = crate::error::parse_id(&payload.user_id, "user ID")?;
+    let user = found(state.users.get(user_id).await?, "User")?;
+
+    issue_session(&state, &user, user_agent(&headers)).await
 }
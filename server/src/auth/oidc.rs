@@ -0,0 +1,470 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use mongodb::bson::oid::ObjectId;
+use oauth2::{CsrfToken, PkceCodeChallenge};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::auth::{
+    consume_oauth_state, finish_external_login, store_oauth_state, Admission, AuthRequest, ExternalProfile,
+};
+use crate::config::OidcProviderConfig;
+use crate::db::AppState;
+use crate::error::{AppError, AppResult, ErrorBody};
+use crate::openapi::SignIn;
+use crate::models::LinkedIdentity;
+
+// Path segments under /auth that belong to other routes and can't name a provider
+const RESERVED_NAMES: &[&str] = &[
+    "github", "test-login", "refresh", "register", "login", "password", "sessions", "logout",
+    "link", "identities", "providers", "2fa", "tokens",
+];
+
+// Provider names become /auth/{name} paths
+pub fn valid_provider_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !RESERVED_NAMES.contains(&name)
+}
+
+// Algorithms accepted for ID tokens; symmetric ones would let anyone with the client secret sign
+const ID_TOKEN_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256, Algorithm::RS384, Algorithm::RS512,
+    Algorithm::PS256, Algorithm::PS384, Algorithm::PS512,
+    Algorithm::ES256, Algorithm::ES384,
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Discovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+// One OpenID Connect issuer. Discovery and JWKS are fetched on first use and cached;
+// the key set is refetched once when a token names a key we haven't seen (rotation).
+#[derive(Debug)]
+pub struct OidcProvider {
+    pub name: String,
+    pub display_name: String,
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    scopes: Vec<String>,
+    client: reqwest::Client,
+    discovery: RwLock<Option<Discovery>>,
+    jwks: RwLock<Option<JwkSet>>,
+}
+
+impl OidcProvider {
+    pub fn new(
+        name: &str,
+        issuer: &str,
+        client_id: &str,
+        client_secret: &str,
+        redirect_url: &str,
+    ) -> Self {
+        OidcProvider {
+            name: name.to_string(),
+            display_name: name.to_string(),
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            redirect_url: redirect_url.to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string(), "profile".to_string()],
+            client: reqwest::Client::new(),
+            discovery: RwLock::new(None),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    fn from_config(name: &str, config: &OidcProviderConfig) -> Self {
+        let mut provider = OidcProvider::new(
+            name,
+            &config.issuer,
+            &config.client_id,
+            &config.client_secret,
+            &config.redirect_url,
+        );
+        if let Some(display_name) = &config.display_name {
+            provider.display_name = display_name.clone();
+        }
+        if let Some(scopes) = &config.scopes {
+            provider.scopes = scopes.clone();
+        }
+        provider
+    }
+
+    pub async fn discovery(&self) -> Result<Discovery, String> {
+        if let Some(discovery) = self.discovery.read().await.as_ref() {
+            return Ok(discovery.clone());
+        }
+
+        let discovery: Discovery = self.client
+            .get(format!("{}/.well-known/openid-configuration", self.issuer))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if discovery.issuer.trim_end_matches('/') != self.issuer {
+            return Err(format!("Discovery document is for issuer {}, expected {}", discovery.issuer, self.issuer));
+        }
+
+        *self.discovery.write().await = Some(discovery.clone());
+        Ok(discovery)
+    }
+
+    async fn jwks(&self, refresh: bool) -> Result<JwkSet, String> {
+        let cached = if refresh { None } else { self.jwks.read().await.clone() };
+        if let Some(jwks) = cached {
+            return Ok(jwks);
+        }
+
+        let jwks_uri = self.discovery().await?.jwks_uri;
+        let jwks: JwkSet = self.client
+            .get(jwks_uri)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *self.jwks.write().await = Some(jwks.clone());
+        Ok(jwks)
+    }
+
+    pub async fn authorize_url(&self, state: &str, nonce: &str, pkce_challenge: &PkceCodeChallenge) -> Result<String, String> {
+        let mut url = reqwest::Url::parse(&self.discovery().await?.authorization_endpoint)
+            .map_err(|e| e.to_string())?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_url)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", pkce_challenge.as_str())
+            .append_pair("code_challenge_method", pkce_challenge.method().as_str());
+        Ok(url.to_string())
+    }
+
+    // Trade the authorization code for the raw ID token
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<String, String> {
+        let token_endpoint = self.discovery().await?.token_endpoint;
+        let response: TokenResponse = self.client
+            .post(token_endpoint)
+            .header("Accept", "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.redirect_url),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("code_verifier", pkce_verifier),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.id_token)
+    }
+
+    // Check signature (against the issuer's JWKS), issuer, audience, expiry and nonce
+    pub async fn validate_id_token(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims, String> {
+        let header = decode_header(id_token).map_err(|e| e.to_string())?;
+        if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+            return Err(format!("ID token algorithm {:?} is not allowed", header.alg));
+        }
+        let kid = header.kid.ok_or("ID token has no key id")?;
+
+        let jwk = match self.jwks(false).await?.find(&kid) {
+            Some(jwk) => jwk.clone(),
+            None => self.jwks(true).await?
+                .find(&kid)
+                .cloned()
+                .ok_or_else(|| format!("Unknown signing key {}", kid))?,
+        };
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| e.to_string())?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer, &format!("{}/", self.issuer)]);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| e.to_string())?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("ID token nonce does not match".to_string());
+        }
+        Ok(claims)
+    }
+}
+
+// Configured OIDC providers by name, from `OIDC_PROVIDERS=google,other`
+#[derive(Debug, Default)]
+pub struct OidcRegistry {
+    providers: BTreeMap<String, Arc<OidcProvider>>,
+}
+
+impl OidcRegistry {
+    // Required fields were checked by `Config::validate`
+    pub fn from_config(providers: &BTreeMap<String, OidcProviderConfig>) -> Self {
+        let mut registry = OidcRegistry::default();
+        for (name, config) in providers {
+            registry.register(OidcProvider::from_config(name, config));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, provider: OidcProvider) {
+        if !valid_provider_name(&provider.name) {
+            panic!("OIDC provider name {:?} is reserved or not lowercase letters, digits and dashes", provider.name);
+        }
+        self.providers.insert(provider.name.clone(), Arc::new(provider));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<OidcProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    pub fn providers(&self) -> impl Iterator<Item = &Arc<OidcProvider>> {
+        self.providers.values()
+    }
+}
+
+pub(crate) fn unknown_provider() -> AppError {
+    AppError::NotFound("Unknown sign-in provider".to_string()).with_code("unknown_provider")
+}
+
+// Store a fresh OAuth state with nonce and build the provider's consent URL for it
+pub(crate) async fn start_oidc(state: &AppState, provider: &OidcProvider, link_user_id: Option<ObjectId>) -> AppResult<String> {
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let csrf_token = CsrfToken::new_random();
+    let nonce = CsrfToken::new_random().secret().clone();
+
+    let auth_url = provider
+        .authorize_url(csrf_token.secret(), &nonce, &pkce_challenge)
+        .await
+        .map_err(|e| AppError::Upstream(format!("OIDC discovery for {} failed: {}", provider.name, e)))?;
+
+    store_oauth_state(state, &provider.name, &csrf_token, &pkce_verifier, Some(nonce), link_user_id).await?;
+    Ok(auth_url)
+}
+
+// GET /api/v1/auth/{provider} - Public: redirect to an OIDC provider's sign-in page
+#[utoipa::path(
+    get, path = "/api/v1/auth/{provider}", tag = "auth",
+    params(("provider" = String, Path, description = "A configured OIDC provider")),
+    responses(
+        (status = 303, description = "Redirect to the provider's sign-in page"),
+        (status = 404, description = "Unknown provider", body = ErrorBody)
+    )
+)]
+pub async fn oidc_login(State(state): State<AppState>, Path(provider): Path<String>) -> AppResult<Response> {
+    let provider = state.oidc.get(&provider).ok_or_else(unknown_provider)?;
+    let auth_url = start_oidc(&state, &provider, None).await?;
+    Ok(Redirect::to(&auth_url).into_response())
+}
+
+// GET /api/v1/auth/{provider}/callback - Public: finish an OIDC sign-in or account link
+#[utoipa::path(
+    get, path = "/api/v1/auth/{provider}/callback", tag = "auth",
+    params(("provider" = String, Path, description = "A configured OIDC provider"), AuthRequest),
+    responses(
+        (status = 200, response = SignIn),
+        (status = 401, description = "The provider rejected the sign-in", body = ErrorBody)
+    )
+)]
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<AuthRequest>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let provider = state.oidc.get(&provider).ok_or_else(unknown_provider)?;
+    let pending = consume_oauth_state(&state, &provider.name, &query.state).await?;
+
+    let claims = match provider.exchange_code(&query.code, &pending.pkce_verifier).await {
+        Ok(id_token) => provider.validate_id_token(&id_token, pending.nonce.as_deref().unwrap_or_default()).await,
+        Err(e) => Err(e),
+    };
+    let claims = claims.map_err(|e| {
+        tracing::warn!(provider = %provider.name, error = %e, "OIDC sign-in failed");
+        AppError::Unauthorized("Sign-in with this provider failed".to_string()).with_code("external_sign_in_failed")
+    })?;
+
+    let verified_email = claims.email
+        .as_ref()
+        .filter(|_| claims.email_verified)
+        .map(|email| email.to_lowercase());
+    let username = claims.preferred_username
+        .clone()
+        .or_else(|| verified_email.as_ref().and_then(|email| email.split('@').next().map(String::from)))
+        .unwrap_or_else(|| format!("{}-{}", provider.name, claims.sub));
+
+    let profile = ExternalProfile {
+        identity: LinkedIdentity::new(
+            &provider.name,
+            &claims.sub,
+            claims.preferred_username.clone(),
+            verified_email.clone(),
+        ),
+        full_name: claims.name.clone().unwrap_or_else(|| username.clone()),
+        username,
+        // Placeholder for providers that don't vouch for an address; never matches a real one
+        email: verified_email.clone().unwrap_or_else(|| format!("{}+{}@users.invalid", provider.name, claims.sub)),
+        verified_emails: verified_email.into_iter().collect(),
+    };
+
+    finish_external_login(&state, &headers, pending.link_user_id, profile, Admission::VerifiedEmail).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::{get, post}, Json, Router};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    const TEST_KEY_PEM: &str = include_str!("testdata/oidc_rsa.pem");
+    const TEST_KEY_N: &str = "xPn5NSjVX80u7SKjySV1-Ey7O9ZDXwP8t_xsG30UzCpk-sd5T_w_z-RaDHMyGAz0K6uH1SZGsB8O6WQlMgObXFLj5IYH7tmWARDE5To6X41IHzQTtoHG5vWjVxjFyCIPdx5sQDXrnZaY9S5U1lfIWfjPIb8g1VKeO2w2vBE98qQX6uRYO5z0et0FuN5U2e0R0rL_teCgTbZT9CrBgmDuoZr6X5DCflEKes53kdY4rEKoMnzQWTaro3KZySUtQoNdkJRO_PPwYJMVDsOQ0Tlc7Fa7Dsn7cRjj1lhC2pIvOX5m9iQ9ZNgbQ1V8YGcheZxqdssuCDDkqYYcyrbnU7yhuQ";
+
+    fn sign(issuer: &str, audience: &str, nonce: &str, kid: &str) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let claims = serde_json::json!({
+            "iss": issuer,
+            "aud": audience,
+            "sub": "1094857",
+            "email": "Student@College.edu",
+            "email_verified": true,
+            "name": "Test Student",
+            "nonce": nonce,
+            "exp": chrono::Utc::now().timestamp() + 300,
+            "iat": chrono::Utc::now().timestamp(),
+        });
+        encode(&header, &claims, &EncodingKey::from_rsa_pem(TEST_KEY_PEM.as_bytes()).unwrap()).unwrap()
+    }
+
+    // Minimal issuer: discovery, a JWKS with one key and a token endpoint that
+    // answers every code with an ID token for nonce "n-123"
+    async fn mock_issuer() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+        let discovery = serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        });
+        let jwks = serde_json::json!({
+            "keys": [{ "kty": "RSA", "kid": "key-1", "alg": "RS256", "use": "sig", "n": TEST_KEY_N, "e": "AQAB" }]
+        });
+        let id_token = sign(&issuer, "iris-client", "n-123", "key-1");
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(move || async move { Json(discovery) }))
+            .route("/jwks", get(move || async move { Json(jwks) }))
+            .route("/token", post(move || async move { Json(serde_json::json!({ "id_token": id_token })) }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        issuer
+    }
+
+    fn provider(issuer: &str) -> OidcProvider {
+        OidcProvider::new("college", issuer, "iris-client", "secret", "http://localhost:3000/auth/college/callback")
+    }
+
+    #[tokio::test]
+    async fn authorize_url_comes_from_discovery() {
+        let issuer = mock_issuer().await;
+        let (challenge, _) = PkceCodeChallenge::new_random_sha256();
+        let url = provider(&issuer).authorize_url("s-1", "n-1", &challenge).await.unwrap();
+
+        assert!(url.starts_with(&format!("{}/authorize?", issuer)));
+        assert!(url.contains("client_id=iris-client"));
+        assert!(url.contains("nonce=n-1"));
+        assert!(url.contains("code_challenge_method=S256"));
+    }
+
+    #[tokio::test]
+    async fn code_exchange_yields_validated_claims() {
+        let issuer = mock_issuer().await;
+        let provider = provider(&issuer);
+
+        let id_token = provider.exchange_code("code", "verifier").await.unwrap();
+        let claims = provider.validate_id_token(&id_token, "n-123").await.unwrap();
+
+        assert_eq!(claims.sub, "1094857");
+        assert_eq!(claims.email.as_deref(), Some("Student@College.edu"));
+        assert!(claims.email_verified);
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_nonce_audience_issuer_and_key() {
+        let issuer = mock_issuer().await;
+        let provider = provider(&issuer);
+
+        let token = sign(&issuer, "iris-client", "n-123", "key-1");
+        assert!(provider.validate_id_token(&token, "other-nonce").await.is_err());
+
+        let token = sign(&issuer, "someone-else", "n-123", "key-1");
+        assert!(provider.validate_id_token(&token, "n-123").await.is_err());
+
+        let token = sign("http://evil.example", "iris-client", "n-123", "key-1");
+        assert!(provider.validate_id_token(&token, "n-123").await.is_err());
+
+        let token = sign(&issuer, "iris-client", "n-123", "unknown-key");
+        assert!(provider.validate_id_token(&token, "n-123").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_symmetric_tokens() {
+        let issuer = mock_issuer().await;
+        let claims = serde_json::json!({
+            "iss": issuer, "aud": "iris-client", "sub": "1", "nonce": "n-123",
+            "exp": chrono::Utc::now().timestamp() + 300,
+        });
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(b"secret")).unwrap();
+
+        assert!(provider(&issuer).validate_id_token(&token, "n-123").await.is_err());
+    }
+}
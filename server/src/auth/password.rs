@@ -0,0 +1,394 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::bson::{doc, DateTime};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+use std::sync::OnceLock;
+
+use crate::auth::access_tokens;
+use crate::auth::sessions::{generate_token, hash_token, issue_session, revoke_user_sessions};
+use crate::auth::user_agent;
+use crate::db::AppState;
+use crate::error::{AppError, AppResult, ErrorBody};
+use crate::openapi::SignIn;
+use crate::mailer::Email;
+use crate::models::{AccountStatus, PasswordReset, Role, User};
+use crate::repo::RepoError;
+use crate::validate::{not_blank, ValidJson};
+
+const MIN_PASSWORD_LENGTH: usize = 10;
+const MAX_PASSWORD_LENGTH: usize = 128;
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+const LOCKOUT_MINUTES: i64 = 15;
+const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct RegisterRequest {
+    #[validate(length(max = 39), custom(function = "not_blank"))]
+    pub username: String,
+    #[validate(length(max = 100), custom(function = "not_blank"))]
+    pub full_name: String,
+    #[validate(email)]
+    pub email: String,
+    pub password: String, // Checked against the password policy, a 400 `weak_password`
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct LoginRequest {
+    pub login: String, // Username or email
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+// Returns every rule the password breaks; empty means it is acceptable
+pub fn password_policy_violations(password: &str, personal: &[&str]) -> Vec<String> {
+    let mut violations = Vec::new();
+    let length = password.chars().count();
+
+    if length < MIN_PASSWORD_LENGTH {
+        violations.push(format!("Password must be at least {} characters long", MIN_PASSWORD_LENGTH));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        violations.push(format!("Password must be at most {} characters long", MAX_PASSWORD_LENGTH));
+    }
+    if !password.chars().any(char::is_alphabetic) || !password.chars().any(|c| c.is_ascii_digit()) {
+        violations.push("Password must contain both letters and digits".to_string());
+    }
+
+    let lowered = password.to_lowercase();
+    let reuses_personal = personal
+        .iter()
+        .map(|value| value.split('@').next().unwrap_or_default().to_lowercase())
+        .any(|value| value.len() >= 3 && lowered.contains(&value));
+    if reuses_personal {
+        violations.push("Password must not contain your username or email".to_string());
+    }
+
+    violations
+}
+
+// Argon2id with a fresh random salt. Hashing is deliberately slow, so keep it off the async workers.
+pub async fn hash_password(password: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+async fn verify_password(password: String, hash: String) -> bool {
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash)
+            .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+            .unwrap_or(false)
+    })
+    .await
+    .unwrap_or(false)
+}
+
+// Verified against when the account doesn't exist, so timing doesn't reveal which logins are real
+fn dummy_hash() -> String {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY
+        .get_or_init(|| {
+            let salt = SaltString::generate(&mut OsRng);
+            Argon2::default()
+                .hash_password(b"iris-dummy-password", &salt)
+                .map(|hash| hash.to_string())
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+pub(crate) fn policy_error(violations: Vec<String>) -> AppError {
+    AppError::BadRequest("Password does not meet the policy".to_string())
+        .with_code("weak_password")
+        .with_details(serde_json::json!(violations))
+}
+
+fn invalid_credentials() -> AppError {
+    AppError::Unauthorized("Invalid username/email or password".to_string()).with_code("invalid_credentials")
+}
+
+// POST /api/v1/auth/register - Public: create a password account
+#[utoipa::path(
+    post, path = "/api/v1/auth/register", tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, response = SignIn),
+        (status = 202, description = "Signed up outside the signup policy", body = Object, example = json!({
+            "success": true,
+            "pending_approval": true,
+            "message": "An admin has to approve your account before you can sign in"
+        })),
+        (status = 400, description = "`weak_password`, with the broken rules in `details`", body = ErrorBody),
+        (status = 409, body = ErrorBody)
+    )
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<RegisterRequest>,
+) -> AppResult<Response> {
+    let username = payload.username.trim().to_string();
+    let email = payload.email.trim().to_lowercase();
+
+    let violations = password_policy_violations(&payload.password, &[&username, &email]);
+    if !violations.is_empty() {
+        return Err(policy_error(violations));
+    }
+
+    let taken = state.users.find_by_login(&email, &username).await?;
+    if taken.is_some() {
+        return Err(AppError::Conflict("Username or email is already registered".to_string()));
+    }
+
+    let password_hash = hash_password(payload.password)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+    // Registration emails are unverified, so a restricted signup policy
+    // always sends password accounts to an admin for approval
+    let status = if state.signup_policy.is_open() {
+        AccountStatus::Active
+    } else {
+        AccountStatus::PendingApproval
+    };
+
+    let now = DateTime::now();
+    let mut user = User {
+        id: None,
+        username,
+        full_name: payload.full_name.trim().to_string(),
+        email,
+        password_hash,
+        role: Role::Member,
+        status,
+        coins: 0,
+        project_ids: Some(Vec::new()),
+        failed_login_attempts: 0,
+        locked_until: None,
+        linked_identities: Vec::new(),
+        legacy_github_unresolved: false,
+        two_factor: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    // Lost a race against a registration with the same email
+    user.id = match state.users.insert(&user).await {
+        Ok(id) => Some(id),
+        Err(RepoError::Duplicate) => {
+            return Err(AppError::Conflict("Username or email is already registered".to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Created, but no session until an admin approves the account
+    if user.status == AccountStatus::PendingApproval {
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "success": true,
+                "pending_approval": true,
+                "message": "An admin has to approve your account before you can sign in"
+            }))
+        ).into_response());
+    }
+
+    let mut response = issue_session(&state, &user, user_agent(&headers)).await?;
+    *response.status_mut() = StatusCode::CREATED;
+    Ok(response)
+}
+
+// POST /api/v1/auth/login - Public: username/email + password login with lockout
+#[utoipa::path(
+    post, path = "/api/v1/auth/login", tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, response = SignIn),
+        (status = 401, description = "Wrong login or password", body = ErrorBody),
+        (status = 423, description = "Locked after too many failed attempts", body = ErrorBody)
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<LoginRequest>,
+) -> AppResult<Response> {
+    let login = payload.login.trim();
+
+    let user = state.users.find_by_login(&login.to_lowercase(), login).await?;
+
+    // OAuth-only accounts have no hash; treat them exactly like unknown logins
+    let user = match user {
+        Some(user) if !user.password_hash.is_empty() => user,
+        _ => {
+            verify_password(payload.password, dummy_hash()).await;
+            return Err(invalid_credentials());
+        }
+    };
+    let user_id = user.id.ok_or_else(|| AppError::Internal("User without an id".to_string()))?;
+
+    // While locked no password is checked, so guesses can't learn anything from the answer
+    if user.locked_until.is_some_and(|until| until > DateTime::now()) {
+        return Err(AppError::Locked(format!(
+            "Too many failed attempts, try again in up to {} minutes or reset your password",
+            LOCKOUT_MINUTES
+        )).with_code("account_locked"));
+    }
+
+    if !verify_password(payload.password, user.password_hash.clone()).await {
+        let until = chrono::Utc::now() + chrono::Duration::minutes(LOCKOUT_MINUTES);
+        let lock_until = DateTime::from_millis(until.timestamp_millis());
+        match state.users.record_failed_login(user_id, MAX_FAILED_ATTEMPTS, lock_until).await {
+            Ok(true) => tracing::info!(user_id = %user_id, "account locked after failed logins"),
+            Ok(false) => {}
+            Err(e) => tracing::warn!(error = ?e, "failed to record failed login"),
+        }
+        return Err(invalid_credentials());
+    }
+
+    if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+        let reset = state.users.clear_failed_logins(user_id).await;
+        if let Err(e) = reset {
+            tracing::warn!(error = ?e, "failed to reset login attempts");
+        }
+    }
+
+    issue_session(&state, &user, user_agent(&headers)).await
+}
+
+// POST /api/v1/auth/password/forgot - Public: email a reset link if the account exists
+#[utoipa::path(
+    post, path = "/api/v1/auth/password/forgot", tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "If that email is registered, a reset link has been sent"}))
+    )
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ForgotPasswordRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    // Same answer either way so this can't be used to probe for accounts
+    let accepted = Json(serde_json::json!({
+        "success": true,
+        "message": "If that email is registered, a reset link has been sent"
+    }));
+
+    let email = payload.email.trim().to_lowercase();
+    let Some(user) = state.users.find_by_email(&email).await? else {
+        return Ok(accepted);
+    };
+    let Some(user_id) = user.id else {
+        return Ok(accepted);
+    };
+
+    let token = generate_token();
+    let now = chrono::Utc::now();
+    let reset = PasswordReset {
+        id: None,
+        user_id,
+        token_hash: hash_token(&token),
+        created_at: DateTime::from_millis(now.timestamp_millis()),
+        expires_at: DateTime::from_millis(
+            (now + chrono::Duration::minutes(RESET_TOKEN_TTL_MINUTES)).timestamp_millis(),
+        ),
+    };
+
+    state.password_resets.insert(&reset).await?;
+
+    let frontend_url = &state.config.server.frontend_url;
+    let email = Email {
+        to: user.email,
+        subject: "Reset your IRIS password".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the link below to set a new password. It expires in {} minutes.\n\n{}/reset-password?token={}\n\nIf you didn't ask for this, you can ignore this email.",
+            user.full_name, RESET_TOKEN_TTL_MINUTES, frontend_url, token
+        ),
+    };
+
+    state.mailer
+        .send(email)
+        .await
+        .map_err(|e| AppError::Upstream(format!("Failed to send password reset email: {:?}", e)))?;
+
+    Ok(accepted)
+}
+
+// POST /api/v1/auth/password/reset - Public: set a new password with an emailed token
+#[utoipa::path(
+    post, path = "/api/v1/auth/password/reset", tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "Password updated, please login again"})),
+        (status = 400, description = "Invalid or expired token, or a weak password", body = ErrorBody)
+    )
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ResetPasswordRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let invalid_token = || AppError::BadRequest("Invalid or expired reset token".to_string()).with_code("invalid_reset_token");
+
+    let token_hash = hash_token(&payload.token);
+    let reset = state.password_resets.find_active(&token_hash).await?.ok_or_else(invalid_token)?;
+    let user = state.users.get(reset.user_id).await?.ok_or_else(invalid_token)?;
+
+    let violations = password_policy_violations(&payload.new_password, &[&user.username, &user.email]);
+    if !violations.is_empty() {
+        return Err(policy_error(violations));
+    }
+
+    // Consume the token only now, so a policy failure doesn't burn it
+    if !state.password_resets.consume(&token_hash).await? {
+        return Err(invalid_token());
+    }
+
+    let password_hash = hash_password(payload.new_password)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+    state.users.set_password(reset.user_id, &password_hash).await?;
+
+    // Whoever knew the old password must not stay logged in or keep tokens they made
+    if let Err(e) = revoke_user_sessions(&state, reset.user_id).await {
+        tracing::error!(error = ?e, "failed to revoke sessions after password reset");
+    }
+    if let Err(e) = access_tokens::revoke_user_tokens(&state, reset.user_id).await {
+        tracing::error!(error = ?e, "failed to revoke access tokens after password reset");
+    }
+    if let Err(e) = state.password_resets.delete_for_user(reset.user_id).await {
+        tracing::warn!(error = ?e, "failed to clear other reset tokens");
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Password updated, please login again"
+    })))
+}
@@ -0,0 +1,210 @@
+use crate::auth::github::GitHubApi;
+use crate::config::SignupConfig;
+use crate::repo::InviteRepository;
+
+// Who may sign up without an admin approving them first. With nothing
+// configured the policy is open and every new account is active right away.
+#[derive(Debug, Clone, Default)]
+pub struct SignupPolicy {
+    pub orgs: Vec<String>,                // SIGNUP_GITHUB_ORGS=nst-sdc,other-org
+    pub teams: Vec<(String, String)>,     // SIGNUP_GITHUB_TEAMS=nst-sdc/core,nst-sdc/robotics
+    pub email_domains: Vec<String>,       // SIGNUP_EMAIL_DOMAINS=college.edu
+    pub invite_only: bool,                // SIGNUP_INVITE_ONLY=true: restricted even without other rules
+}
+
+impl SignupPolicy {
+    pub fn from_config(config: &SignupConfig) -> Self {
+        SignupPolicy {
+            orgs: config.github_orgs.clone(),
+            teams: config.github_teams
+                .iter()
+                .filter_map(|team| {
+                    team.split_once('/')
+                        .map(|(org, slug)| (org.to_string(), slug.to_string()))
+                })
+                .collect(),
+            email_domains: config.email_domains
+                .iter()
+                .map(|domain| domain.trim_start_matches('@').to_lowercase())
+                .collect(),
+            invite_only: config.invite_only,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.orgs.is_empty() && self.teams.is_empty() && self.email_domains.is_empty() && !self.invite_only
+    }
+
+    // Org and team checks need GitHub's read:org scope at login
+    pub fn needs_org_scope(&self) -> bool {
+        !self.orgs.is_empty() || !self.teams.is_empty()
+    }
+
+    fn email_domain_allowed(&self, email: &str) -> bool {
+        email
+            .rsplit_once('@')
+            .is_some_and(|(_, domain)| self.email_domains.iter().any(|allowed| allowed == &domain.to_lowercase()))
+    }
+
+    // Accept an outstanding invite for one of the emails, if there is one
+    async fn accept_invite(&self, invites: &dyn InviteRepository, emails: &[String]) -> Result<bool, String> {
+        if emails.is_empty() {
+            return Ok(false);
+        }
+
+        let emails: Vec<String> = emails.iter().map(|email| email.to_lowercase()).collect();
+        invites.accept(&emails).await.map_err(|e| e.to_string())
+    }
+
+    // Decide whether an account with these verified emails may sign in
+    // (OIDC providers, where org and team membership don't apply)
+    pub async fn admits_email(&self, invites: &dyn InviteRepository, verified_emails: &[String]) -> Result<bool, String> {
+        if self.is_open() || verified_emails.iter().any(|email| self.email_domain_allowed(email)) {
+            return Ok(true);
+        }
+
+        self.accept_invite(invites, verified_emails).await
+    }
+
+    // Decide whether a GitHub account may sign in. `verified_emails` must
+    // only contain addresses GitHub reports as verified.
+    pub async fn admits_github(
+        &self,
+        invites: &dyn InviteRepository,
+        github: &dyn GitHubApi,
+        token: &str,
+        login: &str,
+        verified_emails: &[String],
+    ) -> Result<bool, String> {
+        if self.is_open() {
+            return Ok(true);
+        }
+
+        if verified_emails.iter().any(|email| self.email_domain_allowed(email)) {
+            return Ok(true);
+        }
+
+        for org in &self.orgs {
+            if github.is_org_member(token, org).await? {
+                return Ok(true);
+            }
+        }
+
+        for (org, team_slug) in &self.teams {
+            if github.is_team_member(token, org, team_slug, login).await? {
+                return Ok(true);
+            }
+        }
+
+        self.accept_invite(invites, verified_emails).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::github::HttpGitHubApi;
+    use crate::models::Invite;
+    use crate::repo::memory::MemoryStore;
+    use mongodb::bson::{oid::ObjectId, DateTime};
+    use axum::{extract::Path, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+
+    // Stands in for api.github.com: octocat is an active member of nst-sdc and
+    // nst-sdc/core, hubot's team membership is still pending
+    async fn mock_github() -> HttpGitHubApi {
+        let app = Router::new()
+            .route(
+                "/user/memberships/orgs/{org}",
+                get(|Path(org): Path<String>| async move {
+                    if org == "nst-sdc" {
+                        Json(serde_json::json!({ "state": "active" })).into_response()
+                    } else {
+                        StatusCode::NOT_FOUND.into_response()
+                    }
+                }),
+            )
+            .route(
+                "/orgs/{org}/teams/{team}/memberships/{login}",
+                get(|Path((org, team, login)): Path<(String, String, String)>| async move {
+                    match (org.as_str(), team.as_str(), login.as_str()) {
+                        ("nst-sdc", "core", "octocat") => Json(serde_json::json!({ "state": "active" })).into_response(),
+                        ("nst-sdc", "core", "hubot") => Json(serde_json::json!({ "state": "pending" })).into_response(),
+                        _ => StatusCode::NOT_FOUND.into_response(),
+                    }
+                }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        HttpGitHubApi::new(format!("http://{}", addr))
+    }
+
+    #[tokio::test]
+    async fn open_policy_admits_everyone() {
+        let github = mock_github().await;
+        let policy = SignupPolicy::default();
+        assert!(policy.admits_github(&MemoryStore::default(), &github, "token", "anyone", &[]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn org_membership_is_checked() {
+        let github = mock_github().await;
+        let invites = MemoryStore::default();
+
+        let member_policy = SignupPolicy { orgs: vec!["nst-sdc".to_string()], ..Default::default() };
+        assert!(member_policy.admits_github(&invites, &github, "token", "octocat", &[]).await.unwrap());
+
+        let other_policy = SignupPolicy { orgs: vec!["other-org".to_string()], ..Default::default() };
+        assert!(!other_policy.admits_github(&invites, &github, "token", "octocat", &[]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn only_active_team_membership_counts() {
+        let github = mock_github().await;
+        let invites = MemoryStore::default();
+        let policy = SignupPolicy {
+            teams: vec![("nst-sdc".to_string(), "core".to_string())],
+            ..Default::default()
+        };
+
+        assert!(policy.admits_github(&invites, &github, "token", "octocat", &[]).await.unwrap());
+        assert!(!policy.admits_github(&invites, &github, "token", "hubot", &[]).await.unwrap());
+        assert!(!policy.admits_github(&invites, &github, "token", "stranger", &[]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn email_domain_needs_a_verified_address() {
+        let github = mock_github().await;
+        let invites = MemoryStore::default();
+        let policy = SignupPolicy {
+            email_domains: vec!["college.edu".to_string()],
+            orgs: vec!["other-org".to_string()],
+            ..Default::default()
+        };
+
+        let verified = ["Student@College.edu".to_string()];
+        assert!(policy.admits_github(&invites, &github, "token", "student", &verified).await.unwrap());
+        assert!(!policy.admits_github(&invites, &github, "token", "student", &[]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn an_invite_admits_one_account() {
+        let github = mock_github().await;
+        let invites = MemoryStore::default();
+        let policy = SignupPolicy { invite_only: true, ..Default::default() };
+        let invite = Invite {
+            id: None,
+            email: "guest@example.com".to_string(),
+            invited_by: ObjectId::new(),
+            created_at: DateTime::now(),
+            accepted_at: None,
+        };
+        InviteRepository::insert(&invites, &invite).await.unwrap();
+
+        let verified = ["Guest@Example.com".to_string()];
+        assert!(!policy.admits_email(&invites, &["other@example.com".to_string()]).await.unwrap());
+        assert!(policy.admits_github(&invites, &github, "token", "guest", &verified).await.unwrap());
+        assert!(!policy.admits_email(&invites, &verified).await.unwrap());
+    }
+}
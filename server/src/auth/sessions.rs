@@ -0,0 +1,300 @@
+use axum::{
+    extract::{Path, State},
+    response::Response,
+    Json,
+};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+use sha2::{Digest, Sha256};
+
+use crate::auth::{access_tokens, login_response, pending_approval, two_factor};
+use crate::db::AppState;
+use crate::error::{parse_id, AppError, AppResult, ErrorBody};
+use crate::middleware::auth::{access_token_forbidden, impersonation_forbidden, AuthUser, ACCESS_TOKEN_TTL_MINUTES};
+use crate::middleware::create_jwt;
+use crate::models::{AccountStatus, Session, User};
+use crate::repo::RepoResult;
+use crate::validate::ValidJson;
+
+// Refresh tokens slide: every successful refresh extends the session by this much
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // access token lifetime in seconds
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Validate)]
+pub struct LogoutRequest {
+    pub session_id: Option<String>, // Defaults to the current session
+    #[serde(default)]
+    pub all: bool,
+}
+
+// 256 random bits, hex encoded; used for refresh and password reset tokens
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Only hashes of bearer secrets are stored, so a database leak can't be replayed
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn refresh_expiry() -> DateTime {
+    DateTime::from_millis(
+        (chrono::Utc::now() + chrono::Duration::days(REFRESH_TOKEN_TTL_DAYS)).timestamp_millis(),
+    )
+}
+
+fn unauthorized(message: &str) -> AppError {
+    AppError::Unauthorized(format!("{}, please login again", message))
+}
+
+fn access_token_for(state: &AppState, user: &User, session_id: &ObjectId) -> AppResult<String> {
+    let user_id = user.id.ok_or_else(|| AppError::Internal("User without an id".to_string()))?;
+    create_jwt(&state.config.auth.jwt_secret, user_id, user, *session_id)
+        .map_err(|e| AppError::Internal(format!("Failed to create JWT: {:?}", e)))
+}
+
+// Revoke every session of a user (logout everywhere, account compromise)
+pub async fn revoke_user_sessions(state: &AppState, user_id: ObjectId) -> RepoResult<u64> {
+    state.sessions.revoke_all(user_id).await
+}
+
+// Answer a successful sign-in: a new session and the login body, or a 2FA
+// challenge for users with 2FA enabled, who finish at /auth/2fa/verify
+pub async fn issue_session(
+    state: &AppState,
+    user: &User,
+    user_agent: Option<String>,
+) -> AppResult<Response> {
+    // Accounts waiting for approval never get a session
+    if user.status != AccountStatus::Active {
+        return Err(pending_approval());
+    }
+
+    if user.two_factor_enabled() {
+        return two_factor::start_challenge(state, user, user_agent).await;
+    }
+
+    let tokens = create_session(state, user, user_agent, false).await?;
+    Ok(login_response(user, tokens))
+}
+
+pub(crate) async fn create_session(
+    state: &AppState,
+    user: &User,
+    user_agent: Option<String>,
+    two_factor_verified: bool,
+) -> AppResult<TokenPair> {
+    let user_id = user.id.ok_or_else(|| AppError::Internal("User without an id".to_string()))?;
+    let refresh_token = generate_token();
+    let now = DateTime::now();
+
+    let session = Session {
+        id: None,
+        user_id,
+        refresh_token_hash: hash_token(&refresh_token),
+        previous_token_hash: None,
+        user_agent,
+        two_factor_verified,
+        impersonator_id: None,
+        created_at: now,
+        last_used_at: now,
+        expires_at: refresh_expiry(),
+        revoked_at: None,
+    };
+
+    let session_id = state.sessions.insert(&session).await?;
+
+    let token = access_token_for(state, user, &session_id)?;
+
+    Ok(TokenPair {
+        token,
+        refresh_token,
+        expires_in: ACCESS_TOKEN_TTL_MINUTES * 60,
+    })
+}
+
+// POST /api/v1/auth/refresh - Public: exchange a refresh token for a new token pair
+#[utoipa::path(
+    post, path = "/api/v1/auth/refresh", tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, body = TokenPair),
+        (status = 401, description = "Expired, revoked or reused refresh token", body = ErrorBody)
+    )
+)]
+pub async fn refresh_session(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<RefreshRequest>,
+) -> AppResult<Json<TokenPair>> {
+    let presented_hash = hash_token(&payload.refresh_token);
+
+    let session = state.sessions
+        .find_by_refresh_hash(&presented_hash)
+        .await?
+        .ok_or_else(|| unauthorized("Invalid refresh token"))?;
+    let session_id = session.id.ok_or_else(|| unauthorized("Invalid refresh token"))?;
+
+    // A rotated-out token coming back means it was copied: kill the session
+    if session.refresh_token_hash != presented_hash {
+        if let Err(e) = state.sessions.revoke(session_id, None).await {
+            tracing::warn!(error = ?e, "failed to revoke reused session");
+        }
+        return Err(unauthorized("Refresh token reuse detected"));
+    }
+
+    if session.revoked_at.is_some() {
+        return Err(unauthorized("Session revoked"));
+    }
+    if session.impersonator_id.is_some() {
+        return Err(unauthorized("Impersonation sessions cannot be refreshed"));
+    }
+    if session.expires_at < DateTime::now() {
+        return Err(unauthorized("Session expired"));
+    }
+
+    let user = state.users
+        .get(session.user_id)
+        .await?
+        .ok_or_else(|| unauthorized("User not found"))?;
+    if user.status != AccountStatus::Active {
+        return Err(pending_approval());
+    }
+
+    // Rotate, guarded on the old hash so two concurrent refreshes can't both win
+    let refresh_token = generate_token();
+    let rotated = state.sessions
+        .rotate(session_id, &presented_hash, &hash_token(&refresh_token), refresh_expiry())
+        .await?;
+    if !rotated {
+        return Err(unauthorized("Refresh token reuse detected"));
+    }
+
+    let token = access_token_for(&state, &user, &session_id)?;
+
+    Ok(Json(TokenPair {
+        token,
+        refresh_token,
+        expires_in: ACCESS_TOKEN_TTL_MINUTES * 60,
+    }))
+}
+
+// GET /api/v1/auth/sessions - Authenticated: list the caller's active sessions
+#[utoipa::path(
+    get, path = "/api/v1/auth/sessions", tag = "auth", security(("bearer" = [])),
+    responses(
+        (status = 200, body = Object, example = json!([
+            {
+                "current": true,
+                "id": "507f1f77bcf86cd799439011",
+                "user_agent": "Mozilla/5.0",
+                "created_at": "2025-11-16T14:30:00Z",
+                "last_used_at": "2025-11-16T15:00:00Z",
+                "expires_at": "2025-12-16T15:00:00Z",
+                "impersonator_id": null
+            }
+        ]))
+    )
+)]
+pub async fn get_sessions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<serde_json::Value>>> {
+    let sessions = state.sessions.list_active(auth_user.id).await?;
+
+    let sessions = sessions
+        .into_iter()
+        .map(|session| {
+            serde_json::json!({
+                "current": session.id.is_some() && session.id == auth_user.session_id,
+                "id": session.id.map(|id| id.to_hex()),
+                "user_agent": session.user_agent,
+                "created_at": session.created_at.try_to_rfc3339_string().ok(),
+                "last_used_at": session.last_used_at.try_to_rfc3339_string().ok(),
+                "expires_at": session.expires_at.try_to_rfc3339_string().ok(),
+                "impersonator_id": session.impersonator_id.map(|id| id.to_hex()),
+            })
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+// POST /api/v1/auth/logout - Authenticated: end the current session, one session, or all
+#[utoipa::path(
+    post, path = "/api/v1/auth/logout", tag = "auth", security(("bearer" = [])),
+    request_body = Option<LogoutRequest>,
+    responses((status = 200, body = Object, example = json!({"success": true, "revoked": 1})))
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    payload: Option<ValidJson<LogoutRequest>>,
+) -> AppResult<Json<serde_json::Value>> {
+    let payload = payload.map(|ValidJson(p)| p).unwrap_or_default();
+
+    // Access tokens are revoked under /auth/tokens, they have no session to end
+    let Some(current_session_id) = auth_user.session_id else {
+        return Err(access_token_forbidden());
+    };
+
+    // An impersonating admin can end the impersonation but not the user's own sessions
+    if auth_user.impersonator_id.is_some() && (payload.all || payload.session_id.is_some()) {
+        return Err(impersonation_forbidden());
+    }
+
+    let revoked = if payload.all {
+        revoke_user_sessions(&state, auth_user.id).await?
+    } else {
+        let session_id = match payload.session_id.as_deref() {
+            None => current_session_id,
+            Some(id) => parse_id(id, "session ID")?,
+        };
+        // Scoped to the caller so nobody can log out someone else's session
+        state.sessions.revoke(session_id, Some(auth_user.id)).await?
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "revoked": revoked
+    })))
+}
+
+// DELETE /api/v1/users/{id}/sessions - Admin: revoke every session and access token of a (compromised) account
+#[utoipa::path(
+    delete, path = "/api/v1/users/{id}/sessions", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "revoked": 2, "revoked_tokens": 1}))
+    )
+)]
+pub async fn force_logout_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let user_id = parse_id(&user_id, "user ID")?;
+
+    let revoked = revoke_user_sessions(&state, user_id).await?;
+    let revoked_tokens = access_tokens::revoke_user_tokens(&state, user_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "revoked": revoked,
+        "revoked_tokens": revoked_tokens
+    })))
+}
@@ -0,0 +1,362 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::bson::{doc, DateTime};
+use rand::RngCore;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+use totp_rs::{Algorithm, TOTP};
+
+use crate::auth::sessions::{create_session, generate_token, hash_token};
+use crate::auth::{login_response, user_agent};
+use crate::db::AppState;
+use crate::error::{found, AppError, AppResult, ErrorBody};
+use crate::openapi::SignIn;
+use crate::middleware::auth::{two_factor_enforced, AuthUser};
+use crate::models::{TwoFactor, TwoFactorChallenge, User};
+use crate::repo::RepoResult;
+use crate::validate::ValidJson;
+
+const ISSUER: &str = "IRIS";
+const STEP_SECONDS: u64 = 30;
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CodeRequest {
+    pub code: String, // 6-digit TOTP code or a recovery code
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct VerifyRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+fn invalid_code() -> AppError {
+    AppError::Unauthorized("Invalid two-factor code".to_string()).with_code("invalid_two_factor_code")
+}
+
+fn expired_challenge() -> AppError {
+    AppError::Unauthorized("Invalid or expired two-factor challenge, please login again".to_string())
+        .with_code("invalid_challenge")
+}
+
+fn totp_for(secret: &str, account: &str) -> Option<TOTP> {
+    let secret = totp_rs::Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    // Skew is handled in `matching_step` so we know which step a code belongs to
+    TOTP::new(Algorithm::SHA1, 6, 0, STEP_SECONDS, secret, Some(ISSUER.to_string()), account.to_string()).ok()
+}
+
+// The 30s step a code was generated for, allowing one step of clock drift either way
+fn matching_step(totp: &TOTP, code: &str) -> Option<i64> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let current = now / STEP_SECONDS;
+    [current - 1, current, current + 1]
+        .into_iter()
+        .find(|step| totp.check(code, step * STEP_SECONDS))
+        .map(|step| step as i64)
+}
+
+// "a1b2c-3d4e5": 40 bits each, shown once and stored hashed
+fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            let code = hex::encode(bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+// Accept a TOTP code (each step once) or burn a recovery code. Both updates are
+// guarded so concurrent requests can't use the same code twice.
+async fn use_code(state: &AppState, user: &User, code: &str) -> RepoResult<bool> {
+    let (Some(two_factor), Some(user_id)) = (user.two_factor.as_ref(), user.id) else {
+        return Ok(false);
+    };
+
+    let code = code.trim();
+    let step = totp_for(&two_factor.secret, &user.email).and_then(|totp| matching_step(&totp, code));
+    if let Some(step) = step {
+        return state.users.use_totp_step(user_id, step).await;
+    }
+
+    let code_hash = hash_token(&normalize_recovery_code(code));
+    state.users.use_recovery_code(user_id, &code_hash).await
+}
+
+async fn current_user(state: &AppState, auth_user: &AuthUser) -> AppResult<User> {
+    found(state.users.get(auth_user.id).await?, "User")
+}
+
+// Rejects a code that doesn't verify; database failures surface as 500s
+async fn require_code(state: &AppState, user: &User, code: &str) -> AppResult<()> {
+    if use_code(state, user, code).await? {
+        Ok(())
+    } else {
+        Err(invalid_code())
+    }
+}
+
+// Issued by `issue_session` instead of tokens when the user has 2FA enabled
+pub(crate) async fn start_challenge(state: &AppState, user: &User, user_agent: Option<String>) -> AppResult<Response> {
+    let user_id = user.id.ok_or_else(|| AppError::Internal("User without an id".to_string()))?;
+    let token = generate_token();
+    let now = chrono::Utc::now();
+    let challenge = TwoFactorChallenge {
+        id: None,
+        user_id,
+        token_hash: hash_token(&token),
+        user_agent,
+        attempts: 0,
+        created_at: DateTime::from_millis(now.timestamp_millis()),
+        expires_at: DateTime::from_millis(
+            (now + chrono::Duration::minutes(CHALLENGE_TTL_MINUTES)).timestamp_millis(),
+        ),
+    };
+
+    state.two_factor_challenges.insert(&challenge).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": false,
+        "two_factor_required": true,
+        "challenge_token": token,
+        "expires_in": CHALLENGE_TTL_MINUTES * 60
+    })).into_response())
+}
+
+// POST /api/v1/auth/2fa/verify - Public: second login step, trade a challenge and code for tokens
+#[utoipa::path(
+    post, path = "/api/v1/auth/2fa/verify", tag = "two-factor",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, response = SignIn),
+        (status = 401, description = "Invalid code or challenge", body = ErrorBody)
+    )
+)]
+pub async fn verify_challenge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<VerifyRequest>,
+) -> AppResult<Response> {
+    // Count the attempt up front so parallel guesses can't exceed the limit
+    let challenge = state.two_factor_challenges
+        .record_attempt(&hash_token(&payload.challenge_token), MAX_CHALLENGE_ATTEMPTS)
+        .await?
+        .ok_or_else(expired_challenge)?;
+    let challenge_id = challenge.id.ok_or_else(expired_challenge)?;
+
+    let user = state.users
+        .get(challenge.user_id)
+        .await?
+        .ok_or_else(expired_challenge)?;
+
+    require_code(&state, &user, &payload.code).await?;
+
+    // Single use: a second verify with the same challenge must fail
+    if !state.two_factor_challenges.delete(challenge_id).await? {
+        return Err(expired_challenge());
+    }
+
+    let agent = challenge.user_agent.or_else(|| user_agent(&headers));
+    let tokens = create_session(&state, &user, agent, true).await?;
+    Ok(login_response(&user, tokens))
+}
+
+// GET /api/v1/auth/2fa - Protected: 2FA state of the current user
+#[utoipa::path(
+    get, path = "/api/v1/auth/2fa", tag = "two-factor", security(("bearer" = [])),
+    responses(
+        (status = 200, body = Object, example = json!({"enabled": true, "recovery_codes_remaining": 8, "required": false}))
+    )
+)]
+pub async fn get_two_factor_status(State(state): State<AppState>, auth_user: AuthUser) -> AppResult<Json<serde_json::Value>> {
+    let user = current_user(&state, &auth_user).await?;
+
+    Ok(Json(serde_json::json!({
+        "enabled": user.two_factor_enabled(),
+        "recovery_codes_remaining": user.two_factor
+            .as_ref()
+            .filter(|two_factor| two_factor.enabled)
+            .map_or(0, |two_factor| two_factor.recovery_code_hashes.len()),
+        "required": two_factor_enforced(&state, &user.role),
+        "session_verified": auth_user.two_factor_verified
+    })))
+}
+
+// POST /api/v1/auth/2fa/enroll - Protected: create a new secret; 2FA stays off until confirmed
+#[utoipa::path(
+    post, path = "/api/v1/auth/2fa/enroll", tag = "two-factor", security(("bearer" = [])),
+    responses(
+        (status = 200, body = Object, example = json!({
+            "secret": "JBSWY3DPEHPK3PXP",
+            "provisioning_uri": "otpauth://totp/IRIS:johndoe?secret=JBSWY3DPEHPK3PXP&issuer=IRIS",
+            "message": "Scan the URI as a QR code, then confirm with a code from the app"
+        }))
+    )
+)]
+pub async fn enroll(State(state): State<AppState>, auth_user: AuthUser) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+    let user = current_user(&state, &auth_user).await?;
+
+    if user.two_factor_enabled() {
+        return Err(AppError::Conflict(
+            "Two-factor authentication is already enabled, disable it first to enroll a new authenticator".to_string(),
+        ));
+    }
+
+    let mut secret = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let secret = totp_rs::Secret::Raw(secret.to_vec()).to_encoded().to_string();
+
+    let totp = totp_for(&secret, &user.email)
+        .ok_or_else(|| AppError::Internal("Failed to create authenticator secret".to_string()))?;
+
+    let pending = TwoFactor {
+        secret: secret.clone(),
+        enabled: false,
+        recovery_code_hashes: Vec::new(),
+        last_used_step: None,
+        enabled_at: None,
+    };
+    state.users.start_two_factor(auth_user.id, &pending).await?;
+
+    Ok(Json(serde_json::json!({
+        "secret": secret,
+        "provisioning_uri": totp.get_url(),
+        "message": "Scan the URI as a QR code, then confirm with a code from the app"
+    })))
+}
+
+// POST /api/v1/auth/2fa/confirm - Protected: prove the authenticator works, turn 2FA on, get recovery codes
+#[utoipa::path(
+    post, path = "/api/v1/auth/2fa/confirm", tag = "two-factor", security(("bearer" = [])),
+    request_body = CodeRequest,
+    responses(
+        (status = 200, body = Object, example = json!({
+            "success": true,
+            "recovery_codes": ["a1b2c-3d4e5"],
+            "message": "Store these recovery codes somewhere safe; each works once and they won't be shown again"
+        })),
+        (status = 401, description = "Invalid code", body = ErrorBody)
+    )
+)]
+pub async fn confirm(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CodeRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+    let user = current_user(&state, &auth_user).await?;
+
+    let pending = match user.two_factor.as_ref() {
+        Some(two_factor) if !two_factor.enabled => two_factor,
+        Some(_) => return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string())),
+        None => return Err(AppError::BadRequest("Start enrollment first".to_string())),
+    };
+
+    let step = totp_for(&pending.secret, &user.email)
+        .and_then(|totp| matching_step(&totp, payload.code.trim()))
+        .ok_or_else(invalid_code)?;
+
+    let recovery_codes = generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_token(code)).collect();
+
+    let enabled = state.users.enable_two_factor(auth_user.id, &pending.secret, &hashes, step).await?;
+    if !enabled {
+        return Err(invalid_code());
+    }
+
+    // The user just proved the second factor, so this session counts as verified
+    let upgraded = match auth_user.session_id {
+        Some(session_id) => state.sessions.mark_two_factor_verified(session_id).await,
+        None => Ok(()),
+    };
+    if let Err(e) = upgraded {
+        tracing::warn!(error = ?e, "failed to mark session as 2FA verified");
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "recovery_codes": recovery_codes,
+        "message": "Store these recovery codes somewhere safe; each works once and they won't be shown again"
+    })))
+}
+
+// POST /api/v1/auth/2fa/recovery-codes - Protected: replace all recovery codes (needs a current code)
+#[utoipa::path(
+    post, path = "/api/v1/auth/2fa/recovery-codes", tag = "two-factor", security(("bearer" = [])),
+    request_body = CodeRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "recovery_codes": ["a1b2c-3d4e5"]})),
+        (status = 401, description = "Invalid code", body = ErrorBody)
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CodeRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+    let user = current_user(&state, &auth_user).await?;
+
+    if !user.two_factor_enabled() {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".to_string()));
+    }
+
+    require_code(&state, &user, &payload.code).await?;
+
+    let recovery_codes = generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_token(code)).collect();
+    state.users.set_recovery_codes(auth_user.id, &hashes).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "recovery_codes": recovery_codes
+    })))
+}
+
+// POST /api/v1/auth/2fa/disable - Protected: turn 2FA off (needs a current code)
+#[utoipa::path(
+    post, path = "/api/v1/auth/2fa/disable", tag = "two-factor", security(("bearer" = [])),
+    request_body = CodeRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "Two-factor authentication disabled"})),
+        (status = 401, description = "Invalid code", body = ErrorBody)
+    )
+)]
+pub async fn disable(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CodeRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    auth_user.require_owner()?;
+    let user = current_user(&state, &auth_user).await?;
+
+    if user.two_factor.is_none() {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".to_string()));
+    }
+
+    // An unconfirmed enrollment can be dropped without a code
+    if user.two_factor_enabled() {
+        require_code(&state, &user, &payload.code).await?;
+    }
+
+    state.users.disable_two_factor(auth_user.id).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Two-factor authentication disabled"
+    })))
+}
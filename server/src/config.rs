@@ -0,0 +1,748 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+// Placeholders that ship in .env.example; never acceptable in production
+const DEFAULT_JWT_SECRET: &str = "your-secret-key-change-in-production";
+const DEFAULT_GITHUB_CLIENT_ID: &str = "your_github_client_id";
+const DEFAULT_GITHUB_CLIENT_SECRET: &str = "your_github_client_secret";
+const MIN_PRODUCTION_SECRET_LENGTH: usize = 32;
+
+// Read when neither --config nor CONFIG_FILE names a file
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// Everything the server can be configured with. Layered, later wins:
+// built-in defaults < config file (TOML) < environment variables < CLI flags.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub environment: Environment,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub github: GitHubConfig,
+    pub signup: SignupConfig,
+    pub mail: MailConfig,
+    pub log: LogConfig,
+    pub metrics: MetricsConfig,
+    pub events: EventsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub oidc: BTreeMap<String, OidcProviderConfig>,   // [oidc.google] etc.
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,   // BIND_ADDRESS, or just the port with PORT
+    pub frontend_url: String,   // FRONTEND_URL, used in emailed links
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub uri: String,                // MONGO_URI, required
+    pub name: String,               // DATABASE_NAME
+    pub migrate_on_startup: bool,   // MIGRATE_ON_STARTUP; otherwise run `server migrate up`
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,        // JWT_SECRET
+    pub require_admin_2fa: bool,   // REQUIRE_ADMIN_2FA
+    pub dev_mode: bool,            // DEV_MODE, only matters with the `dev-login` feature
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    pub client_id: String,      // GITHUB_CLIENT_ID
+    pub client_secret: String,  // GITHUB_CLIENT_SECRET
+    pub redirect_url: String,   // GITHUB_REDIRECT_URL
+    pub api_url: String,        // GITHUB_API_URL, point it at a mock GitHub in tests
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignupConfig {
+    pub github_orgs: Vec<String>,     // SIGNUP_GITHUB_ORGS=nst-sdc,other-org
+    pub github_teams: Vec<String>,    // SIGNUP_GITHUB_TEAMS=nst-sdc/core
+    pub email_domains: Vec<String>,   // SIGNUP_EMAIL_DOMAINS=college.edu
+    pub invite_only: bool,            // SIGNUP_INVITE_ONLY
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailerKind {
+    #[default]
+    Console,
+    File,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub mailer: MailerKind,   // MAILER
+    pub dir: String,          // MAIL_DIR, where the file mailer writes outbox.log
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: String,                // RUST_LOG, an EnvFilter directive like "info,server=debug"
+    pub format: Option<LogFormat>,    // LOG_FORMAT; defaults to json in production, text otherwise
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,           // METRICS_ENABLED, serves GET /metrics
+    pub token: Option<String>,   // METRICS_TOKEN, required as a bearer token on /metrics when set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    pub timezone: String,   // EVENT_TIMEZONE, IANA name used when an event doesn't give one
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    #[default]
+    Memory,   // Per process; each instance counts on its own
+    Mongo,    // Shared by every instance through the `rate_limits` collection
+}
+
+// `requests` per `per_seconds`, refilled evenly; up to `requests` at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    pub requests: u32,
+    pub per_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,                // RATE_LIMIT_ENABLED
+    pub backend: RateLimitBackend,    // RATE_LIMIT_BACKEND: "memory" or "mongo"
+    pub trusted_proxies: usize,       // RATE_LIMIT_TRUSTED_PROXIES, proxies in front that append to X-Forwarded-For
+    pub general: RateLimitPolicy,     // RATE_LIMIT_GENERAL=300/60, every API request, per IP
+    pub auth: RateLimitPolicy,        // RATE_LIMIT_AUTH=20/60, sign-in, signup and password resets, per IP
+    pub outreach: RateLimitPolicy,    // RATE_LIMIT_OUTREACH=30/3600, messages, event proposals and join requests, per user
+}
+
+// OIDC_PROVIDERS=google plus OIDC_GOOGLE_ISSUER, _CLIENT_ID, _CLIENT_SECRET,
+// _REDIRECT_URL, and optionally _DISPLAY_NAME and _SCOPES (space separated)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcProviderConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    pub display_name: Option<String>,
+    pub scopes: Option<Vec<String>>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: "0.0.0.0:5657".to_string(),
+            frontend_url: "http://localhost:3000".to_string(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            uri: String::new(),
+            name: "iris".to_string(),
+            migrate_on_startup: true,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            jwt_secret: DEFAULT_JWT_SECRET.to_string(),
+            require_admin_2fa: false,
+            dev_mode: false,
+        }
+    }
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        GitHubConfig {
+            client_id: DEFAULT_GITHUB_CLIENT_ID.to_string(),
+            client_secret: DEFAULT_GITHUB_CLIENT_SECRET.to_string(),
+            redirect_url: "http://localhost:5657/auth/github/callback".to_string(),
+            api_url: "https://api.github.com".to_string(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: "info".to_string(),
+            format: None,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: true,
+            token: None,
+        }
+    }
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            timezone: "UTC".to_string(),
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            backend: RateLimitBackend::Memory,
+            trusted_proxies: 0,
+            general: RateLimitPolicy { requests: 300, per_seconds: 60 },
+            auth: RateLimitPolicy { requests: 20, per_seconds: 60 },
+            outreach: RateLimitPolicy { requests: 30, per_seconds: 3600 },
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            mailer: MailerKind::Console,
+            dir: "mail".to_string(),
+        }
+    }
+}
+
+// Command line flags; each one overrides the file and the environment
+#[derive(Debug, Default, Parser)]
+#[command(name = "server", about = "IRIS API server")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// TOML config file (default: CONFIG_FILE, then ./config.toml if it exists)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    #[arg(long, value_enum, global = true)]
+    pub environment: Option<Environment>,
+
+    /// Address to listen on, e.g. 127.0.0.1:8080
+    #[arg(long, global = true)]
+    pub bind_address: Option<String>,
+
+    #[arg(long, global = true)]
+    pub mongo_uri: Option<String>,
+
+    #[arg(long, global = true)]
+    pub database_name: Option<String>,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Command {
+    /// Run the API server (the default)
+    Serve,
+    /// Inspect or apply database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Find users and projects that disagree about membership, and coin balances that disagree with the ledger
+    Check {
+        /// Fix what was found
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum MigrateAction {
+    /// List every migration and whether it is applied
+    Status,
+    /// Apply pending migrations
+    Up {
+        /// Stop after this version
+        #[arg(long)]
+        to: Option<u32>,
+    },
+    /// Roll back applied migrations
+    Down {
+        /// Roll back everything newer than this version (default: only the latest)
+        #[arg(long)]
+        to: Option<u32>,
+    },
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    File { path: PathBuf, message: String },
+    Env { var: String, message: String },
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::File { path, message } => write!(f, "config file {}: {}", path.display(), message),
+            ConfigError::Env { var, message } => write!(f, "environment variable {}: {}", var, message),
+            ConfigError::Invalid(problems) => {
+                writeln!(f, "invalid configuration:")?;
+                for problem in problems {
+                    writeln!(f, "  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn env_bool(var: &str, value: &str) -> Result<bool, ConfigError> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" | "" => Ok(false),
+        _ => Err(ConfigError::Env { var: var.to_string(), message: format!("expected true or false, got {:?}", value) }),
+    }
+}
+
+// "20/60": 20 requests per 60 seconds
+fn env_rate_limit(var: &str, value: &str) -> Result<RateLimitPolicy, ConfigError> {
+    value
+        .split_once('/')
+        .and_then(|(requests, per_seconds)| {
+            Some(RateLimitPolicy { requests: requests.trim().parse().ok()?, per_seconds: per_seconds.trim().parse().ok()? })
+        })
+        .ok_or_else(|| ConfigError::Env {
+            var: var.to_string(),
+            message: format!("expected requests/seconds like 20/60, got {:?}", value),
+        })
+}
+
+fn env_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl Config {
+    // Load all layers from the real process: parsed CLI args, env (after .env) and the config file
+    pub fn load(cli: &Cli) -> Result<Config, ConfigError> {
+        Config::load_from(cli, |name| std::env::var(name).ok())
+    }
+
+    pub fn load_from(cli: &Cli, env: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let path = cli.config.clone().or_else(|| env("CONFIG_FILE").map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Config::from_file(&path)?,
+            None if std::path::Path::new(DEFAULT_CONFIG_FILE).exists() => Config::from_file(DEFAULT_CONFIG_FILE.as_ref())?,
+            None => Config::default(),
+        };
+
+        config.apply_env(&env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &std::path::Path) -> Result<Config, ConfigError> {
+        let file_error = |message: String| ConfigError::File { path: path.to_path_buf(), message };
+        let contents = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        toml::from_str(&contents).map_err(|e| file_error(e.to_string()))
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let string = |var: &str, target: &mut String| {
+            if let Some(value) = env(var) {
+                *target = value;
+            }
+        };
+        let boolean = |var: &str, target: &mut bool| -> Result<(), ConfigError> {
+            if let Some(value) = env(var) {
+                *target = env_bool(var, &value)?;
+            }
+            Ok(())
+        };
+        let list = |var: &str, target: &mut Vec<String>| {
+            if let Some(value) = env(var) {
+                *target = env_list(&value);
+            }
+        };
+
+        if let Some(value) = env("APP_ENV") {
+            self.environment = match value.trim().to_lowercase().as_str() {
+                "development" | "dev" => Environment::Development,
+                "production" | "prod" => Environment::Production,
+                _ => {
+                    return Err(ConfigError::Env {
+                        var: "APP_ENV".to_string(),
+                        message: format!("expected development or production, got {:?}", value),
+                    });
+                }
+            };
+        }
+
+        string("BIND_ADDRESS", &mut self.server.bind_address);
+        // Hosting platforms hand out only a port; keep the configured host
+        if let Some(port) = env("PORT") {
+            let port: u16 = port.trim().parse().map_err(|_| ConfigError::Env {
+                var: "PORT".to_string(),
+                message: format!("expected a port number, got {:?}", port),
+            })?;
+            let host = self.server.bind_address
+                .rsplit_once(':')
+                .map_or("0.0.0.0", |(host, _)| host)
+                .to_string();
+            self.server.bind_address = format!("{}:{}", host, port);
+        }
+        string("FRONTEND_URL", &mut self.server.frontend_url);
+
+        string("MONGO_URI", &mut self.database.uri);
+        string("DATABASE_NAME", &mut self.database.name);
+        boolean("MIGRATE_ON_STARTUP", &mut self.database.migrate_on_startup)?;
+
+        string("JWT_SECRET", &mut self.auth.jwt_secret);
+        boolean("REQUIRE_ADMIN_2FA", &mut self.auth.require_admin_2fa)?;
+        boolean("DEV_MODE", &mut self.auth.dev_mode)?;
+
+        string("GITHUB_CLIENT_ID", &mut self.github.client_id);
+        string("GITHUB_CLIENT_SECRET", &mut self.github.client_secret);
+        string("GITHUB_REDIRECT_URL", &mut self.github.redirect_url);
+        string("GITHUB_API_URL", &mut self.github.api_url);
+
+        list("SIGNUP_GITHUB_ORGS", &mut self.signup.github_orgs);
+        list("SIGNUP_GITHUB_TEAMS", &mut self.signup.github_teams);
+        list("SIGNUP_EMAIL_DOMAINS", &mut self.signup.email_domains);
+        boolean("SIGNUP_INVITE_ONLY", &mut self.signup.invite_only)?;
+
+        if let Some(mailer) = env("MAILER") {
+            self.mail.mailer = match mailer.trim() {
+                "console" | "" => MailerKind::Console,
+                "file" => MailerKind::File,
+                _ => {
+                    return Err(ConfigError::Env {
+                        var: "MAILER".to_string(),
+                        message: format!("expected console or file, got {:?}", mailer),
+                    });
+                }
+            };
+        }
+        string("MAIL_DIR", &mut self.mail.dir);
+
+        string("RUST_LOG", &mut self.log.level);
+        if let Some(format) = env("LOG_FORMAT") {
+            self.log.format = match format.trim() {
+                "json" => Some(LogFormat::Json),
+                "text" => Some(LogFormat::Text),
+                _ => {
+                    return Err(ConfigError::Env {
+                        var: "LOG_FORMAT".to_string(),
+                        message: format!("expected json or text, got {:?}", format),
+                    });
+                }
+            };
+        }
+
+        boolean("METRICS_ENABLED", &mut self.metrics.enabled)?;
+        if let Some(token) = env("METRICS_TOKEN") {
+            self.metrics.token = Some(token).filter(|token| !token.is_empty());
+        }
+
+        string("EVENT_TIMEZONE", &mut self.events.timezone);
+
+        boolean("RATE_LIMIT_ENABLED", &mut self.rate_limit.enabled)?;
+        if let Some(backend) = env("RATE_LIMIT_BACKEND") {
+            self.rate_limit.backend = match backend.trim() {
+                "memory" | "" => RateLimitBackend::Memory,
+                "mongo" => RateLimitBackend::Mongo,
+                _ => {
+                    return Err(ConfigError::Env {
+                        var: "RATE_LIMIT_BACKEND".to_string(),
+                        message: format!("expected memory or mongo, got {:?}", backend),
+                    });
+                }
+            };
+        }
+        if let Some(proxies) = env("RATE_LIMIT_TRUSTED_PROXIES") {
+            self.rate_limit.trusted_proxies = proxies.trim().parse().map_err(|_| ConfigError::Env {
+                var: "RATE_LIMIT_TRUSTED_PROXIES".to_string(),
+                message: format!("expected a number of proxies, got {:?}", proxies),
+            })?;
+        }
+        for (var, policy) in [
+            ("RATE_LIMIT_GENERAL", &mut self.rate_limit.general),
+            ("RATE_LIMIT_AUTH", &mut self.rate_limit.auth),
+            ("RATE_LIMIT_OUTREACH", &mut self.rate_limit.outreach),
+        ] {
+            if let Some(value) = env(var) {
+                *policy = env_rate_limit(var, &value)?;
+            }
+        }
+
+        // Providers named in OIDC_PROVIDERS are merged over any from the file
+        for name in env("OIDC_PROVIDERS").map(|names| env_list(&names)).unwrap_or_default() {
+            let name = name.to_lowercase();
+            let prefix = format!("OIDC_{}_", name.to_uppercase().replace('-', "_"));
+            let var = |key: &str| env(&format!("{}{}", prefix, key));
+            let provider = self.oidc.entry(name).or_default();
+            if let Some(value) = var("ISSUER") { provider.issuer = value; }
+            if let Some(value) = var("CLIENT_ID") { provider.client_id = value; }
+            if let Some(value) = var("CLIENT_SECRET") { provider.client_secret = value; }
+            if let Some(value) = var("REDIRECT_URL") { provider.redirect_url = value; }
+            if let Some(value) = var("DISPLAY_NAME") { provider.display_name = Some(value); }
+            if let Some(value) = var("SCOPES") {
+                provider.scopes = Some(value.split_whitespace().map(String::from).collect());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(environment) = cli.environment {
+            self.environment = environment;
+        }
+        if let Some(bind_address) = &cli.bind_address {
+            self.server.bind_address = bind_address.clone();
+        }
+        if let Some(uri) = &cli.mongo_uri {
+            self.database.uri = uri.clone();
+        }
+        if let Some(name) = &cli.database_name {
+            self.database.name = name.clone();
+        }
+    }
+
+    pub fn is_production(&self) -> bool {
+        self.environment == Environment::Production
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log.format.unwrap_or(if self.is_production() { LogFormat::Json } else { LogFormat::Text })
+    }
+
+    // Every problem at once, so a bad deploy is fixed in one go
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.server.bind_address.parse::<SocketAddr>().is_err() {
+            problems.push(format!("server.bind_address {:?} is not an address like 0.0.0.0:5657", self.server.bind_address));
+        }
+        if self.database.uri.trim().is_empty() {
+            problems.push("database.uri (MONGO_URI) is required".to_string());
+        }
+        if tracing_subscriber::EnvFilter::try_new(&self.log.level).is_err() {
+            problems.push(format!("log.level (RUST_LOG) {:?} is not a valid filter like \"info\" or \"info,server=debug\"", self.log.level));
+        }
+        if self.database.name.trim().is_empty() {
+            problems.push("database.name (DATABASE_NAME) must not be empty".to_string());
+        }
+        if self.auth.jwt_secret.is_empty() {
+            problems.push("auth.jwt_secret (JWT_SECRET) must not be empty".to_string());
+        }
+        if self.events.timezone.parse::<chrono_tz::Tz>().is_err() {
+            problems.push(format!("events.timezone (EVENT_TIMEZONE) {:?} is not an IANA time zone like \"Asia/Kolkata\"", self.events.timezone));
+        }
+        for (name, policy) in [
+            ("general", self.rate_limit.general),
+            ("auth", self.rate_limit.auth),
+            ("outreach", self.rate_limit.outreach),
+        ] {
+            if policy.requests == 0 || policy.per_seconds == 0 {
+                problems.push(format!("rate_limit.{} must allow at least 1 request per at least 1 second", name));
+            }
+        }
+        for team in &self.signup.github_teams {
+            if team.split_once('/').is_none_or(|(org, slug)| org.is_empty() || slug.is_empty()) {
+                problems.push(format!("signup.github_teams entry {:?} must look like org/team", team));
+            }
+        }
+        for (name, provider) in &self.oidc {
+            if !crate::auth::oidc::valid_provider_name(name) {
+                problems.push(format!("oidc provider name {:?} is reserved or not lowercase letters, digits and dashes", name));
+            }
+            let missing: Vec<&str> = [
+                ("issuer", &provider.issuer),
+                ("client_id", &provider.client_id),
+                ("client_secret", &provider.client_secret),
+                ("redirect_url", &provider.redirect_url),
+            ]
+            .into_iter()
+            .filter(|(_, value)| value.is_empty())
+            .map(|(key, _)| key)
+            .collect();
+            if !missing.is_empty() {
+                problems.push(format!("oidc.{} is missing {}", name, missing.join(", ")));
+            }
+        }
+
+        if self.is_production() {
+            if self.auth.jwt_secret == DEFAULT_JWT_SECRET {
+                problems.push("auth.jwt_secret (JWT_SECRET) is still the default; generate one with `openssl rand -base64 32`".to_string());
+            } else if self.auth.jwt_secret.len() < MIN_PRODUCTION_SECRET_LENGTH {
+                problems.push(format!("auth.jwt_secret (JWT_SECRET) must be at least {} characters in production", MIN_PRODUCTION_SECRET_LENGTH));
+            }
+            if self.github.client_id == DEFAULT_GITHUB_CLIENT_ID || self.github.client_secret == DEFAULT_GITHUB_CLIENT_SECRET {
+                problems.push("github.client_id and github.client_secret (GITHUB_CLIENT_ID/SECRET) are still the defaults".to_string());
+            }
+            if self.auth.dev_mode {
+                problems.push("auth.dev_mode (DEV_MODE) must be off in production".to_string());
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn no_file() -> Cli {
+        // Point at /dev/null so a stray ./config.toml can't leak into the tests
+        Cli { config: Some(PathBuf::from("/dev/null")), ..Cli::default() }
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let cli = Cli { database_name: Some("iris-cli".to_string()), ..no_file() };
+        let config = Config::load_from(&cli, env(&[
+            ("MONGO_URI", "mongodb://localhost"),
+            ("DATABASE_NAME", "iris-env"),
+            ("PORT", "8080"),
+            ("SIGNUP_GITHUB_TEAMS", "nst-sdc/core, nst-sdc/robotics"),
+        ])).unwrap();
+
+        assert_eq!(config.database.name, "iris-cli");
+        assert_eq!(config.server.bind_address, "0.0.0.0:8080");
+        assert_eq!(config.signup.github_teams, vec!["nst-sdc/core", "nst-sdc/robotics"]);
+        assert_eq!(config.github.api_url, "https://api.github.com");
+    }
+
+    #[test]
+    fn file_values_are_overridden_by_env() {
+        let mut config: Config = toml::from_str(r#"
+            environment = "production"
+            [database]
+            uri = "mongodb://file"
+            [oidc.college]
+            issuer = "https://login.college.edu"
+        "#).unwrap();
+        config.apply_env(&env(&[("MONGO_URI", "mongodb://env"), ("OIDC_PROVIDERS", "college"), ("OIDC_COLLEGE_CLIENT_ID", "iris")])).unwrap();
+
+        assert!(config.is_production());
+        assert_eq!(config.database.uri, "mongodb://env");
+        assert_eq!(config.oidc["college"].issuer, "https://login.college.edu");
+        assert_eq!(config.oidc["college"].client_id, "iris");
+    }
+
+    #[test]
+    fn production_refuses_default_secrets() {
+        let Err(ConfigError::Invalid(problems)) = Config::load_from(&no_file(), env(&[
+            ("APP_ENV", "production"),
+            ("MONGO_URI", "mongodb://localhost"),
+            ("DEV_MODE", "true"),
+        ])) else {
+            panic!("production with default secrets must not load");
+        };
+        assert!(problems.iter().any(|problem| problem.contains("JWT_SECRET")));
+        assert!(problems.iter().any(|problem| problem.contains("GITHUB_CLIENT_ID")));
+        assert!(problems.iter().any(|problem| problem.contains("DEV_MODE")));
+
+        let config = Config::load_from(&no_file(), env(&[
+            ("APP_ENV", "production"),
+            ("MONGO_URI", "mongodb://localhost"),
+            ("JWT_SECRET", "k4D9x0bq7Zr2LmW8vN3pT6yH1sJ5cF0e"),
+            ("GITHUB_CLIENT_ID", "Iv1.abc"),
+            ("GITHUB_CLIENT_SECRET", "0123456789abcdef"),
+        ]));
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_values_with_their_source() {
+        let error = Config::load_from(&no_file(), env(&[("REQUIRE_ADMIN_2FA", "sometimes")])).unwrap_err();
+        assert!(error.to_string().contains("REQUIRE_ADMIN_2FA"));
+
+        let Err(ConfigError::Invalid(problems)) = Config::load_from(&no_file(), env(&[("BIND_ADDRESS", "localhost")])) else {
+            panic!("missing MONGO_URI and a bad address must not load");
+        };
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn reads_rate_limits() {
+        let config = Config::load_from(&no_file(), env(&[
+            ("MONGO_URI", "mongodb://localhost"),
+            ("RATE_LIMIT_BACKEND", "mongo"),
+            ("RATE_LIMIT_TRUSTED_PROXIES", "1"),
+            ("RATE_LIMIT_OUTREACH", "5 / 600"),
+        ])).unwrap();
+        assert_eq!(config.rate_limit.backend, RateLimitBackend::Mongo);
+        assert_eq!(config.rate_limit.trusted_proxies, 1);
+        assert_eq!(config.rate_limit.outreach, RateLimitPolicy { requests: 5, per_seconds: 600 });
+        assert_eq!(config.rate_limit.auth, RateLimitConfig::default().auth);
+
+        let error = Config::load_from(&no_file(), env(&[("RATE_LIMIT_AUTH", "20")])).unwrap_err();
+        assert!(error.to_string().contains("RATE_LIMIT_AUTH"));
+
+        let Err(ConfigError::Invalid(problems)) = Config::load_from(&no_file(), env(&[
+            ("MONGO_URI", "mongodb://localhost"),
+            ("RATE_LIMIT_GENERAL", "0/60"),
+        ])) else {
+            panic!("a limit that allows nothing must not load");
+        };
+        assert!(problems[0].contains("rate_limit.general"));
+    }
+}
@@ -0,0 +1,189 @@
+use futures_util::stream::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::db::AppState;
+use crate::models::{Project, User};
+
+// Drift between collections that writes made before transactions could leave
+// behind. `Project.member_ids` is the source of truth for membership: every
+// membership write touched the project first, so the user side is what got lost.
+// The coin ledger is the source of truth for balances, since a transaction could
+// be recorded without its balance change.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub missing_project_links: Vec<(ObjectId, ObjectId)>,   // (user, project): member without the project in project_ids
+    pub stale_project_links: Vec<(ObjectId, ObjectId)>,     // (user, project): project_ids entry the project doesn't list
+    pub dangling_members: Vec<(ObjectId, ObjectId)>,        // (project, user): member_ids entry for a deleted user
+    pub coin_drift: Vec<CoinDrift>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CoinDrift {
+    pub user_id: ObjectId,
+    pub balance: i64,
+    pub ledger: i64,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        *self == Report::default()
+    }
+}
+
+pub async fn check(state: &AppState) -> mongodb::error::Result<Report> {
+    let projects: BTreeMap<ObjectId, Vec<ObjectId>> = state.database.collection::<Project>("projects")
+        .find(doc! {})
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?
+        .into_iter()
+        .filter_map(|project| Some((project.id?, project.member_ids.unwrap_or_default())))
+        .collect();
+    let users: Vec<User> = state.database.collection("users").find(doc! {}).await?.try_collect().await?;
+
+    let user_projects: BTreeMap<ObjectId, Vec<ObjectId>> = users
+        .iter()
+        .filter_map(|user| Some((user.id?, user.project_ids.clone().unwrap_or_default())))
+        .collect();
+    let mut report = membership_drift(&projects, &user_projects);
+
+    let mut ledger: BTreeMap<ObjectId, i64> = BTreeMap::new();
+    let mut totals = state.database.collection::<Document>("coin_transactions")
+        .aggregate(vec![doc! { "$group": { "_id": "$user_id", "total": { "$sum": "$amount" } } }])
+        .await?;
+    while let Some(total) = totals.try_next().await? {
+        if let (Ok(user_id), Some(sum)) = (total.get_object_id("_id"), number(&total, "total")) {
+            ledger.insert(user_id, sum);
+        }
+    }
+    let balances: BTreeMap<ObjectId, i64> = users
+        .iter()
+        .filter_map(|user| Some((user.id?, user.coins as i64)))
+        .collect();
+    report.coin_drift = coin_drift(&balances, &ledger);
+
+    Ok(report)
+}
+
+// `$sum` gives an int32 or an int64 depending on the size of the total
+fn number(document: &Document, key: &str) -> Option<i64> {
+    document.get_i64(key).ok().or_else(|| document.get_i32(key).ok().map(i64::from))
+}
+
+fn membership_drift(projects: &BTreeMap<ObjectId, Vec<ObjectId>>, users: &BTreeMap<ObjectId, Vec<ObjectId>>) -> Report {
+    let mut report = Report::default();
+
+    for (&project_id, members) in projects {
+        for &member_id in members.iter().collect::<BTreeSet<_>>() {
+            match users.get(&member_id) {
+                None => report.dangling_members.push((project_id, member_id)),
+                Some(project_ids) if !project_ids.contains(&project_id) => {
+                    report.missing_project_links.push((member_id, project_id))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    for (&user_id, project_ids) in users {
+        for &project_id in project_ids.iter().collect::<BTreeSet<_>>() {
+            if projects.get(&project_id).is_none_or(|members| !members.contains(&user_id)) {
+                report.stale_project_links.push((user_id, project_id));
+            }
+        }
+    }
+    report
+}
+
+// Users without a single transaction are left alone; their balance predates the ledger
+fn coin_drift(balances: &BTreeMap<ObjectId, i64>, ledger: &BTreeMap<ObjectId, i64>) -> Vec<CoinDrift> {
+    balances
+        .iter()
+        .filter_map(|(&user_id, &balance)| {
+            let ledger = *ledger.get(&user_id)?;
+            (balance != ledger).then_some(CoinDrift { user_id, balance, ledger })
+        })
+        .collect()
+}
+
+// Applies the fixes `check` found. Each fix is a single-document update, so a
+// repair cut short can simply be run again.
+pub async fn repair(state: &AppState, report: &Report) -> mongodb::error::Result<()> {
+    let users = state.database.collection::<Document>("users");
+    let projects = state.database.collection::<Document>("projects");
+    let now = DateTime::now();
+    for &(user_id, project_id) in &report.missing_project_links {
+        users
+            .update_one(
+                doc! { "_id": user_id },
+                doc! { "$addToSet": { "project_ids": project_id }, "$set": { "updated_at": now } },
+            )
+            .await?;
+    }
+    for &(user_id, project_id) in &report.stale_project_links {
+        users
+            .update_one(
+                doc! { "_id": user_id },
+                doc! { "$pull": { "project_ids": project_id }, "$set": { "updated_at": now } },
+            )
+            .await?;
+    }
+    for &(project_id, user_id) in &report.dangling_members {
+        projects
+            .update_one(
+                doc! { "_id": project_id },
+                doc! { "$pull": { "member_ids": user_id }, "$set": { "updated_at": now } },
+            )
+            .await?;
+    }
+    for drift in &report.coin_drift {
+        let Ok(ledger) = i32::try_from(drift.ledger) else {
+            tracing::warn!(user_id = %drift.user_id, ledger = drift.ledger, "coin ledger total doesn't fit a balance, skipping");
+            continue;
+        };
+        // Only if the balance hasn't moved since the check
+        users
+            .update_one(
+                doc! { "_id": drift.user_id, "coins": drift.balance },
+                doc! { "$set": { "coins": ledger, "updated_at": now } },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_members_decide_membership() {
+        let (alice, bob, ghost) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let (rover, drone, deleted) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+
+        let projects = BTreeMap::from([(rover, vec![alice, bob, ghost]), (drone, vec![alice])]);
+        let users = BTreeMap::from([(alice, vec![rover, drone]), (bob, vec![drone, deleted])]);
+
+        let report = membership_drift(&projects, &users);
+        assert_eq!(report.missing_project_links, vec![(bob, rover)]);
+        assert_eq!(report.dangling_members, vec![(rover, ghost)]);
+
+        let mut stale = report.stale_project_links.clone();
+        stale.sort();
+        let mut expected = vec![(bob, drone), (bob, deleted)];
+        expected.sort();
+        assert_eq!(stale, expected);
+    }
+
+    #[test]
+    fn balances_follow_the_ledger() {
+        let (paid, drifted, legacy) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let balances = BTreeMap::from([(paid, 50), (drifted, 20), (legacy, 100)]);
+        let ledger = BTreeMap::from([(paid, 50), (drifted, 35)]);
+
+        assert_eq!(
+            coin_drift(&balances, &ledger),
+            vec![CoinDrift { user_id: drifted, balance: 20, ledger: 35 }]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use futures_util::stream::TryStreamExt;
+use mongodb::bson::{doc, Bson};
+
+use super::{Migration, MigrationError};
+use crate::auth::github;
+use crate::db::AppState;
+use crate::models::{LinkedIdentity, User};
+
+// Users created before `linked_identities` existed were matched by email. GitHub-only
+// accounts took their username from the GitHub login, so look that login up once to
+// get the immutable id. GitHub-only accounts that can't be resolved get an empty list
+// and `legacy_github_unresolved`, and only those can still be claimed by a verified
+// email at their next GitHub login.
+pub struct BackfillGitHubIdentities;
+
+#[async_trait]
+impl Migration for BackfillGitHubIdentities {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "backfill_github_identities"
+    }
+
+    async fn up(&self, state: &AppState) -> Result<(), MigrationError> {
+        let users = state.database.collection::<User>("users");
+        let mut cursor = users.find(doc! { "linked_identities": { "$exists": false } }).await?;
+
+        let mut linked = 0;
+        let mut skipped = 0;
+        while let Some(user) = cursor.try_next().await? {
+            let identities = if user.password_hash.is_empty() {
+                match state.github.user_by_login(&user.username).await {
+                    Ok(Some(github_user)) => vec![LinkedIdentity::new(
+                        github::PROVIDER,
+                        &github_user.id.to_string(),
+                        Some(github_user.login),
+                        None,
+                    )],
+                    Ok(None) => Vec::new(),
+                    Err(e) => {
+                        // Runs once, so a GitHub outage leaves the account to the email fallback
+                        tracing::warn!(username = %user.username, error = %e, "identity backfill: GitHub lookup failed");
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            if identities.is_empty() {
+                skipped += 1;
+            } else {
+                linked += 1;
+            }
+
+            let unresolved = identities.is_empty() && user.password_hash.is_empty();
+            let identities = identities
+                .iter()
+                .map(mongodb::bson::to_bson)
+                .collect::<Result<Vec<Bson>, _>>()
+                .map_err(|e| MigrationError::Failed(e.to_string()))?;
+            users
+                .update_one(
+                    doc! { "_id": user.id, "linked_identities": { "$exists": false } },
+                    doc! { "$set": { "linked_identities": identities, "legacy_github_unresolved": unresolved } },
+                )
+                .await?;
+        }
+
+        if linked + skipped > 0 {
+            tracing::info!(linked, skipped, "identity backfill: linked GitHub accounts");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use mongodb::{bson::{doc, Document}, options::IndexOptions, IndexModel};
+use std::time::Duration;
+
+use super::{Migration, MigrationError};
+use crate::db::{is_duplicate_key, AppState};
+
+// Mongo's code for dropping an index that isn't there
+const INDEX_NOT_FOUND: i32 = 27;
+
+// An index the app relies on, created by a migration and checked by GET /health/ready
+pub struct IndexSpec {
+    pub collection: &'static str,
+    pub keys: Document,
+    pub options: IndexOptions,
+}
+
+impl IndexSpec {
+    fn new(collection: &'static str, keys: Document, options: IndexOptions) -> Self {
+        IndexSpec { collection, keys, options }
+    }
+
+    // The name Mongo gives an index without an explicit one: `target_id_1_created_at_-1`
+    pub fn name(&self) -> String {
+        self.keys
+            .iter()
+            .map(|(field, direction)| format!("{}_{}", field, direction))
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+}
+
+fn unique() -> IndexOptions {
+    IndexOptions::builder().unique(true).build()
+}
+
+fn expire_at_date() -> IndexOptions {
+    IndexOptions::builder().expire_after(Duration::from_secs(0)).build()
+}
+
+fn unique_where(filter: Document) -> IndexOptions {
+    IndexOptions::builder().unique(true).partial_filter_expression(filter).build()
+}
+
+// Indexes that `db::connect` used to create on every startup
+fn initial() -> Vec<IndexSpec> {
+    vec![
+        // Pending OAuth states: unique per login attempt, swept by Mongo once expired
+        IndexSpec::new("oauth_states", doc! { "state": 1 }, unique()),
+        IndexSpec::new("oauth_states", doc! { "expires_at": 1 }, expire_at_date()),
+        // Sessions are looked up by refresh token and listed per user
+        IndexSpec::new("sessions", doc! { "refresh_token_hash": 1 }, unique()),
+        IndexSpec::new("sessions", doc! { "user_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("sessions", doc! { "expires_at": 1 }, expire_at_date()),
+        // Reset tokens are single-use lookups by hash and expire on their own
+        IndexSpec::new("password_resets", doc! { "token_hash": 1 }, unique()),
+        IndexSpec::new("password_resets", doc! { "expires_at": 1 }, expire_at_date()),
+        // One invite per email address
+        IndexSpec::new("invites", doc! { "email": 1 }, unique()),
+        // Second-step challenges are looked up by hash and expire on their own
+        IndexSpec::new("two_factor_challenges", doc! { "token_hash": 1 }, unique()),
+        IndexSpec::new("two_factor_challenges", doc! { "expires_at": 1 }, expire_at_date()),
+        // Audit entries are browsed newest first, overall or per user
+        IndexSpec::new("audit_log", doc! { "created_at": -1 }, IndexOptions::default()),
+        IndexSpec::new("audit_log", doc! { "target_id": 1, "created_at": -1 }, IndexOptions::default()),
+        // Personal access tokens are looked up by hash on every request and listed per user
+        IndexSpec::new("access_tokens", doc! { "token_hash": 1 }, unique()),
+        IndexSpec::new("access_tokens", doc! { "user_id": 1 }, IndexOptions::default()),
+        // An external identity can belong to one user only
+        IndexSpec::new(
+            "users",
+            doc! { "linked_identities.key": 1 },
+            unique_where(doc! { "linked_identities.key": { "$exists": true } }),
+        ),
+    ]
+}
+
+fn unique_constraints() -> Vec<IndexSpec> {
+    vec![
+        // One account per email; legacy accounts with no email are left alone
+        IndexSpec::new("users", doc! { "email": 1 }, unique_where(doc! { "email": { "$gt": "" } })),
+        IndexSpec::new("blogs", doc! { "slug": 1 }, unique()),
+        // At most one open request per user and project; decided ones can pile up
+        IndexSpec::new(
+            "project_join_requests",
+            doc! { "user_id": 1, "project_id": 1 },
+            unique_where(doc! { "status": "pending" }),
+        ),
+    ]
+}
+
+fn query_indexes() -> Vec<IndexSpec> {
+    vec![
+        // A member's inbox, newest first
+        IndexSpec::new("messages", doc! { "recipient_ids": 1, "created_at": -1 }, IndexOptions::default()),
+        // A member's coin history
+        IndexSpec::new("coin_transactions", doc! { "user_id": 1 }, IndexOptions::default()),
+    ]
+}
+
+// Each list endpoint's default order, with `_id` as the tie-break its cursors use.
+// Mongo walks them backwards for descending sorts.
+fn list_indexes() -> Vec<IndexSpec> {
+    vec![
+        IndexSpec::new("users", doc! { "created_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("projects", doc! { "created_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("events", doc! { "starts_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("gallery", doc! { "created_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("blogs", doc! { "created_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("messages", doc! { "created_at": 1, "_id": 1 }, IndexOptions::default()),
+        IndexSpec::new("coin_transactions", doc! { "user_id": 1, "created_at": 1, "_id": 1 }, IndexOptions::default()),
+    ]
+}
+
+// Shared rate limit buckets (`rate_limit.backend = "mongo"`) go once they are full again
+fn rate_limit_indexes() -> Vec<IndexSpec> {
+    vec![IndexSpec::new("rate_limits", doc! { "expires_at": 1 }, expire_at_date())]
+}
+
+// Every index any migration creates, for the readiness check
+pub fn required_indexes() -> Vec<IndexSpec> {
+    [initial(), unique_constraints(), query_indexes(), list_indexes(), rate_limit_indexes()].into_iter().flatten().collect()
+}
+
+// Creates its indexes on the way up and drops them on the way down
+pub struct CreateIndexes {
+    version: u32,
+    name: &'static str,
+    indexes: fn() -> Vec<IndexSpec>,
+}
+
+pub const INITIAL_INDEXES: CreateIndexes = CreateIndexes { version: 1, name: "initial_indexes", indexes: initial };
+pub const UNIQUE_CONSTRAINTS: CreateIndexes = CreateIndexes { version: 3, name: "unique_constraints", indexes: unique_constraints };
+pub const QUERY_INDEXES: CreateIndexes = CreateIndexes { version: 4, name: "query_indexes", indexes: query_indexes };
+pub const LIST_INDEXES: CreateIndexes = CreateIndexes { version: 6, name: "list_indexes", indexes: list_indexes };
+pub const RATE_LIMIT_INDEXES: CreateIndexes = CreateIndexes { version: 7, name: "rate_limit_indexes", indexes: rate_limit_indexes };
+
+#[async_trait]
+impl Migration for CreateIndexes {
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn up(&self, state: &AppState) -> Result<(), MigrationError> {
+        for index in (self.indexes)() {
+            let name = format!("{}.{}", index.collection, index.name());
+            let model = IndexModel::builder().keys(index.keys).options(index.options).build();
+            match state.database.collection::<Document>(index.collection).create_index(model).await {
+                Ok(_) => {}
+                // Existing data breaks the constraint; someone has to decide which copy wins
+                Err(e) if is_duplicate_key(&e) => {
+                    return Err(MigrationError::Failed(format!(
+                        "cannot create unique index {}: existing documents share a key ({}); merge or remove them and rerun",
+                        name, e
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, state: &AppState) -> Result<(), MigrationError> {
+        for index in (self.indexes)() {
+            let collection = state.database.collection::<Document>(index.collection);
+            match collection.drop_index(index.name()).await {
+                Ok(()) => {}
+                Err(e) if matches!(*e.kind, mongodb::error::ErrorKind::Command(ref c) if c.code == INDEX_NOT_FOUND) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_names_match_mongo_defaults() {
+        let names: Vec<String> = required_indexes().iter().map(IndexSpec::name).collect();
+        assert!(names.contains(&"target_id_1_created_at_-1".to_string()));
+        assert!(names.contains(&"linked_identities.key_1".to_string()));
+        assert!(names.contains(&"user_id_1_project_id_1".to_string()));
+    }
+}
@@ -0,0 +1,212 @@
+mod github_identities;
+mod indexes;
+mod native_dates;
+
+use async_trait::async_trait;
+use futures_util::stream::TryStreamExt;
+use mongodb::bson::{doc, DateTime};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::db::{is_duplicate_key, AppState};
+use crate::models::{MigrationRecord, MigrationState};
+
+pub use indexes::required_indexes;
+
+// A claim older than this belongs to an instance that died mid-migration
+const STALE_CLAIM: Duration = Duration::from_secs(10 * 60);
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// One versioned schema or data change. `up` must be safe to rerun after a crash
+// part-way through; `down` undoes it, and defaults to refusing.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    fn version(&self) -> u32;
+    fn name(&self) -> &'static str;
+    async fn up(&self, state: &AppState) -> Result<(), MigrationError>;
+
+    async fn down(&self, _state: &AppState) -> Result<(), MigrationError> {
+        Err(MigrationError::Irreversible(self.name()))
+    }
+}
+
+// Every migration, oldest first. Versions are never reused or reordered.
+pub fn all() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(indexes::INITIAL_INDEXES),
+        Box::new(github_identities::BackfillGitHubIdentities),
+        Box::new(indexes::UNIQUE_CONSTRAINTS),
+        Box::new(indexes::QUERY_INDEXES),
+        Box::new(native_dates::NativeDates),
+        Box::new(indexes::LIST_INDEXES),
+        Box::new(indexes::RATE_LIMIT_INDEXES),
+    ]
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(mongodb::error::Error),
+    Failed(String),
+    Irreversible(&'static str),
+    Unknown(i64),   // Applied in the database, but not in this build
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "database error: {}", e),
+            MigrationError::Failed(message) => f.write_str(message),
+            MigrationError::Irreversible(name) => write!(f, "migration {} cannot be rolled back", name),
+            MigrationError::Unknown(version) => write!(f, "migration {} was applied by a newer build and is unknown to this one", version),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<mongodb::error::Error> for MigrationError {
+    fn from(e: mongodb::error::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+// A known migration and, if it was started, its record
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: &'static str,
+    pub record: Option<MigrationRecord>,
+}
+
+async fn records(state: &AppState) -> mongodb::error::Result<BTreeMap<i64, MigrationRecord>> {
+    let records: Vec<MigrationRecord> = state.migrations.find(doc! {}).await?.try_collect().await?;
+    Ok(records.into_iter().map(|record| (record.version, record)).collect())
+}
+
+pub async fn status(state: &AppState) -> mongodb::error::Result<Vec<MigrationStatus>> {
+    let mut records = records(state).await?;
+    Ok(all()
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version(),
+            name: migration.name(),
+            record: records.remove(&(migration.version() as i64)),
+        })
+        .collect())
+}
+
+// Names of the migrations not yet applied, for GET /health/ready
+pub async fn pending(state: &AppState) -> mongodb::error::Result<Vec<&'static str>> {
+    Ok(status(state)
+        .await?
+        .into_iter()
+        .filter(|status| status.record.as_ref().is_none_or(|record| record.state != MigrationState::Applied))
+        .map(|status| status.name)
+        .collect())
+}
+
+enum Claim {
+    Acquired,
+    AlreadyApplied,
+}
+
+// Several instances may start at once during a rolling deploy. Inserting the
+// record is the lock: whoever inserts it runs the migration, the rest wait.
+async fn claim(state: &AppState, migration: &dyn Migration) -> mongodb::error::Result<Claim> {
+    let version = migration.version() as i64;
+    loop {
+        let record = MigrationRecord {
+            version,
+            name: migration.name().to_string(),
+            state: MigrationState::Running,
+            started_at: DateTime::now(),
+            applied_at: None,
+        };
+        match state.migrations.insert_one(&record).await {
+            Ok(_) => return Ok(Claim::Acquired),
+            Err(e) if is_duplicate_key(&e) => {}
+            Err(e) => return Err(e),
+        }
+
+        let Some(existing) = state.migrations.find_one(doc! { "_id": version }).await? else {
+            continue; // The other instance failed and released it
+        };
+        if existing.state == MigrationState::Applied {
+            return Ok(Claim::AlreadyApplied);
+        }
+
+        let stale = DateTime::from_millis(DateTime::now().timestamp_millis() - STALE_CLAIM.as_millis() as i64);
+        if existing.started_at < stale {
+            let taken = state.migrations
+                .update_one(
+                    doc! { "_id": version, "state": "running", "started_at": existing.started_at },
+                    doc! { "$set": { "started_at": DateTime::now() } },
+                )
+                .await?;
+            if taken.modified_count == 1 {
+                tracing::warn!(version, name = migration.name(), "taking over a stale migration claim");
+                return Ok(Claim::Acquired);
+            }
+        }
+        tokio::time::sleep(CLAIM_POLL_INTERVAL).await;
+    }
+}
+
+// Applies every pending migration up to `target` (all when None), oldest first.
+// Returns the versions this call applied.
+pub async fn migrate_up(state: &AppState, target: Option<u32>) -> Result<Vec<u32>, MigrationError> {
+    let mut applied = Vec::new();
+    for migration in all().iter().filter(|m| target.is_none_or(|target| m.version() <= target)) {
+        let version = migration.version();
+        if let Claim::AlreadyApplied = claim(state, migration.as_ref()).await? {
+            continue;
+        }
+
+        tracing::info!(version, name = migration.name(), "applying migration");
+        let started = Instant::now();
+        if let Err(e) = migration.up(state).await {
+            // Release the claim so the next run retries it
+            state.migrations.delete_one(doc! { "_id": version as i64, "state": "running" }).await?;
+            return Err(e);
+        }
+        state.migrations
+            .update_one(
+                doc! { "_id": version as i64 },
+                doc! { "$set": { "state": "applied", "applied_at": DateTime::now() } },
+            )
+            .await?;
+        tracing::info!(version, name = migration.name(), elapsed_ms = started.elapsed().as_millis() as u64, "applied migration");
+        applied.push(version);
+    }
+    Ok(applied)
+}
+
+// Rolls back every applied migration newer than `target`, newest first.
+// Stops at the first one that can't be undone. Returns the versions rolled back.
+pub async fn migrate_down(state: &AppState, target: u32) -> Result<Vec<u32>, MigrationError> {
+    let migrations: BTreeMap<i64, Box<dyn Migration>> =
+        all().into_iter().map(|migration| (migration.version() as i64, migration)).collect();
+
+    let mut rolled_back = Vec::new();
+    for version in records(state).await?.into_keys().rev().filter(|&version| version > target as i64) {
+        let migration = migrations.get(&version).ok_or(MigrationError::Unknown(version))?;
+
+        tracing::info!(version, name = migration.name(), "rolling back migration");
+        migration.down(state).await?;
+        state.migrations.delete_one(doc! { "_id": version }).await?;
+        rolled_back.push(migration.version());
+    }
+    Ok(rolled_back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_unique_and_ascending() {
+        let versions: Vec<u32> = all().iter().map(|migration| migration.version()).collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", versions);
+        assert_eq!(versions.first(), Some(&1));
+    }
+}
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use futures_util::stream::TryStreamExt;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+
+use super::{Migration, MigrationError};
+use crate::db::AppState;
+
+// Date fields that used to hold RFC 3339 strings, by collection
+const DATE_FIELDS: &[(&str, &[&str])] = &[
+    ("users", &["created_at", "updated_at"]),
+    ("projects", &["created_at", "updated_at"]),
+    ("project_join_requests", &["created_at", "updated_at"]),
+    ("messages", &["created_at"]),
+    ("coin_transactions", &["created_at"]),
+    ("leaderboards", &["week_start", "week_end", "created_at"]),
+    ("gallery", &["created_at"]),
+    ("events", &["created_at", "updated_at"]),
+    ("blogs", &["created_at", "updated_at"]),
+];
+
+// Timestamps were stored as RFC 3339 strings, which sort and compare as text.
+// This rewrites them as BSON dates, and turns each event's free-text
+// `date`/`time`/`end_date` ("2025-03-14", "02:00 PM - 05:00 PM") into
+// `starts_at`/`ends_at` in EVENT_TIMEZONE. The original event strings are kept
+// under `legacy_schedule`; events whose schedule can't be read start at their
+// `created_at` and are logged, to be fixed by hand.
+pub struct NativeDates;
+
+#[async_trait]
+impl Migration for NativeDates {
+    fn version(&self) -> u32 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "native_dates"
+    }
+
+    async fn up(&self, state: &AppState) -> Result<(), MigrationError> {
+        for (collection, fields) in DATE_FIELDS {
+            let converted = convert_strings(state, collection, fields).await?;
+            if converted > 0 {
+                tracing::info!(collection, converted, "native dates: converted string timestamps");
+            }
+        }
+        schedule_events(state).await
+    }
+}
+
+async fn convert_strings(state: &AppState, collection: &str, fields: &[&str]) -> Result<u64, MigrationError> {
+    let collection = state.database.collection::<Document>(collection);
+    let mut any_string: Vec<Document> = fields.iter().map(|field| doc! { *field: { "$type": "string" } }).collect();
+    any_string.push(doc! { "files.uploaded_at": { "$type": "string" } });
+    let mut cursor = collection.find(doc! { "$or": any_string }).await?;
+
+    let mut converted = 0;
+    while let Some(document) = cursor.try_next().await? {
+        let mut set = Document::new();
+        for field in fields {
+            if let Ok(text) = document.get_str(field) {
+                set.insert(*field, parse_rfc3339(text)?);
+            }
+        }
+        // Files embedded in a project carry their own upload time
+        if let Ok(files) = document.get_array("files") {
+            for (i, file) in files.iter().enumerate() {
+                if let Some(Ok(text)) = file.as_document().map(|file| file.get_str("uploaded_at")) {
+                    set.insert(format!("files.{}.uploaded_at", i), parse_rfc3339(text)?);
+                }
+            }
+        }
+        collection.update_one(doc! { "_id": document.get("_id") }, doc! { "$set": set }).await?;
+        converted += 1;
+    }
+    Ok(converted)
+}
+
+fn parse_rfc3339(text: &str) -> Result<DateTime, MigrationError> {
+    DateTime::parse_rfc3339_str(text)
+        .map_err(|e| MigrationError::Failed(format!("timestamp {:?} is not RFC 3339: {}", text, e)))
+}
+
+async fn schedule_events(state: &AppState) -> Result<(), MigrationError> {
+    let timezone: Tz = state.config.events.timezone
+        .parse()
+        .map_err(|_| MigrationError::Failed(format!("unknown time zone {:?}", state.config.events.timezone)))?;
+    let events = state.database.collection::<Document>("events");
+    let mut cursor = events.find(doc! { "starts_at": { "$exists": false } }).await?;
+
+    let mut scheduled = 0;
+    let mut unreadable = 0;
+    while let Some(event) = cursor.try_next().await? {
+        let date = event.get_str("date").unwrap_or_default();
+        let time = event.get_str("time").unwrap_or_default();
+        let end_date = event.get_str("end_date").ok();
+
+        let (starts_at, ends_at) = match legacy_schedule(date, time, end_date, timezone) {
+            Some(schedule) => {
+                scheduled += 1;
+                schedule
+            }
+            None => {
+                unreadable += 1;
+                tracing::warn!(event_id = %event.get("_id").unwrap_or(&Bson::Null), date, time, "native dates: unreadable event schedule, using created_at");
+                let created_at = event.get_datetime("created_at").copied().unwrap_or_else(|_| DateTime::now());
+                (created_at, None)
+            }
+        };
+
+        let mut set = doc! {
+            "starts_at": starts_at,
+            "timezone": timezone.name(),
+            "legacy_schedule": { "date": date, "time": time, "end_date": end_date },
+        };
+        if let Some(ends_at) = ends_at {
+            set.insert("ends_at", ends_at);
+        }
+        events
+            .update_one(
+                doc! { "_id": event.get("_id"), "starts_at": { "$exists": false } },
+                doc! { "$set": set, "$unset": { "date": "", "time": "", "end_date": "" } },
+            )
+            .await?;
+    }
+
+    if scheduled + unreadable > 0 {
+        tracing::info!(scheduled, unreadable, "native dates: gave events start and end times");
+    }
+    Ok(())
+}
+
+// "2025-03-14" + "02:00 PM - 05:00 PM" (+ an optional end date) in `timezone`.
+// A missing time means the start of the day; an end date without an end time
+// ends at the start's time of day.
+fn legacy_schedule(date: &str, time: &str, end_date: Option<&str>, timezone: Tz) -> Option<(DateTime, Option<DateTime>)> {
+    let start_day = parse_day(date)?;
+    let (start_time, end_time) = match split_range(time) {
+        Some((start, Some(end))) => (parse_time(start)?, Some(parse_time(end)?)),
+        Some((start, None)) => (parse_time(start)?, None),
+        None => (NaiveTime::MIN, None),
+    };
+    let end_day = match end_date.map(str::trim).filter(|end_date| !end_date.is_empty()) {
+        Some(end_date) => Some(parse_day(end_date)?),
+        None => None,
+    };
+
+    let starts_at = at(start_day.and_time(start_time), timezone)?;
+    let ends_at = match (end_day, end_time) {
+        (Some(day), time) => Some(at(day.and_time(time.unwrap_or(start_time)), timezone)?),
+        (None, Some(time)) => Some(at(start_day.and_time(time), timezone)?),
+        (None, None) => None,
+    };
+    Some((starts_at, ends_at.filter(|&ends_at| ends_at >= starts_at)))
+}
+
+fn at(local: NaiveDateTime, timezone: Tz) -> Option<DateTime> {
+    let at = timezone.from_local_datetime(&local).earliest()?;
+    Some(DateTime::from_millis(at.timestamp_millis()))
+}
+
+fn parse_day(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y", "%d %B %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+}
+
+// None when there's no time at all; the end is optional
+fn split_range(text: &str) -> Option<(&str, Option<&str>)> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let split = [" - ", "-", "–", " to "].iter().find_map(|separator| text.split_once(separator));
+    Some(match split {
+        Some((start, end)) => (start.trim(), Some(end.trim())),
+        None => (text, None),
+    })
+}
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    let mut text = text.trim().to_uppercase();
+    // chrono wants minutes, so "10AM" is read as "10:00AM"
+    if !text.contains(':') {
+        let hour_end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        text.insert_str(hour_end, ":00");
+    }
+    ["%I:%M %p", "%I:%M%p", "%H:%M"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&text, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime {
+        DateTime::parse_rfc3339_str(text).unwrap()
+    }
+
+    #[test]
+    fn reads_the_admin_forms_time_ranges() {
+        let kolkata: Tz = "Asia/Kolkata".parse().unwrap();
+        assert_eq!(
+            legacy_schedule("2025-03-14", "02:00 PM - 05:00 PM", None, kolkata),
+            Some((utc("2025-03-14T08:30:00Z"), Some(utc("2025-03-14T11:30:00Z"))))
+        );
+        assert_eq!(
+            legacy_schedule("2025-03-14", "10am", Some("2025-03-16"), chrono_tz::UTC),
+            Some((utc("2025-03-14T10:00:00Z"), Some(utc("2025-03-16T10:00:00Z"))))
+        );
+        assert_eq!(
+            legacy_schedule("March 14, 2025", "", None, chrono_tz::UTC),
+            Some((utc("2025-03-14T00:00:00Z"), None))
+        );
+    }
+
+    #[test]
+    fn gives_up_on_free_text() {
+        assert_eq!(legacy_schedule("next Friday", "10:00", None, chrono_tz::UTC), None);
+        assert_eq!(legacy_schedule("2025-03-14", "after lunch", None, chrono_tz::UTC), None);
+    }
+}
@@ -1,46 +1,122 @@
-use mongodb::{Client, Collection};
+pub mod consistency;
+pub mod migrations;
 
-use crate::models::{User, Project, ProjectJoinRequest, Message, CoinTransaction, WeeklyLeaderboard, GalleryItem, Event, Blog};
+use std::sync::Arc;
+
+use mongodb::{Client, Collection, Database, event::EventHandler, options::ClientOptions};
+
+use crate::models::MigrationRecord;
+use crate::mailer::{self, Mailer};
+use crate::auth::github::{GitHubApi, HttpGitHubApi};
+use crate::auth::oidc::OidcRegistry;
+use crate::auth::policy::SignupPolicy;
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::rate_limit::{self, RateLimitStore};
+use crate::repo::mongo::MongoStore;
+use crate::repo::{
+    AccessTokenRepository, AuditRepository, BlogRepository, CoinRepository, EventRepository, GalleryRepository, InviteRepository, JoinRequestRepository,
+    MessageRepository, OAuthStateRepository, PasswordResetRepository, ProjectRepository, SessionRepository, Store,
+    TwoFactorChallengeRepository, UserRepository,
+};
 
 #[derive(Clone, Debug)]
 pub struct AppState{
-    pub users: Collection<User>,
-    pub projects: Collection<Project>,
-    pub project_join_requests: Collection<ProjectJoinRequest>,
-    pub messages: Collection<Message>,
-    pub coin_transactions: Collection<CoinTransaction>,
-    pub leaderboards: Collection<WeeklyLeaderboard>,
-    pub gallery: Collection<GalleryItem>,
-    pub events: Collection<Event>,
-    pub blogs: Collection<Blog>,
+    pub database: Database,
+    pub users: Arc<dyn UserRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub projects: Arc<dyn ProjectRepository>,
+    pub project_join_requests: Arc<dyn JoinRequestRepository>,
+    pub coins: Arc<dyn CoinRepository>,
+    pub messages: Arc<dyn MessageRepository>,
+    pub events: Arc<dyn EventRepository>,
+    pub gallery: Arc<dyn GalleryRepository>,
+    pub blogs: Arc<dyn BlogRepository>,
+    pub invites: Arc<dyn InviteRepository>,
+    pub oauth_states: Arc<dyn OAuthStateRepository>,
+    pub password_resets: Arc<dyn PasswordResetRepository>,
+    pub two_factor_challenges: Arc<dyn TwoFactorChallengeRepository>,
+    pub access_tokens: Arc<dyn AccessTokenRepository>,
+    pub audit_log: Arc<dyn AuditRepository>,
+    pub migrations: Collection<MigrationRecord>,
+    pub mailer: Arc<dyn Mailer>,
+    pub rate_limits: Arc<dyn RateLimitStore>,
+    pub github: Arc<dyn GitHubApi>,
+    pub signup_policy: Arc<SignupPolicy>,
+    pub oidc: Arc<OidcRegistry>,
+    pub config: Arc<Config>,
+    pub metrics: Arc<Metrics>,
 }
 
-pub async fn connect() -> AppState {
-    let client = Client::with_uri_str(std::env::var("MONGO_URI").unwrap())
-        .await
-        .unwrap();
-
-    let db = client.database("iris");
-    
-    let users = db.collection::<User>("users");
-    let projects = db.collection::<Project>("projects");
-    let project_join_requests = db.collection::<ProjectJoinRequest>("project_join_requests");
-    let messages = db.collection::<Message>("messages");
-    let coin_transactions = db.collection::<CoinTransaction>("coin_transactions");
-    let leaderboards = db.collection::<WeeklyLeaderboard>("leaderboards");
-    let gallery = db.collection::<GalleryItem>("gallery");
-    let events = db.collection::<Event>("events");
-    let blogs = db.collection::<Blog>("blogs");
-    
-    AppState {
-        users,
-        projects,
-        project_join_requests,
-        messages,
-        coin_transactions,
-        leaderboards,
-        gallery,
-        events,
-        blogs,
+impl AppState {
+    // Everything the handlers store lives in `store`; the readiness probe, the
+    // maintenance commands and the Mongo rate limiter use `database`
+    pub fn new<S: Store + 'static>(config: Config, database: Database, store: Arc<S>, metrics: Arc<Metrics>) -> AppState {
+        AppState {
+            users: store.clone(),
+            sessions: store.clone(),
+            projects: store.clone(),
+            project_join_requests: store.clone(),
+            coins: store.clone(),
+            messages: store.clone(),
+            events: store.clone(),
+            gallery: store.clone(),
+            blogs: store.clone(),
+            invites: store.clone(),
+            oauth_states: store.clone(),
+            password_resets: store.clone(),
+            two_factor_challenges: store.clone(),
+            access_tokens: store.clone(),
+            audit_log: store,
+            migrations: database.collection("migrations"),
+            rate_limits: rate_limit::from_config(&config.rate_limit, &database),
+            database,
+            mailer: mailer::from_config(&config.mail),
+            github: Arc::new(HttpGitHubApi::new(config.github.api_url.clone())),
+            signup_policy: Arc::new(SignupPolicy::from_config(&config.signup)),
+            oidc: Arc::new(OidcRegistry::from_config(&config.oidc)),
+            config: Arc::new(config),
+            metrics,
+        }
     }
-}
\ No newline at end of file
+}
+
+// True if a write, or building a unique index, failed on duplicate keys
+pub fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match *error.kind {
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(ref e)) => e.code == 11000,
+        mongodb::error::ErrorKind::Command(ref e) => e.code == 11000,
+        _ => false,
+    }
+}
+
+pub async fn connect(config: Config) -> mongodb::error::Result<AppState> {
+    // Every command the driver runs is timed per collection
+    let metrics = Arc::new(Metrics::new());
+    let mut options = ClientOptions::parse(&config.database.uri).await?;
+    let observer = metrics.clone();
+    options.command_event_handler = Some(EventHandler::callback(move |event| observer.observe_mongo(event)));
+    let client = Client::with_options(options)?;
+
+    let database = client.database(&config.database.name);
+    let store = Arc::new(MongoStore::new(&database));
+
+    // Indexes are created by migrations (db::migrations), not here
+    Ok(AppState::new(config, database, store, metrics))
+}
+
+// State over an in-memory store for tests. The Mongo client never connects:
+// only readiness, the maintenance commands and the Mongo rate limiter would use it.
+#[cfg(test)]
+pub fn in_memory(config: Config) -> (AppState, Arc<crate::repo::memory::MemoryStore>) {
+    use mongodb::options::ServerAddress;
+
+    let options = ClientOptions::builder()
+        .hosts(vec![ServerAddress::Tcp { host: "127.0.0.1".to_string(), port: Some(1) }])
+        .server_selection_timeout(std::time::Duration::from_millis(100))
+        .build();
+    let client = Client::with_options(options).expect("client options are valid");
+    let store = Arc::new(crate::repo::memory::MemoryStore::default());
+    let state = AppState::new(config, client.database("test"), store.clone(), Arc::new(Metrics::new()));
+    (state, store)
+}
@@ -0,0 +1,193 @@
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::middleware::request_id;
+use crate::repo::RepoError;
+
+// What every handler fails with. Clients always get the same envelope:
+// `{ "code": "not_found", "message": "Project not found", "details": null, "request_id": "..." }`
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Unprocessable(String),              // Well-formed but invalid input, see `validate`
+    Locked(String),
+    RateLimited(u64),                   // Seconds until the next request is let through, sent as Retry-After
+    Upstream(String),                   // GitHub or an OIDC provider failed us
+    Database(mongodb::error::Error),    // Logged; the client only sees "Database error"
+    Internal(String),                   // Logged; the client only sees "Internal server error"
+    // Any of the above with a more specific code and structured details
+    Detailed {
+        error: Box<AppError>,
+        code: Option<&'static str>,
+        details: Option<serde_json::Value>,
+    },
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+// The JSON body of every error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    #[schema(example = "not_found")]
+    pub code: &'static str,
+    #[schema(example = "Project not found")]
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+}
+
+impl AppError {
+    // Replace the generic code, e.g. "not_found" -> "user_not_found"
+    pub fn with_code(self, code: &'static str) -> Self {
+        match self {
+            AppError::Detailed { error, details, .. } => AppError::Detailed { error, code: Some(code), details },
+            error => AppError::Detailed { error: Box::new(error), code: Some(code), details: None },
+        }
+    }
+
+    pub fn with_details(self, details: serde_json::Value) -> Self {
+        match self {
+            AppError::Detailed { error, code, .. } => AppError::Detailed { error, code, details: Some(details) },
+            error => AppError::Detailed { error: Box::new(error), code: None, details: Some(details) },
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Locked(_) => StatusCode::LOCKED,
+            AppError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Detailed { error, .. } => error.status(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unprocessable(_) => "unprocessable",
+            AppError::Locked(_) => "locked",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::Database(_) => "database_error",
+            AppError::Internal(_) => "internal_error",
+            AppError::Detailed { error, code, .. } => code.unwrap_or_else(|| error.code()),
+        }
+    }
+
+    // The client-facing message; infrastructure errors never leak their cause
+    pub fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::Unprocessable(message)
+            | AppError::Locked(message)
+            | AppError::Upstream(message) => message.clone(),
+            AppError::RateLimited(seconds) => format!("Too many requests; try again in {} seconds", seconds),
+            AppError::Database(_) => "Database error".to_string(),
+            AppError::Internal(_) => "Internal server error".to_string(),
+            AppError::Detailed { error, .. } => error.message(),
+        }
+    }
+
+    fn details(&self) -> Option<&serde_json::Value> {
+        match self {
+            AppError::Detailed { details, .. } => details.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::RateLimited(seconds) => Some(*seconds),
+            AppError::Detailed { error, .. } => error.retry_after(),
+            _ => None,
+        }
+    }
+
+    fn log(&self) {
+        match self {
+            AppError::Database(e) => tracing::error!(error = ?e, "database error"),
+            AppError::Internal(e) => tracing::error!(error = %e, "internal error"),
+            AppError::Upstream(e) => tracing::warn!(error = %e, "upstream error"),
+            AppError::Detailed { error, .. } => error.log(),
+            _ => {}
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.log();
+        let body = ErrorBody {
+            code: self.code(),
+            message: self.message(),
+            details: self.details().cloned(),
+            request_id: request_id::current(),
+        };
+        let mut response = (self.status(), Json(body)).into_response();
+        if let Some(seconds) = self.retry_after() {
+            response.headers_mut().insert(RETRY_AFTER, seconds.into());
+        }
+        response
+    }
+}
+
+impl From<mongodb::error::Error> for AppError {
+    fn from(error: mongodb::error::Error) -> Self {
+        AppError::Database(error)
+    }
+}
+
+impl From<RepoError> for AppError {
+    fn from(error: RepoError) -> Self {
+        match error {
+            RepoError::Duplicate => AppError::Conflict("Conflicts with an existing record".to_string()),
+            RepoError::NotFound(what) => AppError::NotFound(format!("{} not found", what)),
+            RepoError::Database(e) => AppError::Database(e),
+        }
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for AppError {
+    fn from(error: mongodb::bson::ser::Error) -> Self {
+        AppError::Internal(format!("BSON serialization failed: {}", error))
+    }
+}
+
+// Parse an id from a path or body, naming the field in the 400 if it's malformed
+pub fn parse_id(value: &str, field: &str) -> AppResult<ObjectId> {
+    ObjectId::parse_str(value).map_err(|_| {
+        AppError::BadRequest(format!("Invalid {} format", field))
+            .with_code("invalid_id")
+            .with_details(serde_json::json!({ "field": field }))
+    })
+}
+
+// `Ok(Some(doc))` or a 404 naming what was looked up
+pub fn found<T>(value: Option<T>, what: &str) -> AppResult<T> {
+    value.ok_or_else(|| AppError::NotFound(format!("{} not found", what)))
+}
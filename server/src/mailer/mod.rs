@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{MailConfig, MailerKind};
+
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// Outgoing mail. Real providers plug in here; dev builds use the console or file mailers.
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+// Logs every mail. Tokens in links are redacted like in any log line; use the file mailer to follow them.
+#[derive(Debug, Default)]
+pub struct ConsoleMailer;
+
+#[async_trait]
+impl Mailer for ConsoleMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        tracing::info!(to = %email.to, subject = %email.subject, body = %email.body, "console mailer");
+        Ok(())
+    }
+}
+
+// Appends every mail to `<dir>/outbox.log`, handy for local testing of reset links
+#[derive(Debug)]
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileMailer { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|e| e.to_string())?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join("outbox.log"))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let entry = format!(
+            "Date: {}\nTo: {}\nSubject: {}\n\n{}\n\n----\n",
+            chrono::Utc::now().to_rfc3339(),
+            email.to,
+            email.subject,
+            email.body
+        );
+        file.write_all(entry.as_bytes()).await.map_err(|e| e.to_string())
+    }
+}
+
+// Picks the mailer from `mail.mailer` ("console" or "file"; `mail.dir` for the latter)
+pub fn from_config(config: &MailConfig) -> Arc<dyn Mailer> {
+    match config.mailer {
+        MailerKind::File => Arc::new(FileMailer::new(config.dir.clone())),
+        MailerKind::Console => Arc::new(ConsoleMailer),
+    }
+}
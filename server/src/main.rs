@@ -3,26 +3,191 @@ mod models;
 mod routes;
 mod auth;
 mod middleware;
+mod mailer;
+mod audit;
+mod config;
+mod error;
+mod logging;
+mod metrics;
+mod openapi;
+mod rate_limit;
+mod repo;
+mod validate;
 
 use axum::serve;
+use clap::Parser;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 
+use crate::config::{Cli, Command, Config, MigrateAction};
+use crate::db::AppState;
+use crate::db::migrations::MigrationError;
+
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
 
+    let cli = Cli::parse();
+
+    // Logging isn't set up before the config is read, so this one goes to stderr
+    let config = Config::load(&cli).unwrap_or_else(|e| {
+        eprintln!("Refusing to start: {}", e);
+        std::process::exit(1);
+    });
+    logging::init(&config);
+
     // Database connection
-    let state = db::connect().await;
+    let state = db::connect(config).await.unwrap_or_else(|e| {
+        tracing::error!(error = %e, "failed to connect to MongoDB");
+        std::process::exit(1);
+    });
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => run_server(state).await,
+        Command::Migrate { action } => run_migrations(&state, action).await,
+        Command::Check { repair } => run_consistency_check(&state, repair).await,
+    }
+}
+
+async fn run_server(state: AppState) {
+    // Serving with missing migrations leaves /health/ready at 503 until `migrate up` runs
+    if state.config.database.migrate_on_startup {
+        let migrated = db::migrations::migrate_up(&state, None).await;
+        if let Err(e) = migrated {
+            tracing::error!(error = %e, "migration failed");
+            std::process::exit(1);
+        }
+    }
+
+    let bind_address = state.config.server.bind_address.clone();
 
     // Build routes
     let app = routes::create_routes(state);
 
     // Run server
-    let listener = TcpListener::bind("0.0.0.0:5657")
-        .await
-        .unwrap();
+    let listener = TcpListener::bind(&bind_address).await.unwrap_or_else(|e| {
+        tracing::error!(%bind_address, error = %e, "failed to bind");
+        std::process::exit(1);
+    });
+
+    tracing::info!(%bind_address, "server is running");
+
+    // On SIGTERM (or Ctrl+C) stop accepting connections and let in-flight requests finish.
+    // The peer address is kept for rate limiting by IP.
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    if let Err(e) = serve(listener, app).with_graceful_shutdown(shutdown_signal()).await {
+        tracing::error!(error = %e, "server error");
+        std::process::exit(1);
+    }
+    tracing::info!("server stopped");
+}
+
+// `server migrate status|up|down`: prints a table for status, logs the rest
+async fn run_migrations(state: &AppState, action: MigrateAction) {
+    if let Err(e) = migrate(state, action).await {
+        tracing::error!(error = %e, "migration command failed");
+        std::process::exit(1);
+    }
+}
+
+async fn migrate(state: &AppState, action: MigrateAction) -> Result<(), MigrationError> {
+    match action {
+        MigrateAction::Status => {
+            for migration in db::migrations::status(state).await? {
+                let (status, applied_at) = match migration.record {
+                    Some(record) => (
+                        format!("{:?}", record.state).to_lowercase(),
+                        record.applied_at.and_then(|at| at.try_to_rfc3339_string().ok()).unwrap_or_default(),
+                    ),
+                    None => ("pending".to_string(), String::new()),
+                };
+                println!("{:>4}  {:<28} {:<8} {}", migration.version, migration.name, status, applied_at);
+            }
+        }
+        MigrateAction::Up { to } => {
+            let applied = db::migrations::migrate_up(state, to).await?;
+            tracing::info!(?applied, "migrations are up to date");
+        }
+        MigrateAction::Down { to } => {
+            // Without --to, undo only the latest applied migration
+            let target = match to {
+                Some(to) => to,
+                None => db::migrations::status(state)
+                    .await?
+                    .iter()
+                    .filter(|migration| migration.record.is_some())
+                    .map(|migration| migration.version)
+                    .max()
+                    .map_or(0, |latest| latest - 1),
+            };
+            let rolled_back = db::migrations::migrate_down(state, target).await?;
+            tracing::info!(?rolled_back, "rolled back migrations");
+        }
+    }
+    Ok(())
+}
+
+// `server check [--repair]`: prints every inconsistency, exits 1 if any is left
+async fn run_consistency_check(state: &AppState, repair: bool) {
+    let report = db::consistency::check(state).await.unwrap_or_else(|e| {
+        tracing::error!(error = %e, "consistency check failed");
+        std::process::exit(1);
+    });
+
+    for (user_id, project_id) in &report.missing_project_links {
+        println!("user {} is a member of project {} but doesn't list it", user_id, project_id);
+    }
+    for (user_id, project_id) in &report.stale_project_links {
+        println!("user {} lists project {} but isn't one of its members", user_id, project_id);
+    }
+    for (project_id, user_id) in &report.dangling_members {
+        println!("project {} lists member {}, who doesn't exist", project_id, user_id);
+    }
+    for drift in &report.coin_drift {
+        println!("user {} has {} coins but their transactions add up to {}", drift.user_id, drift.balance, drift.ledger);
+    }
+
+    if report.is_clean() {
+        println!("no inconsistencies found");
+        return;
+    }
+    if !repair {
+        println!("run again with --repair to fix these");
+        std::process::exit(1);
+    }
+    if let Err(e) = db::consistency::repair(state, &report).await {
+        tracing::error!(error = %e, "repair failed");
+        std::process::exit(1);
+    }
+    println!("repaired");
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
 
-    println!("Server is running on http://localhost:5657");
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    serve(listener, app).await.unwrap();
-}
\ No newline at end of file
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received, draining in-flight requests");
+}
@@ -0,0 +1,166 @@
+use mongodb::bson::Document;
+use mongodb::event::command::CommandEvent;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Mongo round trips are much shorter than whole requests
+const MONGO_BUCKETS: &[f64] = &[0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+// Started Mongo commands waiting for their result, by driver request id
+type PendingCommands = Mutex<HashMap<i32, (String, String)>>;
+
+// Everything exposed on GET /metrics. HTTP series are labelled by route template
+// (`/projects/{id}`), never the raw path, so the label set stays bounded.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    mongo_duration: HistogramVec,
+    mongo_pending: PendingCommands,
+    pub pending_join_requests: IntGauge,
+    pub active_projects: IntGauge,
+    pub coins_in_circulation: IntGauge,
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("iris".to_string()), None).expect("valid registry prefix");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route template, method and status"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route template and method"),
+            &["method", "route"],
+        )
+        .expect("valid metric");
+        let mongo_duration = HistogramVec::new(
+            HistogramOpts::new("mongodb_operation_duration_seconds", "MongoDB command latency by collection and command")
+                .buckets(MONGO_BUCKETS.to_vec()),
+            &["collection", "operation", "outcome"],
+        )
+        .expect("valid metric");
+        let pending_join_requests = IntGauge::new("pending_join_requests", "Project join requests waiting for a decision")
+            .expect("valid metric");
+        let active_projects = IntGauge::new("active_projects", "Projects with status Active").expect("valid metric");
+        let coins_in_circulation = IntGauge::new("coins_in_circulation", "Sum of all users' coin balances")
+            .expect("valid metric");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_duration.clone()),
+            Box::new(mongo_duration.clone()),
+            Box::new(pending_join_requests.clone()),
+            Box::new(active_projects.clone()),
+            Box::new(coins_in_circulation.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_duration,
+            mongo_duration,
+            mongo_pending: Mutex::new(HashMap::new()),
+            pending_join_requests,
+            active_projects,
+            coins_in_circulation,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests.with_label_values(&[method, route, &status.to_string()]).inc();
+        self.http_duration.with_label_values(&[method, route]).observe(elapsed.as_secs_f64());
+    }
+
+    // Hooked into the Mongo client as its command event handler. Commands that
+    // don't target a collection (hello, ping, auth) are ignored.
+    pub fn observe_mongo(&self, event: CommandEvent) {
+        let mut pending = self.mongo_pending.lock().unwrap_or_else(|e| e.into_inner());
+        let (request_id, duration, outcome) = match event {
+            CommandEvent::Started(started) => {
+                if let Some(collection) = collection_of(&started.command_name, &started.command) {
+                    pending.insert(started.request_id, (collection, started.command_name));
+                }
+                return;
+            }
+            CommandEvent::Succeeded(done) => (done.request_id, done.duration, "success"),
+            CommandEvent::Failed(failed) => (failed.request_id, failed.duration, "failure"),
+            _ => return,
+        };
+        if let Some((collection, operation)) = pending.remove(&request_id) {
+            self.mongo_duration
+                .with_label_values(&[&collection, &operation, outcome])
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding of gathered metrics");
+        String::from_utf8(buffer).expect("prometheus text is utf-8")
+    }
+}
+
+// `{ find: "users", ... }` names its collection under the command name;
+// `{ getMore: <cursor id>, collection: "users" }` is the odd one out
+fn collection_of(command_name: &str, command: &Document) -> Option<String> {
+    command
+        .get_str(command_name)
+        .or_else(|_| command.get_str("collection"))
+        .ok()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn renders_http_series_by_route_template() {
+        let metrics = Metrics::new();
+        metrics.observe_request("GET", "/projects/{id}/join-requests", 200, Duration::from_millis(12));
+        metrics.observe_request("GET", "/projects/{id}/join-requests", 403, Duration::from_millis(3));
+        metrics.coins_in_circulation.set(420);
+
+        let text = metrics.render();
+        assert!(text.contains(r#"iris_http_requests_total{method="GET",route="/projects/{id}/join-requests",status="200"} 1"#));
+        assert!(text.contains(r#"iris_http_requests_total{method="GET",route="/projects/{id}/join-requests",status="403"} 1"#));
+        assert!(text.contains(r#"iris_http_request_duration_seconds_count{method="GET",route="/projects/{id}/join-requests"} 2"#));
+        assert!(text.contains("iris_coins_in_circulation 420"));
+    }
+
+    #[test]
+    fn finds_the_collection_a_command_targets() {
+        assert_eq!(collection_of("find", &doc! { "find": "users", "filter": {} }).as_deref(), Some("users"));
+        assert_eq!(
+            collection_of("getMore", &doc! { "getMore": 42_i64, "collection": "messages" }).as_deref(),
+            Some("messages")
+        );
+        assert_eq!(collection_of("ping", &doc! { "ping": 1 }), None);
+    }
+}
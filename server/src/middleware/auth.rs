@@ -1,17 +1,21 @@
 use axum::{
-    extract::{Request, State},
-    http::{StatusCode, header},
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, header, request::Parts},
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
 };
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::future::Future;
+use std::pin::Pin;
 
+use crate::audit;
+use crate::auth::access_tokens;
 use crate::db::AppState;
-use crate::models::user::Role;
+use crate::error::{AppError, AppResult};
+use crate::models::user::{Permission, Role, User};
+use crate::repo::RepoError;
 
 #[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,53 +24,258 @@ pub struct Claims {
     pub username: String,
     pub email: String,
     pub role: String,
+    pub jti: String,   // Session id, checked against the sessions collection
     pub exp: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,   // Set on impersonation tokens: the admin acting as `sub`
+}
+
+// The real user behind an impersonation token (RFC 8693 "act" claim)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Actor {
+    pub sub: String,
+    pub username: String,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct AuthUser {
-    pub id: String,
+    pub id: ObjectId,
     pub username: String,
     pub email: String,
     pub role: Role,
+    pub session_id: Option<ObjectId>,   // None when signed in with a personal access token
+    pub access_token: Option<TokenGrant>,
+    pub two_factor_verified: bool,   // The session (or the one that created the token) used a second factor
+    pub two_factor_enforced: bool,   // An admin while `auth.require_admin_2fa` is on
+    pub impersonator_id: Option<ObjectId>,   // Admin acting as this user, if any
+}
+
+// The personal access token a request was made with
+#[derive(Debug, Clone)]
+pub struct TokenGrant {
+    pub id: ObjectId,
+    pub name: String,
+    pub scopes: Vec<Permission>,
+}
+
+impl AuthUser {
+    // A token only carries the permissions it was scoped to, and only while the role still has them
+    pub fn can(&self, permission: Permission) -> bool {
+        !self.needs_two_factor()
+            && self.role.has_permission(permission)
+            && self.access_token.as_ref().is_none_or(|grant| grant.scopes.contains(&permission))
+    }
+
+    // Account-security endpoints are for the account owner in a login session:
+    // not for an impersonating admin, not for scripts holding an access token
+    pub fn require_owner(&self) -> AppResult<()> {
+        if self.impersonator_id.is_some() {
+            Err(impersonation_forbidden())
+        } else if self.access_token.is_some() {
+            Err(access_token_forbidden())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Admins without a 2FA session are locked out of privileged actions when `auth.require_admin_2fa` is on
+    pub fn needs_two_factor(&self) -> bool {
+        self.two_factor_enforced && !self.two_factor_verified
+    }
+}
+
+// Returned by account-security endpoints that an impersonating admin must not touch
+pub fn impersonation_forbidden() -> AppError {
+    AppError::Forbidden("Not allowed while impersonating, account security settings can only be changed by the account owner".to_string())
+        .with_code("impersonation_forbidden")
+}
+
+pub fn access_token_forbidden() -> AppError {
+    AppError::Forbidden("Not allowed with an access token, sign in to manage account security settings".to_string())
+        .with_code("access_token_forbidden")
 }
 
-fn get_jwt_secret() -> String {
-    env::var("JWT_SECRET").unwrap_or_else(|_| "your-secret-key-change-in-production".to_string())
+pub fn two_factor_enforced(state: &AppState, role: &Role) -> bool {
+    *role == Role::Admin && state.config.auth.require_admin_2fa
 }
 
-pub fn create_jwt(user_id: &str, username: &str, email: &str, role: &Role) -> Result<String, jsonwebtoken::errors::Error> {
+// Access tokens are short-lived; clients renew them with a refresh token
+pub const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+
+pub fn create_jwt(secret: &str, user_id: ObjectId, user: &User, session_id: ObjectId) -> Result<String, jsonwebtoken::errors::Error> {
+    sign_jwt(secret, user_id, user, session_id, ACCESS_TOKEN_TTL_MINUTES, None)
+}
+
+// Token for an admin acting as another user; `act` names the admin
+pub fn create_impersonation_jwt(
+    secret: &str,
+    user_id: ObjectId,
+    user: &User,
+    session_id: ObjectId,
+    ttl_minutes: i64,
+    actor: Actor,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    sign_jwt(secret, user_id, user, session_id, ttl_minutes, Some(actor))
+}
+
+fn sign_jwt(
+    secret: &str,
+    user_id: ObjectId,
+    user: &User,
+    session_id: ObjectId,
+    ttl_minutes: i64,
+    act: Option<Actor>,
+) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::days(7))
+        .checked_add_signed(chrono::Duration::minutes(ttl_minutes))
         .expect("valid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
-        sub: user_id.to_string(),
-        username: username.to_string(),
-        email: email.to_string(),
-        role: format!("{:?}", role),
+        sub: user_id.to_hex(),
+        username: user.username.clone(),
+        email: user.email.clone(),
+        role: format!("{:?}", user.role),
+        jti: session_id.to_hex(),
         exp: expiration,
+        act,
     };
 
     encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(get_jwt_secret().as_bytes()),
+        &EncodingKey::from_secret(secret.as_bytes()),
     )
 }
 
-pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+pub fn verify_jwt(secret: &str, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
     let validation = Validation::default();
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(get_jwt_secret().as_bytes()),
+        &DecodingKey::from_secret(secret.as_bytes()),
         &validation,
     )?;
     Ok(token_data.claims)
 }
 
+// Why a request could not be tied to a user
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    InvalidUserId,
+    UserNotFound,
+    SessionRevoked,
+    Database(RepoError),
+}
+
+impl From<AuthError> for AppError {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::MissingToken => AppError::Unauthorized("Missing authorization token".to_string())
+                .with_code("missing_token"),
+            AuthError::InvalidToken => AppError::Unauthorized("Invalid or expired token, please login again".to_string())
+                .with_code("invalid_token"),
+            AuthError::InvalidUserId => AppError::Unauthorized("Invalid user ID in token".to_string())
+                .with_code("invalid_token"),
+            AuthError::UserNotFound => AppError::Unauthorized("User associated with this token no longer exists".to_string())
+                .with_code("user_not_found"),
+            AuthError::SessionRevoked => AppError::Unauthorized("This session has been logged out, please login again".to_string())
+                .with_code("session_revoked"),
+            AuthError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+}
+
+// Resolve a bearer token to the user and session it was issued for
+async fn authenticate(state: &AppState, token: &str) -> Result<AuthUser, AuthError> {
+    if token.starts_with(access_tokens::TOKEN_PREFIX) {
+        return authenticate_access_token(state, token).await;
+    }
+
+    let claims = verify_jwt(&state.config.auth.jwt_secret, token).map_err(|_| AuthError::InvalidToken)?;
+
+    // Verify user still exists in database
+    let user_id = ObjectId::parse_str(&claims.sub).map_err(|_| AuthError::InvalidUserId)?;
+    let user = state.users
+        .get(user_id)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or(AuthError::UserNotFound)?;
+
+    // Verify the session behind the token has not been revoked
+    let session_id = ObjectId::parse_str(&claims.jti).map_err(|_| AuthError::SessionRevoked)?;
+    let session = state.sessions
+        .find_active(session_id, user_id)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or(AuthError::SessionRevoked)?;
+
+    // Impersonation tokens only work on the impersonation session they were issued for
+    let actor_id = claims.act.as_ref().and_then(|act| ObjectId::parse_str(&act.sub).ok());
+    if actor_id != session.impersonator_id {
+        return Err(AuthError::InvalidToken);
+    }
+
+    Ok(AuthUser {
+        id: user_id,
+        username: user.username,
+        email: user.email,
+        two_factor_enforced: two_factor_enforced(state, &user.role),
+        role: user.role,
+        session_id: Some(session_id),
+        access_token: None,
+        two_factor_verified: session.two_factor_verified,
+        impersonator_id: session.impersonator_id,
+    })
+}
+
+async fn authenticate_access_token(state: &AppState, token: &str) -> Result<AuthUser, AuthError> {
+    let access_token = access_tokens::find_active(state, token)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or(AuthError::InvalidToken)?;
+    let token_id = access_token.id.ok_or(AuthError::InvalidToken)?;
+
+    let user = state.users
+        .get(access_token.user_id)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or(AuthError::UserNotFound)?;
+
+    access_tokens::touch(state, token_id).await;
+
+    Ok(AuthUser {
+        id: access_token.user_id,
+        username: user.username,
+        email: user.email,
+        two_factor_enforced: two_factor_enforced(state, &user.role),
+        role: user.role,
+        session_id: None,
+        access_token: Some(TokenGrant {
+            id: token_id,
+            name: access_token.name,
+            scopes: access_token.scopes,
+        }),
+        two_factor_verified: access_token.two_factor_verified,
+        impersonator_id: None,
+    })
+}
+
 pub async fn auth_middleware(
     State(state): State<AppState>,
     mut request: Request,
@@ -74,126 +283,133 @@ pub async fn auth_middleware(
 ) -> Response {
     let uri = request.uri().clone();
     let method = request.method().clone();
-    println!("=== AUTH MIDDLEWARE: {} {} ===", method, uri);
-    
-    let auth_header = request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|header| header.to_str().ok());
 
-    let token = if let Some(auth_header) = auth_header {
-        if auth_header.starts_with("Bearer ") {
-            Some(&auth_header[7..])
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let token = match token {
+    let token = match bearer_token(request.headers()) {
         Some(token) => token,
-        None => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Missing authorization token",
-                    "message": "Please provide a valid JWT token in Authorization header"
-                }))
-            ).into_response();
-        }
+        None => return AuthError::MissingToken.into_response(),
     };
 
-    let claims = match verify_jwt(token) {
-        Ok(claims) => claims,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Invalid or expired token",
-                    "message": "Please login again to get a new token"
-                }))
-            ).into_response();
-        }
+    let auth_user = match authenticate(&state, token).await {
+        Ok(auth_user) => auth_user,
+        Err(e) => return e.into_response(),
     };
 
-    // Verify user still exists in database
-    let user_id = match ObjectId::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Invalid user ID in token"
-                }))
-            ).into_response();
-        }
-    };
+    // Everything done with an impersonation token is attributed to the admin
+    if let Some(admin_id) = auth_user.impersonator_id {
+        let details = serde_json::json!({ "method": method.as_str(), "path": uri.path() });
+        audit::record(&state, admin_id, "impersonation.request", Some(auth_user.id), details, None).await;
+    }
 
-    let user = match state.users
-        .find_one(mongodb::bson::doc! { "_id": user_id })
-        .await
-    {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "User not found",
-                    "message": "User associated with this token no longer exists"
-                }))
-            ).into_response();
-        }
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Database error"
-                }))
-            ).into_response();
-        }
-    };
+    // Writes made by scripts are kept per token; reads only bump `last_used_at`
+    if let Some(grant) = auth_user.access_token.as_ref().filter(|_| !method.is_safe()) {
+        let details = serde_json::json!({ "method": method.as_str(), "path": uri.path(), "token_name": grant.name });
+        audit::record(&state, auth_user.id, "access_token.request", Some(grant.id), details, None).await;
+    }
 
-    // Add user info to request extensions
-    let auth_user = AuthUser {
-        id: claims.sub,
-        username: user.username,
-        email: user.email,
-        role: user.role,
-    };
+    tracing::Span::current().record("user_id", tracing::field::display(auth_user.id));
 
+    // Add user info to request extensions
     request.extensions_mut().insert(auth_user);
     next.run(request).await
 }
 
-pub async fn admin_middleware(
+// The current user, taken only from the bearer token. Reuses the result of
+// `auth_middleware` when it already ran, otherwise authenticates on its own.
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(auth_user) = parts.extensions.get::<AuthUser>() {
+            return Ok(auth_user.clone());
+        }
+
+        let token = bearer_token(&parts.headers).ok_or(AuthError::MissingToken)?;
+        let auth_user = authenticate(state, token).await?;
+        tracing::Span::current().record("user_id", tracing::field::display(auth_user.id));
+        parts.extensions.insert(auth_user.clone());
+        Ok(auth_user)
+    }
+}
+
+// The current user if the request carries a token. A missing token is
+// anonymous; a bad one is still rejected so clients notice expired logins.
+#[derive(Debug, Clone)]
+pub struct MaybeAuthUser(pub Option<AuthUser>);
+
+impl FromRequestParts<AppState> for MaybeAuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if parts.extensions.get::<AuthUser>().is_none() && bearer_token(&parts.headers).is_none() {
+            return Ok(MaybeAuthUser(None));
+        }
+
+        AuthUser::from_request_parts(parts, state).await.map(|user| MaybeAuthUser(Some(user)))
+    }
+}
+
+async fn permission_guard(
+    permission: Permission,
     request: Request,
     next: Next,
 ) -> Response {
     let auth_user = request.extensions().get::<AuthUser>().cloned();
 
-    match auth_user {
-        Some(user) => {
-            match user.role {
-                Role::Admin => next.run(request).await,
-                Role::Member => {
-                    (
-                        StatusCode::FORBIDDEN,
-                        Json(serde_json::json!({
-                            "error": "Insufficient permissions",
-                            "message": "This endpoint requires admin privileges"
-                        }))
-                    ).into_response()
-                }
-            }
+    let error = match auth_user {
+        Some(user) if user.needs_two_factor() => {
+            AppError::Forbidden("Admins must enable 2FA and sign in with it to use this endpoint".to_string())
+                .with_code("two_factor_required")
         }
-        None => {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "error": "Authentication required"
-                }))
-            ).into_response()
+        Some(user) if user.can(permission) => return next.run(request).await,
+        Some(_) => {
+            AppError::Forbidden("This endpoint requires a role with this permission".to_string())
+                .with_code("insufficient_permissions")
+                .with_details(serde_json::json!({ "required_permission": permission }))
         }
+        None => AppError::Unauthorized("Authentication required".to_string()),
+    };
+    error.into_response()
+}
+
+// Layer function for `middleware::from_fn`; must run inside `auth_middleware`
+pub fn require_permission(
+    permission: Permission,
+) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send>> + Clone {
+    move |request, next| Box::pin(permission_guard(permission, request, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(role: Role, scopes: Option<Vec<Permission>>) -> AuthUser {
+        AuthUser {
+            id: ObjectId::new(),
+            username: "bot-owner".to_string(),
+            email: "owner@example.com".to_string(),
+            role,
+            session_id: None,
+            access_token: scopes.map(|scopes| TokenGrant { id: ObjectId::new(), name: "bot".to_string(), scopes }),
+            two_factor_verified: true,
+            two_factor_enforced: false,
+            impersonator_id: None,
+        }
+    }
+
+    #[test]
+    fn access_token_is_limited_to_its_scopes() {
+        let token = user(Role::Treasurer, Some(vec![Permission::CoinsGrant]));
+        assert!(token.can(Permission::CoinsGrant));
+
+        let unscoped = user(Role::Treasurer, Some(vec![]));
+        assert!(!unscoped.can(Permission::CoinsGrant));
+        assert!(unscoped.require_owner().is_err());
+    }
+
+    #[test]
+    fn scopes_never_exceed_the_role() {
+        // Scoped while the owner was an event coordinator, then demoted
+        let demoted = user(Role::Member, Some(vec![Permission::EventsWrite]));
+        assert!(!demoted.can(Permission::EventsWrite));
     }
 }
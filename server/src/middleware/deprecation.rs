@@ -0,0 +1,24 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+// RFC 9745 date the pre-/api/v1 routes were deprecated: 2026-10-18T00:00:00Z
+const DEPRECATED_SINCE: HeaderValue = HeaderValue::from_static("@1792281600");
+
+// Where clients find each legacy route's successor
+const DEPRECATION_LINK: HeaderValue = HeaderValue::from_static("</docs>; rel=\"deprecation\"; type=\"text/html\"");
+
+// Marks every response of a legacy route, errors included, so clients can find
+// what they still call before the aliases go away
+pub async fn deprecation(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, DEPRECATED_SINCE);
+    headers.append(header::LINK, DEPRECATION_LINK);
+    response
+}
@@ -1,3 +1,11 @@
 pub mod auth;
+pub mod request_id;
+pub mod metrics;
+pub mod deprecation;
+pub mod rate_limit;
 
-pub use auth::{auth_middleware, admin_middleware, create_jwt};
+pub use auth::{auth_middleware, require_permission, create_jwt};
+pub use request_id::request_id;
+pub use metrics::track_metrics;
+pub use deprecation::deprecation;
+pub use rate_limit::rate_limit;
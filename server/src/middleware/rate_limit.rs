@@ -0,0 +1,112 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderName,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::db::AppState;
+use crate::error::AppError;
+use crate::middleware::auth::AuthUser;
+use crate::rate_limit::{Decision, KeyBy, Policy};
+
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+// Layer function for `middleware::from_fn_with_state`. `KeyBy::User` policies
+// must run inside `auth_middleware`; without a user they fall back to the IP.
+pub fn rate_limit(
+    policy: Policy,
+) -> impl Fn(State<AppState>, Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send>> + Clone {
+    move |State(state), request, next| Box::pin(limit(state, policy, request, next))
+}
+
+async fn limit(state: AppState, policy: Policy, request: Request, next: Next) -> Response {
+    let config = &state.config.rate_limit;
+    if !config.enabled {
+        return next.run(request).await;
+    }
+
+    let caller = match (policy.key, request.extensions().get::<AuthUser>()) {
+        (KeyBy::User, Some(user)) => format!("user:{}", user.id),
+        _ => match client_ip(&request, config.trusted_proxies) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        },
+    };
+
+    match state.rate_limits.take(&format!("{}:{}", policy.name, caller), &policy).await {
+        Ok(Decision::Allowed) => next.run(request).await,
+        Ok(Decision::Limited(wait)) => {
+            tracing::info!(policy = policy.name, %caller, "rate limited");
+            AppError::RateLimited(whole_seconds(wait)).into_response()
+        }
+        // A broken shared store shouldn't take the API down with it
+        Err(e) => {
+            tracing::error!(error = %e, policy = policy.name, "rate limit store failed");
+            next.run(request).await
+        }
+    }
+}
+
+// Retry-After takes whole seconds; round up so a retry isn't refused again
+fn whole_seconds(wait: Duration) -> u64 {
+    (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).max(1)
+}
+
+// Behind `trusted_proxies` proxies the client is the entry the outermost one
+// appended to X-Forwarded-For; entries left of it are whatever the client sent.
+// Otherwise it's the peer address from `into_make_service_with_connect_info`.
+pub fn client_ip(request: &Request, trusted_proxies: usize) -> Option<IpAddr> {
+    let peer = || request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    if trusted_proxies == 0 {
+        return peer();
+    }
+
+    let forwarded: Vec<&str> = request
+        .headers()
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    forwarded
+        .len()
+        .checked_sub(trusted_proxies)
+        .and_then(|index| forwarded[index].parse().ok())
+        .or_else(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(forwarded: &[&str]) -> Request {
+        let mut request = Request::builder();
+        for value in forwarded {
+            request = request.header(X_FORWARDED_FOR, *value);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 9], 443))));
+        request
+    }
+
+    #[test]
+    fn trusts_only_what_the_proxies_appended() {
+        let ip = |text: &str| Some(text.parse::<IpAddr>().unwrap());
+
+        // Directly exposed: X-Forwarded-For is the client's to forge
+        assert_eq!(client_ip(&request(&["203.0.113.7"]), 0), ip("10.0.0.9"));
+        // One proxy: the last entry is the one it saw
+        assert_eq!(client_ip(&request(&["1.1.1.1, 203.0.113.7"]), 1), ip("203.0.113.7"));
+        assert_eq!(client_ip(&request(&["1.1.1.1", "203.0.113.7, 198.51.100.2"]), 2), ip("203.0.113.7"));
+        // Fewer entries than proxies: the header didn't come through them
+        assert_eq!(client_ip(&request(&[]), 1), ip("10.0.0.9"));
+        assert_eq!(whole_seconds(Duration::from_millis(1500)), 2);
+    }
+}
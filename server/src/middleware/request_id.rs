@@ -0,0 +1,122 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::Instrument;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longer or odd-looking ids from clients are replaced, so they can't inject into logs
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// Id of the request being handled, for error bodies
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+fn incoming_id(request: &Request) -> Option<String> {
+    let id = request.headers().get(&X_REQUEST_ID)?.to_str().ok()?;
+    let valid = !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then(|| id.to_string())
+}
+
+// Outermost layer: one span per request, keyed by the caller's X-Request-Id or a
+// fresh UUID, and a single completion line with status and latency. The route is
+// the template (`/projects/{id}`), never the raw path with its query string.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = incoming_id(&request).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        route = %route,
+        user_id = tracing::field::Empty,
+    );
+
+    let started = Instant::now();
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span.clone()))
+        .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let status = response.status();
+
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::error!(status = status.as_u16(), latency_ms, "request failed");
+        } else {
+            tracing::info!(status = status.as_u16(), latency_ms, "request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    use crate::error::AppError;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/missing/{id}",
+                get(|| async { Err::<(), _>(AppError::NotFound("Project not found".to_string())) }),
+            )
+            .layer(axum::middleware::from_fn(request_id))
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn propagates_the_callers_id_into_header_and_error_body() {
+        let request = Request::builder()
+            .uri("/missing/1")
+            .header(&X_REQUEST_ID, "client-abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[&X_REQUEST_ID], "client-abc-123");
+        let body = body_json(response).await;
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["request_id"], "client-abc-123");
+    }
+
+    #[tokio::test]
+    async fn replaces_missing_or_unsafe_ids() {
+        let request = Request::builder()
+            .uri("/missing/1")
+            .header(&X_REQUEST_ID, "bad id <script>")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+
+        let header = response.headers()[&X_REQUEST_ID].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+        assert_eq!(body_json(response).await["request_id"], header);
+    }
+}
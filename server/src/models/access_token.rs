@@ -0,0 +1,25 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::Permission;
+
+// Personal access token for scripts and bots, sent as `Authorization: Bearer iris_pat_...`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessToken {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub name: String,                   // What the owner calls it, e.g. "leaderboard bot"
+    pub token_hash: String,             // SHA-256 of the full token, which is shown only once
+    pub token_prefix: String,           // First characters of the token, to recognise it in lists
+    #[serde(default)]
+    pub scopes: Vec<Permission>,        // Permissions the token may use, on top of member access
+    #[serde(default)]
+    pub two_factor_verified: bool,      // Created from a 2FA-verified session
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime>,
+}
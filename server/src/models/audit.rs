@@ -0,0 +1,25 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::openapi::Oid;
+
+// Append-only record of sensitive actions (impersonation, token management)
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuditEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
+    pub id: Option<ObjectId>,
+    pub action: String,                 // e.g. "user.impersonate"
+    #[schema(value_type = Oid)]
+    pub actor_id: ObjectId,             // Who did it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
+    pub target_id: Option<ObjectId>,    // Who or what it was done to
+    #[serde(default)]
+    pub details: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[schema(value_type = Object)]
+    pub created_at: DateTime,
+}
@@ -1,20 +1,30 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Blog {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
     pub title: String,
     pub slug: String,
     pub description: String,
     pub content: String,              // Markdown content
     pub author_name: String,
+    #[schema(value_type = Oid)]
     pub author_id: ObjectId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_url: Option<String>,    // Cover image
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime,
 }
@@ -1,29 +1,46 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct CoinTransaction {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
+    #[schema(value_type = Oid)]
     pub user_id: ObjectId,
     pub amount: i32,
+    #[schema(value_type = Oid)]
     pub admin_id: ObjectId,
     pub reason: String,
-    pub created_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct WeeklyLeaderboard {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
-    pub week_start: String,
-    pub week_end: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub week_start: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub week_end: DateTime,
     pub rankings: Vec<LeaderboardEntry>,
-    pub created_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct LeaderboardEntry {
+    #[schema(value_type = Oid)]
     pub user_id: ObjectId,
     pub username: String,
     pub coins_earned: i32,
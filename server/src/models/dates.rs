@@ -0,0 +1,98 @@
+use mongodb::bson::{Bson, DateTime, Document, RawDocumentBuf};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+// Dates are stored as BSON dates but go out in API responses as RFC 3339
+// strings, which is what the frontend parses. The driver's (de)serializers are
+// the non-human-readable ones; serde_json is human readable. Reading also
+// accepts RFC 3339 strings, so documents written before the `native_dates`
+// migration still load.
+//
+// `bson::to_document`/`to_bson` count as human readable and would store strings;
+// build update documents from structs with `to_stored_document` instead.
+
+pub mod bson_date {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(date: &DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            date.try_to_rfc3339_string().map_err(ser::Error::custom)?.serialize(serializer)
+        } else {
+            date.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+        match Bson::deserialize(deserializer)? {
+            Bson::DateTime(date) => Ok(date),
+            Bson::String(text) => DateTime::parse_rfc3339_str(&text).map_err(de::Error::custom),
+            other => Err(de::Error::custom(format!("expected a date, got {}", other))),
+        }
+    }
+}
+
+pub mod optional_bson_date {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(date: &Option<DateTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => bson_date::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime>, D::Error> {
+        match Bson::deserialize(deserializer)? {
+            Bson::Null => Ok(None),
+            Bson::DateTime(date) => Ok(Some(date)),
+            Bson::String(text) => DateTime::parse_rfc3339_str(&text).map(Some).map_err(de::Error::custom),
+            other => Err(de::Error::custom(format!("expected a date, got {}", other))),
+        }
+    }
+}
+
+// Like `bson::to_document`, but dates stay BSON dates
+pub fn to_stored_document<T: Serialize>(value: &T) -> mongodb::bson::ser::Result<Document> {
+    let raw: RawDocumentBuf = mongodb::bson::to_raw_document_buf(value)?;
+    raw.to_document().map_err(ser::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Stamped {
+        #[serde(with = "bson_date")]
+        created_at: DateTime,
+        #[serde(default, with = "optional_bson_date", skip_serializing_if = "Option::is_none")]
+        ends_at: Option<DateTime>,
+    }
+
+    #[test]
+    fn stored_as_dates_and_sent_as_strings() {
+        let created_at = DateTime::parse_rfc3339_str("2025-03-01T10:00:00Z").unwrap();
+        let stamped = Stamped { created_at, ends_at: Some(created_at) };
+
+        let stored = to_stored_document(&stamped).unwrap();
+        assert_eq!(stored.get_datetime("created_at").unwrap(), &created_at);
+        assert_eq!(stored.get_datetime("ends_at").unwrap(), &created_at);
+
+        let json = serde_json::to_value(&stamped).unwrap();
+        assert_eq!(json["created_at"], "2025-03-01T10:00:00Z");
+    }
+
+    #[test]
+    fn reads_legacy_strings_and_native_dates() {
+        let legacy = doc! { "created_at": "2025-03-01T15:30:00.123456789+05:30" };
+        let raw = RawDocumentBuf::from_document(&legacy).unwrap();
+        let stamped: Stamped = mongodb::bson::from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(stamped.created_at.try_to_rfc3339_string().unwrap(), "2025-03-01T10:00:00.123Z");
+        assert_eq!(stamped.ends_at, None);
+
+        let native = doc! { "created_at": DateTime::from_millis(0) };
+        let raw = RawDocumentBuf::from_document(&native).unwrap();
+        let stamped: Stamped = mongodb::bson::from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(stamped.created_at, DateTime::from_millis(0));
+    }
+}
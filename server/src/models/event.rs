@@ -1,7 +1,11 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::{bson_date, optional_bson_date};
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum EventType {
     Workshop,
     Competition,
@@ -10,14 +14,14 @@ pub enum EventType {
     Other,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum EventStatus {
     Upcoming,
     Ongoing,
     Completed,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct EventSpeaker {
     pub name: String,
     pub role: String,
@@ -25,15 +29,19 @@ pub struct EventSpeaker {
     pub avatar: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Event {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
     pub title: String,
-    pub date: String,
-    pub time: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub end_date: Option<String>,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub starts_at: DateTime,
+    #[serde(default, with = "optional_bson_date", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub ends_at: Option<DateTime>,
+    pub timezone: String,                // IANA name the times were entered in, e.g. "Asia/Kolkata"
     pub location: String,
     pub event_type: EventType,
     pub status: EventStatus,
@@ -47,7 +55,12 @@ pub struct Event {
     pub recap_link: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub speakers: Option<Vec<EventSpeaker>>,
+    #[schema(value_type = Oid)]
     pub created_by: ObjectId,            // Admin who created it
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime,
 }
@@ -1,9 +1,14 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct GalleryItem {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
     pub title: String,
     pub category: String,          // "Hackathon", "Workshop", "Competition", "Team", "Project", "Other"
@@ -11,7 +16,10 @@ pub struct GalleryItem {
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_url: Option<String>,
+    #[schema(value_type = Oid)]
     pub uploaded_by: ObjectId,      // Admin who uploaded
     pub featured: bool,
-    pub created_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
 }
@@ -0,0 +1,21 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::openapi::Oid;
+
+// Email address an admin has allowed to sign up while the signup policy is restricted
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Invite {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
+    pub id: Option<ObjectId>,
+    pub email: String,             // Stored lowercase
+    #[schema(value_type = Oid)]
+    pub invited_by: ObjectId,
+    #[schema(value_type = Object)]
+    pub created_at: DateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub accepted_at: Option<DateTime>,
+}
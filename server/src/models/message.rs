@@ -1,26 +1,36 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum MessageType {
     Individual,      // Message to a single member
     ProjectTeam,     // Message to all members of a project
     Broadcast,       // Message to all members
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Message {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
+    #[schema(value_type = Oid)]
     pub sender_id: ObjectId,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<Oid>>)]
     pub recipient_ids: Option<Vec<ObjectId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub project_id: Option<ObjectId>,  // For project-specific messages
     pub subject: String,
     pub content: String,
     pub message_type: MessageType,
-    pub created_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
     #[serde(default)]
     pub read: bool,
 }
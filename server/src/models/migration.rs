@@ -0,0 +1,21 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationState {
+    Running,   // Claimed by an instance; others wait for it
+    Applied,
+}
+
+// One schema migration, keyed by its version. Rolling back deletes the record.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MigrationRecord {
+    #[serde(rename = "_id")]
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub started_at: DateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<DateTime>,
+}
@@ -6,12 +6,29 @@ pub mod coin;
 pub mod gallery;
 pub mod event;
 pub mod blog;
+pub mod oauth_state;
+pub mod session;
+pub mod password_reset;
+pub mod invite;
+pub mod two_factor_challenge;
+pub mod audit;
+pub mod access_token;
+pub mod migration;
+pub mod dates;
 
-pub use user::{User, Role};
+pub use user::{User, Role, AccountStatus, LinkedIdentity, TwoFactor, UserResponse};
 pub use project::{Project, ProjectStatus, ProjectFile};
-pub use project_join_request::{ProjectJoinRequest, JoinRequestStatus, CreateJoinRequest, UpdateJoinRequestStatus};
+pub use project_join_request::{ProjectJoinRequest, JoinRequestStatus, CreateJoinRequest, UpdateJoinRequestStatus, parse_decision};
 pub use message::{Message, MessageType};
 pub use coin::{CoinTransaction, WeeklyLeaderboard, LeaderboardEntry};
 pub use gallery::GalleryItem;
 pub use event::{Event, EventType, EventStatus, EventSpeaker};
-pub use blog::Blog;
\ No newline at end of file
+pub use blog::Blog;
+pub use oauth_state::OAuthState;
+pub use session::Session;
+pub use password_reset::PasswordReset;
+pub use invite::Invite;
+pub use two_factor_challenge::TwoFactorChallenge;
+pub use audit::AuditEntry;
+pub use access_token::AccessToken;
+pub use migration::{MigrationRecord, MigrationState};
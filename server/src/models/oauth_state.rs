@@ -0,0 +1,19 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// Pending OAuth login, created by /auth/{provider} and consumed once by its callback
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OAuthState {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub state: String,
+    #[serde(default)]
+    pub provider: String,             // "github" or an OIDC provider name
+    pub pkce_verifier: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,        // OIDC only, must come back in the ID token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_user_id: Option<ObjectId>,   // Set when a signed-in user is linking GitHub to their account
+    pub created_at: DateTime,
+    pub expires_at: DateTime,      // TTL index removes the document after this
+}
@@ -0,0 +1,13 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// Outstanding password reset, consumed once by /auth/password/reset
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordReset {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub token_hash: String,        // SHA-256 of the emailed token
+    pub created_at: DateTime,
+    pub expires_at: DateTime,      // TTL index removes the document after this
+}
@@ -1,41 +1,57 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum ProjectStatus {
     Active,
     Completed,
     OnHold,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ProjectFile {
     #[serde(rename = "_id")]
+    #[schema(value_type = Oid)]
     pub id: ObjectId,
     pub name: String,
     pub url: String,
     pub file_type: String, // "stl" or "dxf"
     pub size: i64, // in bytes
+    #[schema(value_type = Oid)]
     pub uploaded_by: ObjectId,
-    pub uploaded_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub uploaded_at: DateTime,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Project {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
     pub name: String,
     pub description: String,
     pub status: ProjectStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<Oid>>)]
     pub member_ids: Option<Vec<ObjectId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub project_lead_id: Option<ObjectId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub github_link: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub files: Option<Vec<ProjectFile>>,
+    #[schema(value_type = Oid)]
     pub created_by: ObjectId,
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime,
 }
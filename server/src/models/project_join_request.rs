@@ -1,8 +1,13 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+use crate::validate::not_one_of;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum JoinRequestStatus {
     Pending,
@@ -10,25 +15,48 @@ pub enum JoinRequestStatus {
     Rejected,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ProjectJoinRequest {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
     pub id: Option<ObjectId>,
+    #[schema(value_type = Oid)]
     pub project_id: ObjectId,
+    #[schema(value_type = Oid)]
     pub user_id: ObjectId,
     pub message: String,
     pub status: JoinRequestStatus,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateJoinRequest {
-    pub project_id: String,
+    #[validate(length(max = 1000))]
     pub message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct UpdateJoinRequestStatus {
+    #[validate(custom(function = "known_decision"))]
     pub status: String, // "approved" or "rejected"
 }
+
+const DECISIONS: &[&str] = &["approved", "rejected"];
+
+// What a lead may set a pending request to
+pub fn parse_decision(s: &str) -> Result<JoinRequestStatus, ValidationError> {
+    match s {
+        "approved" => Ok(JoinRequestStatus::Approved),
+        "rejected" => Ok(JoinRequestStatus::Rejected),
+        _ => Err(not_one_of(DECISIONS)),
+    }
+}
+
+fn known_decision(value: &str) -> Result<(), ValidationError> {
+    parse_decision(value).map(drop)
+}
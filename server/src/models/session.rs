@@ -0,0 +1,24 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// One login on one device. Access tokens carry the session id as `jti`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub refresh_token_hash: String,            // SHA-256 of the current refresh token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_token_hash: Option<String>,   // Last rotated-out token, used to detect reuse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub two_factor_verified: bool,             // Signed in with a TOTP or recovery code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator_id: Option<ObjectId>,     // Admin support session: short-lived, never refreshed
+    pub created_at: DateTime,
+    pub last_used_at: DateTime,
+    pub expires_at: DateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime>,
+}
@@ -0,0 +1,16 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+// First factor passed, waiting for a TOTP or recovery code at /auth/2fa/verify
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TwoFactorChallenge {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub token_hash: String,        // SHA-256 of the challenge token handed to the client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    pub attempts: i32,             // Wrong codes so far; the challenge dies after a few
+    pub created_at: DateTime,
+    pub expires_at: DateTime,      // TTL index removes the document after this
+}
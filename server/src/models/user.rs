@@ -1,24 +1,223 @@
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+use super::dates::bson_date;
+use crate::openapi::Oid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum Permission {
+    #[serde(rename = "users:manage")]
+    UsersManage,          // Add/delete users, assign roles, force logout
+    #[serde(rename = "projects:manage")]
+    ProjectsManage,       // Create/update/delete projects, assign members and leads
+    #[serde(rename = "coins:grant")]
+    CoinsGrant,           // Mint/remove coins, save leaderboard snapshots
+    #[serde(rename = "messages:broadcast")]
+    MessagesBroadcast,    // Send project team and broadcast messages
+    #[serde(rename = "messages:read_all")]
+    MessagesReadAll,      // Read every message in the system
+    #[serde(rename = "events:write")]
+    EventsWrite,          // Create/update/delete events, receive event proposals
+    #[serde(rename = "gallery:write")]
+    GalleryWrite,         // Create/update/delete gallery items
+    #[serde(rename = "blogs:moderate")]
+    BlogsModerate,        // Delete blogs written by other members
+}
+
+impl Permission {
+    pub const ALL: &'static [Permission] = &[
+        Permission::UsersManage,
+        Permission::ProjectsManage,
+        Permission::CoinsGrant,
+        Permission::MessagesBroadcast,
+        Permission::MessagesReadAll,
+        Permission::EventsWrite,
+        Permission::GalleryWrite,
+        Permission::BlogsModerate,
+    ];
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum Role {
     Admin,
+    EventCoordinator,
+    Editor,
+    Treasurer,
     Member,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Role {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Role::Admin => Permission::ALL,
+            Role::EventCoordinator => &[
+                Permission::EventsWrite,
+                Permission::GalleryWrite,
+                Permission::MessagesBroadcast,
+            ],
+            Role::Editor => &[Permission::BlogsModerate, Permission::GalleryWrite],
+            Role::Treasurer => &[Permission::CoinsGrant],
+            Role::Member => &[],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    // Parses the role names accepted by the admin API ("Admin", "Editor", ...)
+    pub fn parse(s: &str) -> Option<Role> {
+        match s {
+            "Admin" => Some(Role::Admin),
+            "EventCoordinator" => Some(Role::EventCoordinator),
+            "Editor" => Some(Role::Editor),
+            "Treasurer" => Some(Role::Treasurer),
+            "Member" => Some(Role::Member),
+            _ => None,
+        }
+    }
+
+    // Roles that hold a permission, for querying users by capability
+    pub fn with_permission(permission: Permission) -> Vec<Role> {
+        [Role::Admin, Role::EventCoordinator, Role::Editor, Role::Treasurer, Role::Member]
+            .into_iter()
+            .filter(|role| role.has_permission(permission))
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, ToSchema)]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    PendingApproval,    // Signed up outside the signup policy, waiting for an admin
+}
+
+// An external account that signs in as this user, e.g. a GitHub account.
+// Matched by the provider's immutable id, never by email or username.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct LinkedIdentity {
+    pub key: String,                    // "<provider>:<subject>", unique across all users
+    pub provider: String,               // "github", or an OIDC provider name
+    pub subject: String,                // Provider's immutable account id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,       // Provider login at link time, informational only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[schema(value_type = Object)]
+    pub linked_at: DateTime,
+}
+
+impl LinkedIdentity {
+    pub fn key(provider: &str, subject: &str) -> String {
+        format!("{}:{}", provider, subject)
+    }
+
+    pub fn new(provider: &str, subject: &str, username: Option<String>, email: Option<String>) -> Self {
+        LinkedIdentity {
+            key: LinkedIdentity::key(provider, subject),
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+            username,
+            email,
+            linked_at: DateTime::now(),
+        }
+    }
+}
+
+// TOTP second factor. Enrollment stores the secret with `enabled: false`
+// until the user proves their authenticator works.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TwoFactor {
+    pub secret: String,                     // Base32, as shown to authenticator apps
+    pub enabled: bool,
+    #[serde(default)]
+    pub recovery_code_hashes: Vec<String>,  // SHA-256 of unused recovery codes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_step: Option<i64>,        // 30s step of the last accepted code, blocks replays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
     pub username: String,
     pub full_name: String,
     pub email: String,
-    pub password_hash: String,
+    #[serde(default)]
+    pub password_hash: String,          // Argon2id PHC string, empty for OAuth-only accounts
+    pub role: Role,
+    #[serde(default)]
+    pub status: AccountStatus,
+    pub coins: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_ids: Option<Vec<ObjectId>>,
+    #[serde(default)]
+    pub failed_login_attempts: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<DateTime>,
+    #[serde(default)]
+    pub linked_identities: Vec<LinkedIdentity>,
+    #[serde(default)]
+    pub legacy_github_unresolved: bool, // Pre-identity GitHub account the backfill couldn't look up; cleared once linked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub two_factor: Option<TwoFactor>,
+    #[serde(with = "bson_date")]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    pub updated_at: DateTime,
+}
+
+// What the API returns for a user: never the password hash or lockout state
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserResponse {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Oid>)]
+    pub id: Option<ObjectId>,
+    pub username: String,
+    pub full_name: String,
+    pub email: String,
     pub role: Role,
+    pub status: AccountStatus,
     pub coins: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<Oid>>)]
     pub project_ids: Option<Vec<ObjectId>>,
-    pub created_at: String,
-    pub updated_at: String,
-}
\ No newline at end of file
+    pub linked_identities: Vec<LinkedIdentity>,
+    pub two_factor_enabled: bool,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime,
+    #[serde(with = "bson_date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime,
+}
+
+impl User {
+    pub fn two_factor_enabled(&self) -> bool {
+        self.two_factor.as_ref().is_some_and(|two_factor| two_factor.enabled)
+    }
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        let two_factor_enabled = user.two_factor_enabled();
+        UserResponse {
+            id: user.id,
+            username: user.username,
+            full_name: user.full_name,
+            email: user.email,
+            role: user.role,
+            status: user.status,
+            coins: user.coins,
+            project_ids: user.project_ids,
+            linked_identities: user.linked_identities,
+            two_factor_enabled,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use axum::{response::Html, Json};
+use std::borrow::Cow;
+use std::sync::LazyLock;
+use utoipa::openapi::path::{Operation, PathItem};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::example::ExampleBuilder;
+use utoipa::openapi::{ContentBuilder, Deprecated, OpenApi as Spec, Ref, RefOr, Response, ResponseBuilder, Schema};
+use utoipa::{Modify, OpenApi, PartialSchema, ToResponse, ToSchema};
+use utoipa_scalar::Scalar;
+
+use crate::error::ErrorBody;
+use crate::routes::legacy::SUCCESSORS;
+use crate::{auth, routes};
+
+// The API as clients see it. Handlers describe themselves with `#[utoipa::path]`
+// and are listed here; a route registered in `create_routes` but missing from
+// this list fails `every_route_is_documented`. Legacy routes that are plain
+// aliases need no entry, `LegacyRoutes` copies their successor.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "IRIS Server API",
+        description = "Everything lives under `/api/v1`; the older unversioned routes are deprecated aliases. Errors share one body (`ErrorBody`); list endpoints return one page at a time with `next_cursor` and `total`."
+    ),
+    paths(
+        routes::root_handler,
+        routes::health::get_live,
+        routes::health::get_ready,
+        routes::metrics::get_metrics,
+        get_openapi,
+        get_docs,
+        auth::github_login,
+        auth::github_callback,
+        auth::oidc::oidc_login,
+        auth::oidc::oidc_callback,
+        auth::get_providers,
+        auth::link_provider,
+        auth::identities::get_identities,
+        auth::identities::unlink_identity,
+        auth::password::register,
+        auth::password::login,
+        auth::password::forgot_password,
+        auth::password::reset_password,
+        auth::sessions::refresh_session,
+        auth::sessions::get_sessions,
+        auth::sessions::logout,
+        auth::two_factor::verify_challenge,
+        auth::two_factor::get_two_factor_status,
+        auth::two_factor::enroll,
+        auth::two_factor::confirm,
+        auth::two_factor::regenerate_recovery_codes,
+        auth::two_factor::disable,
+        auth::access_tokens::get_access_tokens,
+        auth::access_tokens::create_access_token,
+        auth::access_tokens::revoke_access_token,
+        auth::access_tokens::get_access_token_activity,
+        routes::users::get_users,
+        routes::users::add_user,
+        routes::users::delete_user,
+        routes::users::get_members,
+        routes::users::get_user_by_id,
+        routes::users::update_user_role,
+        routes::users::get_pending_users,
+        routes::users::approve_user,
+        routes::users::get_invites,
+        routes::users::create_invite,
+        auth::sessions::force_logout_user,
+        auth::impersonation::impersonate_user,
+        routes::audit::get_audit_log,
+        routes::projects::get_all_projects,
+        routes::projects::get_user_projects,
+        routes::projects::create_project,
+        routes::projects::update_project,
+        routes::projects::delete_project,
+        routes::projects::assign_member_to_project,
+        routes::projects::remove_member_from_project,
+        routes::projects::set_project_lead,
+        routes::projects::add_file_to_project,
+        routes::projects::delete_file_from_project,
+        routes::project_join_requests::create_join_request,
+        routes::project_join_requests::get_project_join_requests,
+        routes::project_join_requests::update_join_request_status,
+        routes::coins::manage_coins,
+        routes::coins::get_coin_transactions,
+        routes::coins::get_weekly_leaderboard,
+        routes::coins::save_weekly_leaderboard,
+        routes::messages::send_message,
+        routes::messages::get_user_messages,
+        routes::messages::get_all_messages,
+        routes::gallery::get_all_gallery,
+        routes::gallery::create_gallery_item,
+        routes::gallery::update_gallery_item,
+        routes::gallery::delete_gallery_item,
+        routes::events::get_all_events,
+        routes::events::create_event,
+        routes::events::update_event,
+        routes::events::delete_event,
+        routes::events::propose_event,
+        routes::blogs::get_all_blogs,
+        routes::blogs::get_blog_by_slug,
+        routes::blogs::create_blog,
+        routes::blogs::delete_blog,
+        routes::stats::get_stats,
+        routes::legacy::delete_user,
+        routes::legacy::update_user_role,
+        routes::legacy::approve_user,
+        routes::legacy::force_logout_user,
+        routes::legacy::impersonate_user,
+        routes::legacy::update_project,
+        routes::legacy::delete_project,
+        routes::legacy::assign_member_to_project,
+        routes::legacy::remove_member_from_project,
+        routes::legacy::remove_member_by_lead,
+        routes::legacy::set_project_lead,
+        routes::legacy::add_file_to_project,
+        routes::legacy::delete_file_from_project,
+        routes::legacy::create_join_request,
+        routes::legacy::manage_coins,
+        routes::legacy::update_gallery_item,
+        routes::legacy::delete_gallery_item,
+        routes::legacy::update_event,
+        routes::legacy::delete_event,
+        routes::legacy::delete_blog,
+    ),
+    components(schemas(ErrorBody), responses(SignIn)),
+    modifiers(&BearerAuth, &ErrorResponses, &Aliases),
+    tags(
+        (name = "auth", description = "Sign-in with GitHub, OIDC or a password; sessions and linked accounts"),
+        (name = "two-factor", description = "TOTP second factor and recovery codes"),
+        (name = "access-tokens", description = "Personal access tokens for scripts and bots"),
+        (name = "users", description = "Accounts, roles, invites, impersonation and the audit log"),
+        (name = "projects", description = "Projects, their members, lead and files"),
+        (name = "join-requests", description = "Members asking to join a project"),
+        (name = "coins", description = "Coin grants, history and the leaderboard"),
+        (name = "messages", description = "Direct, project team and broadcast messages"),
+        (name = "gallery", description = "Photos from past activities"),
+        (name = "events", description = "Workshops, competitions and other events"),
+        (name = "blogs", description = "Member-written posts"),
+        (name = "stats", description = "Homepage counters"),
+        (name = "operations", description = "Health probes and metrics"),
+        (name = "docs", description = "This document"),
+    )
+)]
+struct ApiDoc;
+
+// How an ObjectId comes out in JSON: `{ "$oid": "507f1f77bcf86cd799439011" }`.
+// Use as `#[schema(value_type = Oid)]` on `ObjectId` fields.
+pub struct Oid;
+
+impl PartialSchema for Oid {
+    fn schema() -> RefOr<Schema> {
+        let hex = ObjectBuilder::new()
+            .schema_type(Type::String)
+            .pattern(Some("^[0-9a-f]{24}$"))
+            .examples(["507f1f77bcf86cd799439011"]);
+        ObjectBuilder::new().property("$oid", hex).required("$oid").into()
+    }
+}
+
+impl ToSchema for Oid {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("ObjectId")
+    }
+}
+
+// What every sign-in answers with (`auth::login_response`), or a challenge to
+// finish at /auth/2fa/verify when the user has 2FA on
+pub struct SignIn;
+
+impl<'r> ToResponse<'r> for SignIn {
+    fn response() -> (&'r str, RefOr<Response>) {
+        let signed_in = serde_json::json!({
+            "success": true,
+            "message": "Successfully authenticated",
+            "user": {
+                "id": "507f1f77bcf86cd799439011",
+                "username": "johndoe",
+                "full_name": "John Doe",
+                "email": "john@example.com",
+                "role": "Member",
+                "permissions": [],
+                "coins": 150
+            },
+            "token": "eyJhbGciOiJIUzI1NiJ9...",
+            "refresh_token": "9f86d081884c7d659a2feaa0c55ad015...",
+            "expires_in": 900
+        });
+        let challenge = serde_json::json!({
+            "success": false,
+            "two_factor_required": true,
+            "challenge_token": "3a7bd3e2360a3d29eea436fcfb7e44c7...",
+            "expires_in": 300
+        });
+        let content = ContentBuilder::new()
+            .schema(Some(ObjectBuilder::new()))
+            .examples_from_iter([
+                ("signed_in", ExampleBuilder::new().value(Some(signed_in)).build()),
+                ("two_factor_required", ExampleBuilder::new().value(Some(challenge)).build()),
+            ])
+            .build();
+        let response = ResponseBuilder::new()
+            .description("Tokens and the user, or a 2FA challenge")
+            .content("application/json", content)
+            .build();
+        ("SignIn", response.into())
+    }
+}
+
+// Operations marked `security(("bearer" = []))` take an access token or a
+// personal access token in `Authorization: Bearer ...`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut Spec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+    }
+}
+
+// Any operation can fail with the shared error body; handlers only list the
+// error statuses worth calling out
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut Spec) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                add_error(operation, "default", "Error; `code` says which");
+                // Every JSON body goes through `ValidJson`
+                if operation.request_body.is_some() {
+                    add_error(operation, "422", "`validation_failed`: `details.fields` names each invalid field and what's wrong with it");
+                }
+            }
+        }
+    }
+}
+
+fn add_error(operation: &mut Operation, status: &str, description: &str) {
+    let response = ResponseBuilder::new()
+        .description(description)
+        .content("application/json", ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorBody"))).build())
+        .build();
+    operation.responses.responses.entry(status.to_string()).or_insert(response.into());
+}
+
+// Extra paths served by the handlers of a documented path: (alias, documented)
+const ALIASES: &[(&str, &str)] = &[("/health", "/health/live")];
+
+struct Aliases;
+
+impl Modify for Aliases {
+    fn modify(&self, openapi: &mut Spec) {
+        for (alias, path) in ALIASES {
+            let Some(mut item) = openapi.paths.paths.get(*path).cloned() else { continue };
+            // Operation ids must stay unique
+            for operation in [&mut item.get, &mut item.post, &mut item.put, &mut item.patch, &mut item.delete]
+                .into_iter()
+                .flatten()
+            {
+                operation.operation_id = None;
+            }
+            openapi.paths.paths.insert(alias.to_string(), item);
+        }
+    }
+}
+
+// Marks each pre-/api/v1 route deprecated and names its successor. Adapters
+// have their own operation; aliases get a copy of the successor's.
+struct LegacyRoutes;
+
+impl Modify for LegacyRoutes {
+    fn modify(&self, openapi: &mut Spec) {
+        for (legacy, successor) in SUCCESSORS {
+            let Some((method, path)) = legacy.split_once(' ') else { continue };
+            let Some((successor_method, successor_path)) = successor.split_once(' ') else { continue };
+
+            let adapter = openapi.paths.paths.get_mut(path).and_then(|item| operation(item, method).take());
+            let alias = || {
+                let mut item = openapi.paths.paths.get(successor_path)?.clone();
+                operation(&mut item, successor_method).take()
+            };
+            let Some(mut legacy_operation) = adapter.or_else(alias) else { continue };
+
+            legacy_operation.deprecated = Some(Deprecated::True);
+            legacy_operation.operation_id = None;
+            legacy_operation.description = Some(format!("Deprecated, use `{}`.", successor));
+            let item = openapi.paths.paths.entry(path.to_string()).or_default();
+            *operation(item, method) = Some(legacy_operation);
+        }
+    }
+}
+
+fn operation<'a>(item: &'a mut PathItem, method: &str) -> &'a mut Option<Operation> {
+    match method {
+        "GET" => &mut item.get,
+        "POST" => &mut item.post,
+        "PUT" => &mut item.put,
+        "PATCH" => &mut item.patch,
+        "DELETE" => &mut item.delete,
+        _ => panic!("no {} operations in this API", method),
+    }
+}
+
+static SPEC: LazyLock<Spec> = LazyLock::new(|| {
+    let mut spec = ApiDoc::openapi();
+    #[cfg(feature = "dev-login")]
+    spec.merge(DevLoginDoc::openapi());
+    // After the merge, so dev-login's legacy alias finds its successor
+    LegacyRoutes.modify(&mut spec);
+    spec
+});
+
+#[cfg(feature = "dev-login")]
+#[derive(OpenApi)]
+#[openapi(paths(crate::auth::test_login), modifiers(&ErrorResponses))]
+struct DevLoginDoc;
+
+// GET /openapi.json - Public: this API as an OpenAPI 3.1 document
+#[utoipa::path(get, path = "/openapi.json", tag = "docs", responses((status = 200, description = "OpenAPI document", body = Object)))]
+pub async fn get_openapi() -> Json<&'static Spec> {
+    Json(&SPEC)
+}
+
+// GET /docs - Public: interactive docs for /openapi.json
+#[utoipa::path(get, path = "/docs", tag = "docs", responses((status = 200, description = "HTML page", content_type = "text/html")))]
+pub async fn get_docs() -> Html<String> {
+    static PAGE: LazyLock<String> = LazyLock::new(|| Scalar::new(SPEC.clone()).to_html());
+    Html(PAGE.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    // Every `.route("/path", get(a).post(b))` in a router's source, as ("GET", "/prefix/path")
+    fn registered_routes(source: &str, prefix: &str) -> Vec<(String, String)> {
+        let route = Regex::new(r#"\.route\("([^"]+)",\s*(.+?)\)\s*\)?;?$"#).unwrap();
+        let method = Regex::new(r"\b(get|post|put|patch|delete)\(").unwrap();
+        let mut routes = Vec::new();
+        for line in source.lines() {
+            let Some(captures) = route.captures(line.trim()) else { continue };
+            for found in method.captures_iter(&captures[2]) {
+                routes.push((found[1].to_uppercase(), format!("{}{}", prefix, &captures[1])));
+            }
+        }
+        routes
+            .into_iter()
+            .filter(|(_, path)| cfg!(feature = "dev-login") || !path.ends_with("/auth/test-login"))
+            .collect()
+    }
+
+    fn documented(method: &str, path: &str) -> Option<Operation> {
+        let mut item = SPEC.paths.paths.get(path)?.clone();
+        operation(&mut item, method).take()
+    }
+
+    fn undocumented(routes: &[(String, String)]) -> Vec<String> {
+        routes
+            .iter()
+            .filter(|(method, path)| documented(method, path).is_none())
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect()
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let current = [registered_routes(include_str!("routes/mod.rs"), ""), registered_routes(include_str!("routes/v1.rs"), "/api/v1")].concat();
+        let legacy = registered_routes(include_str!("routes/legacy.rs"), "");
+        assert!(current.len() > 50, "only found {} routes; did create_routes change shape?", current.len());
+        assert!(legacy.len() > 50, "only found {} legacy routes", legacy.len());
+
+        let missing = [undocumented(&current), undocumented(&legacy)].concat();
+        assert!(missing.is_empty(), "routes missing from the OpenAPI spec: {:?}", missing);
+    }
+
+    #[test]
+    fn only_legacy_routes_are_deprecated() {
+        let deprecated = |(method, path): &(String, String)| {
+            documented(method, path).is_some_and(|operation| operation.deprecated == Some(Deprecated::True))
+        };
+        let current = [registered_routes(include_str!("routes/mod.rs"), ""), registered_routes(include_str!("routes/v1.rs"), "/api/v1")].concat();
+        let legacy = registered_routes(include_str!("routes/legacy.rs"), "");
+
+        let wrongly_deprecated: Vec<_> = current.iter().filter(|route| deprecated(route)).collect();
+        assert!(wrongly_deprecated.is_empty(), "current routes marked deprecated: {:?}", wrongly_deprecated);
+        let not_deprecated: Vec<_> = legacy.iter().filter(|route| !deprecated(route)).collect();
+        assert!(not_deprecated.is_empty(), "legacy routes not marked deprecated: {:?}", not_deprecated);
+    }
+
+    #[test]
+    fn spec_serializes() {
+        let json = serde_json::to_value(&*SPEC).unwrap();
+        assert!(json["components"]["schemas"]["ErrorBody"].is_object());
+        assert!(json["components"]["securitySchemes"]["bearer"].is_object());
+    }
+}
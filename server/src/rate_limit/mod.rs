@@ -0,0 +1,172 @@
+pub mod mongo;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use mongodb::Database;
+
+use crate::config::{RateLimitBackend, RateLimitConfig, RateLimitPolicy};
+
+// Token buckets, one per policy and caller. A bucket holds `requests` tokens
+// and gets them back one every `per / requests`; a request takes one or is
+// turned away with a 429 until the next one is back.
+//
+// A bucket is kept as the moment it will be full again (GCRA): a full bucket
+// needs no record at all, and taking a token is one compare-and-add, which
+// the Mongo backend can do in a single update.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    Ip,     // The client address, see `middleware::rate_limit::client_ip`
+    User,   // The signed-in user; must run inside `auth_middleware`
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Policy {
+    pub name: &'static str,   // Names the bucket, so routes under one policy share it
+    pub requests: u32,
+    pub per: Duration,
+    pub key: KeyBy,
+}
+
+impl Policy {
+    fn new(name: &'static str, config: RateLimitPolicy, key: KeyBy) -> Policy {
+        Policy { name, requests: config.requests, per: Duration::from_secs(config.per_seconds), key }
+    }
+
+    // How long one token takes to come back
+    pub fn interval(&self) -> Duration {
+        self.per / self.requests.max(1)
+    }
+}
+
+// One policy per route group, see `routes::create_routes`
+#[derive(Debug, Clone, Copy)]
+pub struct Policies {
+    pub general: Policy,    // Every API request
+    pub auth: Policy,       // Sign-in, signup, password resets and OAuth callbacks
+    pub outreach: Policy,   // Requests that notify other members: messages, event proposals, join requests
+}
+
+impl Policies {
+    pub fn from_config(config: &RateLimitConfig) -> Policies {
+        Policies {
+            general: Policy::new("general", config.general, KeyBy::Ip),
+            auth: Policy::new("auth", config.auth, KeyBy::Ip),
+            outreach: Policy::new("outreach", config.outreach, KeyBy::User),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed,
+    Limited(Duration),   // Until the next token is back
+}
+
+// Where buckets live. `MemoryLimiter` counts per process; `mongo::MongoLimiter`
+// shares the counts between instances.
+#[async_trait]
+pub trait RateLimitStore: Debug + Send + Sync {
+    async fn take(&self, key: &str, policy: &Policy) -> mongodb::error::Result<Decision>;
+}
+
+// Takes a token from a bucket that is full again at `full_at`; `Ok` is when it
+// will be full after this request
+fn take(full_at: Option<Instant>, now: Instant, policy: &Policy) -> Result<Instant, Duration> {
+    let next = full_at.map_or(now, |full_at| full_at.max(now)) + policy.interval();
+    let wait = next - now;
+    if wait <= policy.per {
+        Ok(next)
+    } else {
+        Err(wait - policy.per)
+    }
+}
+
+// Full buckets are dropped every this many requests
+const PRUNE_EVERY: u32 = 1024;
+
+#[derive(Debug, Default)]
+struct Buckets {
+    full_at: HashMap<String, Instant>,
+    takes: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryLimiter {
+    buckets: Mutex<Buckets>,
+}
+
+impl MemoryLimiter {
+    fn take_at(&self, key: &str, policy: &Policy, now: Instant) -> Decision {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        buckets.takes += 1;
+        if buckets.takes == PRUNE_EVERY {
+            buckets.takes = 0;
+            buckets.full_at.retain(|_, full_at| *full_at > now);
+        }
+
+        match take(buckets.full_at.get(key).copied(), now, policy) {
+            Ok(full_at) => {
+                buckets.full_at.insert(key.to_string(), full_at);
+                Decision::Allowed
+            }
+            Err(retry_after) => Decision::Limited(retry_after),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryLimiter {
+    async fn take(&self, key: &str, policy: &Policy) -> mongodb::error::Result<Decision> {
+        Ok(self.take_at(key, policy, Instant::now()))
+    }
+}
+
+// Picks the store from `rate_limit.backend` ("memory" or "mongo")
+pub fn from_config(config: &RateLimitConfig, database: &Database) -> Arc<dyn RateLimitStore> {
+    match config.backend {
+        RateLimitBackend::Mongo => Arc::new(mongo::MongoLimiter::new(database)),
+        RateLimitBackend::Memory => Arc::new(MemoryLimiter::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(requests: u32, per_seconds: u64) -> Policy {
+        Policy::new("test", RateLimitPolicy { requests, per_seconds }, KeyBy::Ip)
+    }
+
+    #[test]
+    fn allows_a_burst_then_refills_evenly() {
+        let limiter = MemoryLimiter::default();
+        let policy = policy(3, 60);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, start), Decision::Allowed);
+        }
+        assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, start), Decision::Limited(Duration::from_secs(20)));
+        // Other callers have their own bucket
+        assert_eq!(limiter.take_at("ip:10.0.0.2", &policy, start), Decision::Allowed);
+
+        let later = start + Duration::from_secs(15);
+        assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, later), Decision::Limited(Duration::from_secs(5)));
+        let later = start + Duration::from_secs(20);
+        assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, later), Decision::Allowed);
+        assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, later), Decision::Limited(Duration::from_secs(20)));
+
+        // Idle long enough, the bucket is full again but no fuller
+        let idle = start + Duration::from_secs(600);
+        for _ in 0..3 {
+            assert_eq!(limiter.take_at("ip:10.0.0.1", &policy, idle), Decision::Allowed);
+        }
+        assert!(matches!(limiter.take_at("ip:10.0.0.1", &policy, idle), Decision::Limited(_)));
+    }
+}
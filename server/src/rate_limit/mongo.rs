@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::{Collection, Database};
+use std::time::Duration;
+
+use super::{Decision, Policy, RateLimitStore};
+
+// Buckets in the `rate_limits` collection, one document per key:
+// `{ _id: "outreach:user:...", expires_at, wait, allowed }`. `expires_at` is
+// when the bucket is full again; the TTL index drops it from then on.
+#[derive(Debug)]
+pub struct MongoLimiter {
+    buckets: Collection<Document>,
+}
+
+impl MongoLimiter {
+    pub fn new(database: &Database) -> Self {
+        MongoLimiter { buckets: database.collection("rate_limits") }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MongoLimiter {
+    // `take` from the parent module as one atomic update, timed by the server's clock
+    async fn take(&self, key: &str, policy: &Policy) -> mongodb::error::Result<Decision> {
+        let interval = (policy.interval().as_millis() as i64).max(1);
+        let per = policy.per.as_millis() as i64;
+        let update = vec![
+            doc! { "$set": {
+                "wait": { "$subtract": [
+                    { "$add": [{ "$max": [{ "$ifNull": ["$expires_at", "$$NOW"] }, "$$NOW"] }, interval] },
+                    "$$NOW",
+                ] },
+            } },
+            doc! { "$set": {
+                "allowed": { "$lte": ["$wait", per] },
+                "expires_at": { "$cond": [
+                    { "$lte": ["$wait", per] },
+                    { "$add": ["$$NOW", "$wait"] },
+                    "$expires_at",
+                ] },
+            } },
+        ];
+
+        let bucket = self
+            .buckets
+            .find_one_and_update(doc! { "_id": key }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .unwrap_or_default();
+
+        if bucket.get_bool("allowed").unwrap_or(true) {
+            return Ok(Decision::Allowed);
+        }
+        let wait = bucket.get_i64("wait").or_else(|_| bucket.get_i32("wait").map(i64::from)).unwrap_or(per);
+        Ok(Decision::Limited(Duration::from_millis((wait - per).max(0) as u64)))
+    }
+}
@@ -0,0 +1,123 @@
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::{RepoError, RepoResult};
+use crate::models::dates::to_stored_document;
+
+// One page of a list: equality filters on stored fields, a sort on one field
+// with `_id` breaking ties, and where the previous page stopped. Backends fetch
+// `limit + 1` items and hand them to `Page::from_batch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListParams {
+    pub filters: Vec<(&'static str, Bson)>,
+    pub sort: Sort,
+    pub after: Option<Cursor>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub field: &'static str,
+    pub descending: bool,
+}
+
+// The last item of a page: its sort value and id. Opaque to clients, who get
+// it hex-encoded as `next_cursor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub value: Bson,
+    pub id: ObjectId,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::new();
+        // Writing a document to a Vec can't fail
+        let _ = doc! { "v": self.value.clone(), "id": self.id }.to_writer(&mut bytes);
+        hex::encode(bytes)
+    }
+
+    pub fn decode(text: &str) -> Option<Cursor> {
+        let bytes = hex::decode(text).ok()?;
+        let document = Document::from_reader(bytes.as_slice()).ok()?;
+        Some(Cursor { value: document.get("v")?.clone(), id: document.get_object_id("id").ok()? })
+    }
+
+    // Where a page ending in `item` stops, sorted by `field`
+    fn after<T: Serialize>(item: &T, field: &str) -> RepoResult<Cursor> {
+        let document = to_stored_document(item)?;
+        let id = document
+            .get_object_id("_id")
+            .map_err(|_| RepoError::Database(mongodb::error::Error::custom("listed a document without an _id")))?;
+        Ok(Cursor { value: document.get(field).cloned().unwrap_or(Bson::Null), id })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,   // None on the last page
+    pub total: u64,                    // Everything matching the filters, across pages
+}
+
+impl<T: Serialize> Page<T> {
+    // `batch` holds up to `limit + 1` items in order; the extra one only says there's more
+    pub fn from_batch(mut batch: Vec<T>, params: &ListParams, total: u64) -> RepoResult<Page<T>> {
+        let more = batch.len() > params.limit;
+        batch.truncate(params.limit);
+        let next_cursor = match batch.last() {
+            Some(last) if more => Some(Cursor::after(last, params.sort.field)?.encode()),
+            _ => None,
+        };
+        Ok(Page { items: batch, next_cursor, total })
+    }
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page { items: self.items.into_iter().map(f).collect(), next_cursor: self.next_cursor, total: self.total }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::DateTime;
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = Cursor { value: Bson::DateTime(DateTime::from_millis(1_700_000_000_000)), id: ObjectId::new() };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::decode("not hex"), None);
+        assert_eq!(Cursor::decode("00"), None);
+    }
+
+    #[derive(Serialize)]
+    struct Item {
+        #[serde(rename = "_id")]
+        id: ObjectId,
+        name: &'static str,
+    }
+
+    #[test]
+    fn extra_item_means_another_page() {
+        let params = ListParams {
+            filters: Vec::new(),
+            sort: Sort { field: "name", descending: false },
+            after: None,
+            limit: 2,
+        };
+        let (a, b) = (ObjectId::new(), ObjectId::new());
+        let items = || vec![Item { id: a, name: "a" }, Item { id: b, name: "b" }, Item { id: ObjectId::new(), name: "c" }];
+
+        let page = Page::from_batch(items(), &params, 3).unwrap();
+        assert_eq!(page.items.len(), 2);
+        let cursor = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor, Cursor { value: Bson::String("b".to_string()), id: b });
+
+        let mut last = items();
+        last.truncate(2);
+        assert_eq!(Page::from_batch(last, &params, 2).unwrap().next_cursor, None);
+    }
+}
@@ -0,0 +1,927 @@
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, Bson, DateTime, Document};
+use serde::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use super::{
+    AccessTokenRepository, AuditFilter, AuditRepository, BlogRepository, CoinRepository, EventChanges, EventRepository, GalleryChanges, GalleryRepository,
+    InviteRepository, JoinRequestRepository, ListParams, MessageRepository, OAuthStateRepository, Page,
+    PasswordResetRepository, ProjectChanges, ProjectRepository, RepoError, RepoResult, SessionRepository,
+    TwoFactorChallengeRepository, UserRepository,
+};
+use crate::models::dates::to_stored_document;
+use crate::models::{
+    AccessToken, AccountStatus, AuditEntry, Blog, CoinTransaction, Event, EventType, GalleryItem, Invite,
+    JoinRequestStatus,
+    LinkedIdentity, Message, OAuthState, PasswordReset, Project, ProjectFile, ProjectJoinRequest, ProjectStatus, Role, Session,
+    TwoFactor, TwoFactorChallenge, User, WeeklyLeaderboard,
+};
+
+// Every aggregate in process memory, for tests. One lock over all of it makes
+// writes that span aggregates atomic, and the unique indexes from the
+// migrations are checked by hand.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    data: Mutex<Data>,
+}
+
+#[derive(Debug, Default)]
+struct Data {
+    users: BTreeMap<ObjectId, User>,
+    sessions: BTreeMap<ObjectId, Session>,
+    projects: BTreeMap<ObjectId, Project>,
+    project_join_requests: BTreeMap<ObjectId, ProjectJoinRequest>,
+    coin_transactions: BTreeMap<ObjectId, CoinTransaction>,
+    leaderboards: Vec<WeeklyLeaderboard>,
+    messages: BTreeMap<ObjectId, Message>,
+    events: BTreeMap<ObjectId, Event>,
+    gallery: BTreeMap<ObjectId, GalleryItem>,
+    blogs: BTreeMap<ObjectId, Blog>,
+    invites: BTreeMap<ObjectId, Invite>,
+    oauth_states: BTreeMap<ObjectId, OAuthState>,
+    password_resets: BTreeMap<ObjectId, PasswordReset>,
+    two_factor_challenges: BTreeMap<ObjectId, TwoFactorChallenge>,
+    access_tokens: BTreeMap<ObjectId, AccessToken>,
+    audit_log: BTreeMap<ObjectId, AuditEntry>,
+}
+
+impl MemoryStore {
+    fn data(&self) -> MutexGuard<'_, Data> {
+        // A test that panicked mid-write poisons the lock; the data is still usable
+        self.data.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Stores a copy under a fresh id, like an insert with `_id` left out; an id the
+// caller picked is kept, as Mongo keeps a given `_id`
+fn insert<T: Clone>(map: &mut BTreeMap<ObjectId, T>, value: &T, id: impl FnOnce(&mut T) -> &mut Option<ObjectId>) -> ObjectId {
+    let mut value = value.clone();
+    let id = *id(&mut value).get_or_insert_with(ObjectId::new);
+    map.insert(id, value);
+    id
+}
+
+fn add_to_set(ids: &mut Option<Vec<ObjectId>>, id: ObjectId) {
+    let ids = ids.get_or_insert_with(Vec::new);
+    if !ids.contains(&id) {
+        ids.push(id);
+    }
+}
+
+fn pull(ids: &mut Option<Vec<ObjectId>>, id: ObjectId) {
+    if let Some(ids) = ids {
+        ids.retain(|other| *other != id);
+    }
+}
+
+// `$set` of only the fields a change gives
+fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        *field = value.clone();
+    }
+}
+
+fn set_some<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+    if value.is_some() {
+        field.clone_from(value);
+    }
+}
+
+// Orders the way Mongo does for the types the models store
+fn compare(a: &Bson, b: &Bson) -> Ordering {
+    match (a, b) {
+        (Bson::String(a), Bson::String(b)) => a.cmp(b),
+        (Bson::DateTime(a), Bson::DateTime(b)) => a.cmp(b),
+        (Bson::ObjectId(a), Bson::ObjectId(b)) => a.cmp(b),
+        (Bson::Boolean(a), Bson::Boolean(b)) => a.cmp(b),
+        (Bson::Int32(a), Bson::Int32(b)) => a.cmp(b),
+        (Bson::Int64(a), Bson::Int64(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+// Same contract as the Mongo backend: filter on the stored form, sort with `_id`
+// breaking ties, skip up to the cursor
+fn page<'a, T>(items: impl IntoIterator<Item = &'a T>, params: &ListParams) -> RepoResult<Page<T>>
+where
+    T: Clone + Serialize + 'a,
+{
+    let mut rows: Vec<(Document, &T)> = Vec::new();
+    for item in items {
+        let document = to_stored_document(item)?;
+        if params.filters.iter().all(|(field, value)| document.get(*field) == Some(value)) {
+            rows.push((document, item));
+        }
+    }
+    let total = rows.len() as u64;
+
+    let field = params.sort.field;
+    let value = |document: &Document, key: &str| document.get(key).cloned().unwrap_or(Bson::Null);
+    let order = |a: &Document, b: &Document| {
+        let order = compare(&value(a, field), &value(b, field)).then_with(|| compare(&value(a, "_id"), &value(b, "_id")));
+        if params.sort.descending { order.reverse() } else { order }
+    };
+    rows.sort_by(|(a, _), (b, _)| order(a, b));
+
+    if let Some(after) = &params.after {
+        let cursor = mongodb::bson::doc! { field: after.value.clone(), "_id": after.id };
+        rows.retain(|(document, _)| order(document, &cursor) == Ordering::Greater);
+    }
+
+    let batch = rows.into_iter().take(params.limit + 1).map(|(_, item)| item.clone()).collect();
+    Page::from_batch(batch, params, total)
+}
+
+#[async_trait]
+impl UserRepository for MemoryStore {
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<User>> {
+        Ok(self.data().users.get(&id).cloned())
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>> {
+        Ok(self.data().users.values().find(|user| user.email == email).cloned())
+    }
+
+    async fn find_by_login(&self, email: &str, username: &str) -> RepoResult<Option<User>> {
+        let data = self.data();
+        Ok(data.users.values().find(|user| user.email == email || user.username == username).cloned())
+    }
+
+    async fn find_by_identity(&self, key: &str) -> RepoResult<Option<User>> {
+        let data = self.data();
+        Ok(data.users.values().find(|user| user.linked_identities.iter().any(|identity| identity.key == key)).cloned())
+    }
+
+    async fn find_unlinked_github_account(&self, emails: &[String]) -> RepoResult<Option<User>> {
+        let data = self.data();
+        let user = data.users.values().find(|user| {
+            emails.contains(&user.email)
+                && user.password_hash.is_empty()
+                && user.linked_identities.is_empty()
+                && user.legacy_github_unresolved
+        });
+        Ok(user.cloned())
+    }
+
+    async fn list(&self) -> RepoResult<Vec<User>> {
+        Ok(self.data().users.values().cloned().collect())
+    }
+
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<User>> {
+        page(self.data().users.values(), params)
+    }
+
+    async fn page_by_status(&self, status: AccountStatus, params: &ListParams) -> RepoResult<Page<User>> {
+        page(self.data().users.values().filter(|user| user.status == status), params)
+    }
+
+    async fn list_by_roles(&self, roles: &[Role]) -> RepoResult<Vec<User>> {
+        Ok(self.data().users.values().filter(|user| roles.contains(&user.role)).cloned().collect())
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.data().users.len() as u64)
+    }
+
+    async fn total_coins(&self) -> RepoResult<i64> {
+        Ok(self.data().users.values().map(|user| user.coins as i64).sum())
+    }
+
+    async fn insert(&self, user: &User) -> RepoResult<ObjectId> {
+        let mut data = self.data();
+        // Same as the unique indexes: emails (when set) and identity keys
+        let taken = data.users.values().any(|other| {
+            (!user.email.is_empty() && other.email == user.email)
+                || user.linked_identities.iter().any(|identity| {
+                    other.linked_identities.iter().any(|theirs| theirs.key == identity.key)
+                })
+        });
+        if taken {
+            return Err(RepoError::Duplicate);
+        }
+        Ok(insert(&mut data.users, user, |user| &mut user.id))
+    }
+
+    async fn set_role(&self, id: ObjectId, role: Role) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(user) = data.users.get_mut(&id) else {
+            return Ok(false);
+        };
+        user.role = role;
+        user.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn set_status(&self, id: ObjectId, from: Option<AccountStatus>, to: AccountStatus) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(user) = data.users.get_mut(&id) else {
+            return Ok(false);
+        };
+        if from.is_some_and(|from| user.status != from) {
+            return Ok(false);
+        }
+        user.status = to;
+        user.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn record_failed_login(&self, id: ObjectId, max_attempts: i32, lock_until: DateTime) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(user) = data.users.get_mut(&id).filter(|user| user.locked_until.is_none_or(|until| until <= DateTime::now())) else {
+            return Ok(false);
+        };
+        user.failed_login_attempts += 1;
+        if user.failed_login_attempts < max_attempts {
+            return Ok(false);
+        }
+        user.failed_login_attempts = 0;
+        user.locked_until = Some(lock_until);
+        Ok(true)
+    }
+
+    async fn clear_failed_logins(&self, id: ObjectId) -> RepoResult<()> {
+        if let Some(user) = self.data().users.get_mut(&id) {
+            user.failed_login_attempts = 0;
+            user.locked_until = None;
+        }
+        Ok(())
+    }
+
+    async fn set_password(&self, id: ObjectId, password_hash: &str) -> RepoResult<()> {
+        if let Some(user) = self.data().users.get_mut(&id) {
+            user.password_hash = password_hash.to_string();
+            user.failed_login_attempts = 0;
+            user.locked_until = None;
+            user.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn link_identity(&self, id: ObjectId, identity: &LinkedIdentity) -> RepoResult<bool> {
+        let mut data = self.data();
+        let taken = data.users
+            .iter()
+            .any(|(other_id, other)| *other_id != id && other.linked_identities.iter().any(|theirs| theirs.key == identity.key));
+        if taken {
+            return Err(RepoError::Duplicate);
+        }
+        let Some(user) = data.users.get_mut(&id) else {
+            return Ok(false);
+        };
+        if user.linked_identities.iter().any(|linked| linked.provider == identity.provider) {
+            return Ok(false);
+        }
+        user.linked_identities.push(identity.clone());
+        user.legacy_github_unresolved = false;
+        user.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn unlink_identity(&self, id: ObjectId, provider: &str) -> RepoResult<()> {
+        if let Some(user) = self.data().users.get_mut(&id) {
+            user.linked_identities.retain(|identity| identity.provider != provider);
+            user.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn start_two_factor(&self, id: ObjectId, pending: &TwoFactor) -> RepoResult<()> {
+        if let Some(user) = self.data().users.get_mut(&id) {
+            user.two_factor = Some(pending.clone());
+        }
+        Ok(())
+    }
+
+    async fn enable_two_factor(&self, id: ObjectId, secret: &str, recovery_code_hashes: &[String], step: i64) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(user) = data.users.get_mut(&id) else {
+            return Ok(false);
+        };
+        let Some(two_factor) = user.two_factor.as_mut().filter(|tf| tf.secret == secret && !tf.enabled) else {
+            return Ok(false);
+        };
+        two_factor.enabled = true;
+        two_factor.recovery_code_hashes = recovery_code_hashes.to_vec();
+        two_factor.last_used_step = Some(step);
+        two_factor.enabled_at = Some(DateTime::now());
+        user.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn set_recovery_codes(&self, id: ObjectId, recovery_code_hashes: &[String]) -> RepoResult<()> {
+        let mut data = self.data();
+        if let Some(two_factor) = data.users.get_mut(&id).and_then(|user| user.two_factor.as_mut()) {
+            two_factor.recovery_code_hashes = recovery_code_hashes.to_vec();
+        }
+        Ok(())
+    }
+
+    async fn disable_two_factor(&self, id: ObjectId) -> RepoResult<()> {
+        if let Some(user) = self.data().users.get_mut(&id) {
+            user.two_factor = None;
+            user.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, id: ObjectId, step: i64) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(two_factor) = data.users.get_mut(&id).and_then(|user| user.two_factor.as_mut()) else {
+            return Ok(false);
+        };
+        if two_factor.last_used_step.is_some_and(|last| last >= step) {
+            return Ok(false);
+        }
+        two_factor.last_used_step = Some(step);
+        Ok(true)
+    }
+
+    async fn use_recovery_code(&self, id: ObjectId, code_hash: &str) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(two_factor) = data.users.get_mut(&id).and_then(|user| user.two_factor.as_mut()) else {
+            return Ok(false);
+        };
+        let before = two_factor.recovery_code_hashes.len();
+        two_factor.recovery_code_hashes.retain(|hash| hash != code_hash);
+        Ok(two_factor.recovery_code_hashes.len() != before)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        let mut data = self.data();
+        if data.users.remove(&id).is_none() {
+            return Ok(false);
+        }
+        for project in data.projects.values_mut() {
+            pull(&mut project.member_ids, id);
+        }
+        Ok(true)
+    }
+}
+
+fn is_active(session: &Session) -> bool {
+    session.revoked_at.is_none() && session.expires_at > DateTime::now()
+}
+
+#[async_trait]
+impl SessionRepository for MemoryStore {
+    async fn insert(&self, session: &Session) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().sessions, session, |session| &mut session.id))
+    }
+
+    async fn find_active(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<Session>> {
+        let data = self.data();
+        Ok(data.sessions.get(&id).filter(|session| session.user_id == user_id && is_active(session)).cloned())
+    }
+
+    async fn find_by_refresh_hash(&self, hash: &str) -> RepoResult<Option<Session>> {
+        let data = self.data();
+        let session = data.sessions.values().find(|session| {
+            session.refresh_token_hash == hash || session.previous_token_hash.as_deref() == Some(hash)
+        });
+        Ok(session.cloned())
+    }
+
+    async fn list_active(&self, user_id: ObjectId) -> RepoResult<Vec<Session>> {
+        let mut sessions: Vec<Session> = self.data()
+            .sessions
+            .values()
+            .filter(|session| session.user_id == user_id && is_active(session))
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| Reverse(session.last_used_at));
+        Ok(sessions)
+    }
+
+    async fn rotate(&self, id: ObjectId, presented_hash: &str, new_hash: &str, expires_at: DateTime) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(session) = data.sessions.get_mut(&id).filter(|session| session.refresh_token_hash == presented_hash) else {
+            return Ok(false);
+        };
+        session.refresh_token_hash = new_hash.to_string();
+        session.previous_token_hash = Some(presented_hash.to_string());
+        session.last_used_at = DateTime::now();
+        session.expires_at = expires_at;
+        Ok(true)
+    }
+
+    async fn mark_two_factor_verified(&self, id: ObjectId) -> RepoResult<()> {
+        if let Some(session) = self.data().sessions.get_mut(&id) {
+            session.two_factor_verified = true;
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, id: ObjectId, user_id: Option<ObjectId>) -> RepoResult<u64> {
+        let mut data = self.data();
+        let session = data.sessions.get_mut(&id).filter(|session| {
+            session.revoked_at.is_none() && user_id.is_none_or(|user_id| session.user_id == user_id)
+        });
+        let Some(session) = session else {
+            return Ok(0);
+        };
+        session.revoked_at = Some(DateTime::now());
+        Ok(1)
+    }
+
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64> {
+        let mut revoked = 0;
+        for session in self.data().sessions.values_mut() {
+            if session.user_id == user_id && session.revoked_at.is_none() {
+                session.revoked_at = Some(DateTime::now());
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for MemoryStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Project>> {
+        page(self.data().projects.values(), params)
+    }
+
+    async fn list_for_member(&self, user_id: ObjectId) -> RepoResult<Vec<Project>> {
+        let data = self.data();
+        let projects = data.projects
+            .values()
+            .filter(|project| project.member_ids.as_ref().is_some_and(|ids| ids.contains(&user_id)))
+            .cloned()
+            .collect();
+        Ok(projects)
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Project>> {
+        Ok(self.data().projects.get(&id).cloned())
+    }
+
+    async fn insert(&self, project: &Project) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().projects, project, |project| &mut project.id))
+    }
+
+    async fn update(&self, id: ObjectId, changes: &ProjectChanges) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(project) = data.projects.get_mut(&id) else {
+            return Ok(false);
+        };
+        set(&mut project.name, &changes.name);
+        set(&mut project.description, &changes.description);
+        set(&mut project.status, &changes.status);
+        set_some(&mut project.github_link, &changes.github_link);
+        project.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn set_lead(&self, id: ObjectId, lead_id: ObjectId) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(project) = data.projects.get_mut(&id) else {
+            return Ok(false);
+        };
+        project.project_lead_id = Some(lead_id);
+        project.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn add_file(&self, id: ObjectId, file: &ProjectFile) -> RepoResult<()> {
+        if let Some(project) = self.data().projects.get_mut(&id) {
+            project.files.get_or_insert_with(Vec::new).push(file.clone());
+            project.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn remove_file(&self, id: ObjectId, file_id: ObjectId) -> RepoResult<()> {
+        if let Some(project) = self.data().projects.get_mut(&id) {
+            if let Some(files) = project.files.as_mut() {
+                files.retain(|file| file.id != file_id);
+            }
+            project.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn add_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()> {
+        let mut data = self.data();
+        let Data { projects, users, .. } = &mut *data;
+        let project = projects.get_mut(&id).ok_or(RepoError::NotFound("Project"))?;
+        let user = users.get_mut(&user_id).ok_or(RepoError::NotFound("User"))?;
+        add_to_set(&mut project.member_ids, user_id);
+        add_to_set(&mut user.project_ids, id);
+        project.updated_at = DateTime::now();
+        user.updated_at = DateTime::now();
+        Ok(())
+    }
+
+    async fn remove_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()> {
+        let mut data = self.data();
+        if let Some(project) = data.projects.get_mut(&id) {
+            pull(&mut project.member_ids, user_id);
+            project.updated_at = DateTime::now();
+        }
+        if let Some(user) = data.users.get_mut(&user_id) {
+            pull(&mut user.project_ids, id);
+            user.updated_at = DateTime::now();
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        let mut data = self.data();
+        if data.projects.remove(&id).is_none() {
+            return Ok(false);
+        }
+        for user in data.users.values_mut() {
+            pull(&mut user.project_ids, id);
+        }
+        Ok(true)
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.data().projects.len() as u64)
+    }
+
+    async fn count_by_status(&self, status: ProjectStatus) -> RepoResult<u64> {
+        Ok(self.data().projects.values().filter(|project| project.status == status).count() as u64)
+    }
+}
+
+#[async_trait]
+impl JoinRequestRepository for MemoryStore {
+    async fn insert(&self, request: &ProjectJoinRequest) -> RepoResult<ObjectId> {
+        let mut data = self.data();
+        let pending = data.project_join_requests.values().any(|other| {
+            other.status == JoinRequestStatus::Pending
+                && other.user_id == request.user_id
+                && other.project_id == request.project_id
+        });
+        if pending && request.status == JoinRequestStatus::Pending {
+            return Err(RepoError::Duplicate);
+        }
+        Ok(insert(&mut data.project_join_requests, request, |request| &mut request.id))
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<ProjectJoinRequest>> {
+        Ok(self.data().project_join_requests.get(&id).cloned())
+    }
+
+    async fn list_pending(&self, project_id: ObjectId) -> RepoResult<Vec<ProjectJoinRequest>> {
+        let data = self.data();
+        let requests = data.project_join_requests
+            .values()
+            .filter(|request| request.project_id == project_id && request.status == JoinRequestStatus::Pending)
+            .cloned()
+            .collect();
+        Ok(requests)
+    }
+
+    async fn count_pending(&self) -> RepoResult<u64> {
+        let data = self.data();
+        Ok(data.project_join_requests.values().filter(|request| request.status == JoinRequestStatus::Pending).count() as u64)
+    }
+
+    async fn decide(&self, request: &ProjectJoinRequest, status: JoinRequestStatus) -> RepoResult<bool> {
+        let mut data = self.data();
+        let pending = request.id.and_then(|id| data.project_join_requests.get_mut(&id))
+            .filter(|stored| stored.status == JoinRequestStatus::Pending);
+        let Some(stored) = pending else {
+            return Ok(false);
+        };
+        stored.status = status.clone();
+        stored.updated_at = DateTime::now();
+
+        if status == JoinRequestStatus::Approved {
+            if let Some(project) = data.projects.get_mut(&request.project_id) {
+                add_to_set(&mut project.member_ids, request.user_id);
+            }
+            if let Some(user) = data.users.get_mut(&request.user_id) {
+                add_to_set(&mut user.project_ids, request.project_id);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl CoinRepository for MemoryStore {
+    async fn record(&self, entry: &CoinTransaction) -> RepoResult<()> {
+        let mut data = self.data();
+        let user = data.users.get_mut(&entry.user_id).ok_or(RepoError::NotFound("User"))?;
+        user.coins += entry.amount;
+        user.updated_at = DateTime::now();
+        insert(&mut data.coin_transactions, entry, |entry| &mut entry.id);
+        Ok(())
+    }
+
+    async fn page_for_user(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<CoinTransaction>> {
+        page(self.data().coin_transactions.values().filter(|entry| entry.user_id == user_id), params)
+    }
+
+    async fn save_leaderboard(&self, leaderboard: &WeeklyLeaderboard) -> RepoResult<()> {
+        self.data().leaderboards.push(leaderboard.clone());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageRepository for MemoryStore {
+    async fn insert(&self, message: &Message) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().messages, message, |message| &mut message.id))
+    }
+
+    async fn page_for_recipient(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<Message>> {
+        let data = self.data();
+        let received = data.messages
+            .values()
+            .filter(|message| message.recipient_ids.as_ref().is_some_and(|ids| ids.contains(&user_id)));
+        page(received, params)
+    }
+
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Message>> {
+        page(self.data().messages.values(), params)
+    }
+}
+
+#[async_trait]
+impl EventRepository for MemoryStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Event>> {
+        page(self.data().events.values(), params)
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Event>> {
+        Ok(self.data().events.get(&id).cloned())
+    }
+
+    async fn insert(&self, event: &Event) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().events, event, |event| &mut event.id))
+    }
+
+    async fn update(&self, id: ObjectId, changes: &EventChanges) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(event) = data.events.get_mut(&id) else {
+            return Ok(false);
+        };
+        set(&mut event.title, &changes.title);
+        set(&mut event.starts_at, &changes.starts_at);
+        set_some(&mut event.ends_at, &changes.ends_at);
+        set(&mut event.timezone, &changes.timezone);
+        set(&mut event.location, &changes.location);
+        set(&mut event.event_type, &changes.event_type);
+        set(&mut event.status, &changes.status);
+        set(&mut event.description, &changes.description);
+        set_some(&mut event.image, &changes.image);
+        set(&mut event.featured, &changes.featured);
+        set_some(&mut event.register_link, &changes.register_link);
+        set_some(&mut event.recap_link, &changes.recap_link);
+        set_some(&mut event.speakers, &changes.speakers);
+        event.updated_at = DateTime::now();
+        Ok(true)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.data().events.remove(&id).is_some())
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.data().events.len() as u64)
+    }
+
+    async fn count_by_type(&self, event_type: EventType) -> RepoResult<u64> {
+        Ok(self.data().events.values().filter(|event| event.event_type == event_type).count() as u64)
+    }
+}
+
+#[async_trait]
+impl GalleryRepository for MemoryStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<GalleryItem>> {
+        page(self.data().gallery.values(), params)
+    }
+
+    async fn insert(&self, item: &GalleryItem) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().gallery, item, |item| &mut item.id))
+    }
+
+    async fn update(&self, id: ObjectId, changes: &GalleryChanges) -> RepoResult<bool> {
+        let mut data = self.data();
+        let Some(item) = data.gallery.get_mut(&id) else {
+            return Ok(false);
+        };
+        set(&mut item.title, &changes.title);
+        set(&mut item.category, &changes.category);
+        set(&mut item.image_url, &changes.image_url);
+        set(&mut item.description, &changes.description);
+        set_some(&mut item.thumbnail_url, &changes.thumbnail_url);
+        set(&mut item.featured, &changes.featured);
+        Ok(true)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.data().gallery.remove(&id).is_some())
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.data().gallery.len() as u64)
+    }
+}
+
+#[async_trait]
+impl BlogRepository for MemoryStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Blog>> {
+        page(self.data().blogs.values(), params)
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Blog>> {
+        Ok(self.data().blogs.get(&id).cloned())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> RepoResult<Option<Blog>> {
+        Ok(self.data().blogs.values().find(|blog| blog.slug == slug).cloned())
+    }
+
+    async fn insert(&self, blog: &Blog) -> RepoResult<ObjectId> {
+        let mut data = self.data();
+        if data.blogs.values().any(|other| other.slug == blog.slug) {
+            return Err(RepoError::Duplicate);
+        }
+        Ok(insert(&mut data.blogs, blog, |blog| &mut blog.id))
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.data().blogs.remove(&id).is_some())
+    }
+}
+
+#[async_trait]
+impl InviteRepository for MemoryStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Invite>> {
+        page(self.data().invites.values(), params)
+    }
+
+    async fn insert(&self, invite: &Invite) -> RepoResult<ObjectId> {
+        let mut data = self.data();
+        if data.invites.values().any(|other| other.email == invite.email) {
+            return Err(RepoError::Duplicate);
+        }
+        Ok(insert(&mut data.invites, invite, |invite| &mut invite.id))
+    }
+
+    async fn accept(&self, emails: &[String]) -> RepoResult<bool> {
+        let mut data = self.data();
+        let outstanding = data.invites
+            .values_mut()
+            .find(|invite| invite.accepted_at.is_none() && emails.contains(&invite.email));
+        let Some(invite) = outstanding else {
+            return Ok(false);
+        };
+        invite.accepted_at = Some(DateTime::now());
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl OAuthStateRepository for MemoryStore {
+    async fn insert(&self, state: &OAuthState) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().oauth_states, state, |state| &mut state.id))
+    }
+
+    async fn take(&self, state: &str) -> RepoResult<Option<OAuthState>> {
+        let mut data = self.data();
+        let id = data.oauth_states.iter().find(|(_, pending)| pending.state == state).map(|(id, _)| *id);
+        Ok(id.and_then(|id| data.oauth_states.remove(&id)))
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for MemoryStore {
+    async fn insert(&self, reset: &PasswordReset) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().password_resets, reset, |reset| &mut reset.id))
+    }
+
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<PasswordReset>> {
+        let now = DateTime::now();
+        let data = self.data();
+        Ok(data.password_resets.values().find(|reset| reset.token_hash == token_hash && reset.expires_at > now).cloned())
+    }
+
+    async fn consume(&self, token_hash: &str) -> RepoResult<bool> {
+        let now = DateTime::now();
+        let mut data = self.data();
+        let before = data.password_resets.len();
+        data.password_resets.retain(|_, reset| reset.token_hash != token_hash || reset.expires_at <= now);
+        Ok(data.password_resets.len() < before)
+    }
+
+    async fn delete_for_user(&self, user_id: ObjectId) -> RepoResult<()> {
+        self.data().password_resets.retain(|_, reset| reset.user_id != user_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactorChallengeRepository for MemoryStore {
+    async fn insert(&self, challenge: &TwoFactorChallenge) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().two_factor_challenges, challenge, |challenge| &mut challenge.id))
+    }
+
+    async fn record_attempt(&self, token_hash: &str, max_attempts: i32) -> RepoResult<Option<TwoFactorChallenge>> {
+        let now = DateTime::now();
+        let mut data = self.data();
+        let live = data.two_factor_challenges.values_mut().find(|challenge| {
+            challenge.token_hash == token_hash && challenge.attempts < max_attempts && challenge.expires_at > now
+        });
+        // Returned as it was before this attempt, like the Mongo update
+        Ok(live.map(|challenge| {
+            let before = challenge.clone();
+            challenge.attempts += 1;
+            before
+        }))
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.data().two_factor_challenges.remove(&id).is_some())
+    }
+}
+
+#[async_trait]
+impl AccessTokenRepository for MemoryStore {
+    async fn insert(&self, token: &AccessToken) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().access_tokens, token, |token| &mut token.id))
+    }
+
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<AccessToken>> {
+        let now = DateTime::now();
+        let data = self.data();
+        Ok(data.access_tokens
+            .values()
+            .find(|token| token.token_hash == token_hash && token.revoked_at.is_none() && token.expires_at > now)
+            .cloned())
+    }
+
+    async fn get_for_user(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<AccessToken>> {
+        Ok(self.data().access_tokens.get(&id).filter(|token| token.user_id == user_id).cloned())
+    }
+
+    async fn list_for_user(&self, user_id: ObjectId) -> RepoResult<Vec<AccessToken>> {
+        let data = self.data();
+        let mut tokens: Vec<AccessToken> =
+            data.access_tokens.values().filter(|token| token.user_id == user_id).cloned().collect();
+        tokens.sort_by_key(|token| Reverse((token.created_at, token.id)));
+        Ok(tokens)
+    }
+
+    async fn touch(&self, id: ObjectId, now: DateTime, recent: DateTime) -> RepoResult<()> {
+        let mut data = self.data();
+        let stale = data.access_tokens.get_mut(&id).filter(|token| token.last_used_at.is_none_or(|used| used < recent));
+        if let Some(token) = stale {
+            token.last_used_at = Some(now);
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<bool> {
+        let mut data = self.data();
+        let live = data.access_tokens
+            .get_mut(&id)
+            .filter(|token| token.user_id == user_id && token.revoked_at.is_none());
+        let Some(token) = live else {
+            return Ok(false);
+        };
+        token.revoked_at = Some(DateTime::now());
+        Ok(true)
+    }
+
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64> {
+        let mut revoked = 0;
+        for token in self.data().access_tokens.values_mut() {
+            if token.user_id == user_id && token.revoked_at.is_none() {
+                token.revoked_at = Some(DateTime::now());
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MemoryStore {
+    async fn insert(&self, entry: &AuditEntry) -> RepoResult<ObjectId> {
+        Ok(insert(&mut self.data().audit_log, entry, |entry| &mut entry.id))
+    }
+
+    async fn list(&self, filter: &AuditFilter, limit: i64) -> RepoResult<Vec<AuditEntry>> {
+        let data = self.data();
+        let mut entries: Vec<AuditEntry> = data.audit_log
+            .values()
+            .filter(|entry| {
+                filter.action.as_ref().is_none_or(|action| entry.action == *action)
+                    && filter.actor_id.is_none_or(|actor_id| entry.actor_id == actor_id)
+                    && filter.target_id.is_none_or(|target_id| entry.target_id == Some(target_id))
+            })
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| Reverse((entry.created_at, entry.id)));
+        entries.truncate(limit.max(0) as usize);
+        Ok(entries)
+    }
+}
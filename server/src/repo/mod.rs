@@ -0,0 +1,342 @@
+pub mod list;
+pub mod mongo;
+#[cfg(test)]
+pub mod memory;
+
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::Serialize;
+use std::fmt::Debug;
+
+pub use self::list::{ListParams, Page};
+use crate::db::is_duplicate_key;
+use crate::models::dates::optional_bson_date;
+use crate::models::{
+    AccessToken, AccountStatus, AuditEntry, Blog, CoinTransaction, Event, EventSpeaker, EventStatus, EventType, GalleryItem, Invite,
+    JoinRequestStatus, LinkedIdentity, Message, OAuthState, PasswordReset, Project, ProjectFile,
+    ProjectJoinRequest, ProjectStatus, Role, Session, TwoFactor, TwoFactorChallenge, User, WeeklyLeaderboard,
+};
+
+// Storage for each aggregate the handlers work with. `mongo::MongoStore` is the
+// real one; tests run the same handlers against `memory::MemoryStore`.
+// Writes that span aggregates (membership, coin balances) are single methods, so
+// every backend can make them atomic its own way.
+
+#[derive(Debug)]
+pub enum RepoError {
+    Duplicate,                          // A unique constraint refused the write
+    NotFound(&'static str),             // A write across documents referenced a missing one, e.g. "User"
+    Database(mongodb::error::Error),
+}
+
+pub type RepoResult<T> = Result<T, RepoError>;
+
+impl std::fmt::Display for RepoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoError::Duplicate => write!(f, "duplicate key"),
+            RepoError::NotFound(what) => write!(f, "{} not found", what),
+            RepoError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for RepoError {
+    fn from(error: mongodb::error::Error) -> Self {
+        if is_duplicate_key(&error) {
+            RepoError::Duplicate
+        } else {
+            RepoError::Database(error)
+        }
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for RepoError {
+    fn from(error: mongodb::bson::ser::Error) -> Self {
+        RepoError::Database(error.into())
+    }
+}
+
+#[async_trait]
+pub trait UserRepository: Debug + Send + Sync {
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<User>>;
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>>;
+    // The user with this email or this username
+    async fn find_by_login(&self, email: &str, username: &str) -> RepoResult<Option<User>>;
+    async fn find_by_identity(&self, key: &str) -> RepoResult<Option<User>>;
+    // A GitHub-only account from before identities were stored, by one of its emails
+    async fn find_unlinked_github_account(&self, emails: &[String]) -> RepoResult<Option<User>>;
+    async fn list(&self) -> RepoResult<Vec<User>>;
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<User>>;
+    async fn page_by_status(&self, status: AccountStatus, params: &ListParams) -> RepoResult<Page<User>>;
+    async fn list_by_roles(&self, roles: &[Role]) -> RepoResult<Vec<User>>;
+    async fn count(&self) -> RepoResult<u64>;
+    async fn total_coins(&self) -> RepoResult<i64>;
+    // Duplicate if the email or a linked identity is taken
+    async fn insert(&self, user: &User) -> RepoResult<ObjectId>;
+    // False if there's no such user
+    async fn set_role(&self, id: ObjectId, role: Role) -> RepoResult<bool>;
+    // Only from `from` when given; false if nothing matched
+    async fn set_status(&self, id: ObjectId, from: Option<AccountStatus>, to: AccountStatus) -> RepoResult<bool>;
+    // Counts one more failure and, at `max_attempts`, resets the count and locks until
+    // `lock_until`, in one step so concurrent guesses can't share a count. Failures
+    // while locked aren't counted. True if this failure locked the account.
+    async fn record_failed_login(&self, id: ObjectId, max_attempts: i32, lock_until: DateTime) -> RepoResult<bool>;
+    async fn clear_failed_logins(&self, id: ObjectId) -> RepoResult<()>;
+    // Also lifts any lockout
+    async fn set_password(&self, id: ObjectId, password_hash: &str) -> RepoResult<()>;
+    // False if there's no such user or they already have this provider; Duplicate if another user has the identity
+    async fn link_identity(&self, id: ObjectId, identity: &LinkedIdentity) -> RepoResult<bool>;
+    async fn unlink_identity(&self, id: ObjectId, provider: &str) -> RepoResult<()>;
+    async fn start_two_factor(&self, id: ObjectId, pending: &TwoFactor) -> RepoResult<()>;
+    // Turns on the pending enrollment for `secret`; false if it was replaced or is already on
+    async fn enable_two_factor(&self, id: ObjectId, secret: &str, recovery_code_hashes: &[String], step: i64) -> RepoResult<bool>;
+    async fn set_recovery_codes(&self, id: ObjectId, recovery_code_hashes: &[String]) -> RepoResult<()>;
+    async fn disable_two_factor(&self, id: ObjectId) -> RepoResult<()>;
+    // Each TOTP step and recovery code is accepted once, however many requests race for it
+    async fn use_totp_step(&self, id: ObjectId, step: i64) -> RepoResult<bool>;
+    async fn use_recovery_code(&self, id: ObjectId, code_hash: &str) -> RepoResult<bool>;
+    // Also takes the user off every project; false if there's no such user
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+}
+
+#[async_trait]
+pub trait SessionRepository: Debug + Send + Sync {
+    async fn insert(&self, session: &Session) -> RepoResult<ObjectId>;
+    // Not revoked, not expired, and `user_id`'s
+    async fn find_active(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<Session>>;
+    // By the current or the previous refresh token
+    async fn find_by_refresh_hash(&self, hash: &str) -> RepoResult<Option<Session>>;
+    // Active sessions, most recently used first
+    async fn list_active(&self, user_id: ObjectId) -> RepoResult<Vec<Session>>;
+    // Swaps in a new refresh token if `presented_hash` is still the current one
+    async fn rotate(&self, id: ObjectId, presented_hash: &str, new_hash: &str, expires_at: DateTime) -> RepoResult<bool>;
+    async fn mark_two_factor_verified(&self, id: ObjectId) -> RepoResult<()>;
+    // Revokes one live session, only if it's `user_id`'s when given; returns how many were revoked
+    async fn revoke(&self, id: ObjectId, user_id: Option<ObjectId>) -> RepoResult<u64>;
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64>;
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectChanges {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ProjectStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github_link: Option<String>,
+}
+
+#[async_trait]
+pub trait ProjectRepository: Debug + Send + Sync {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Project>>;
+    async fn list_for_member(&self, user_id: ObjectId) -> RepoResult<Vec<Project>>;
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Project>>;
+    async fn insert(&self, project: &Project) -> RepoResult<ObjectId>;
+    // False if there's no such project
+    async fn update(&self, id: ObjectId, changes: &ProjectChanges) -> RepoResult<bool>;
+    async fn set_lead(&self, id: ObjectId, lead_id: ObjectId) -> RepoResult<bool>;
+    async fn add_file(&self, id: ObjectId, file: &ProjectFile) -> RepoResult<()>;
+    async fn remove_file(&self, id: ObjectId, file_id: ObjectId) -> RepoResult<()>;
+    // Membership is kept on the project and the user; these change both together.
+    // Adding fails with NotFound("Project") or NotFound("User").
+    async fn add_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()>;
+    async fn remove_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()>;
+    // Also takes the project off its members; false if there's no such project
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+    async fn count(&self) -> RepoResult<u64>;
+    async fn count_by_status(&self, status: ProjectStatus) -> RepoResult<u64>;
+}
+
+#[async_trait]
+pub trait JoinRequestRepository: Debug + Send + Sync {
+    // Duplicate if the user already has a pending request for the project
+    async fn insert(&self, request: &ProjectJoinRequest) -> RepoResult<ObjectId>;
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<ProjectJoinRequest>>;
+    async fn list_pending(&self, project_id: ObjectId) -> RepoResult<Vec<ProjectJoinRequest>>;
+    async fn count_pending(&self) -> RepoResult<u64>;
+    // Records the decision; an approval makes the requester a member in the same write.
+    // False, and nothing changes, if the request was no longer pending.
+    async fn decide(&self, request: &ProjectJoinRequest, status: JoinRequestStatus) -> RepoResult<bool>;
+}
+
+#[async_trait]
+pub trait CoinRepository: Debug + Send + Sync {
+    // The ledger entry and the balance change land together; NotFound("User") if the user is gone
+    async fn record(&self, transaction: &CoinTransaction) -> RepoResult<()>;
+    async fn page_for_user(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<CoinTransaction>>;
+    async fn save_leaderboard(&self, leaderboard: &WeeklyLeaderboard) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait MessageRepository: Debug + Send + Sync {
+    async fn insert(&self, message: &Message) -> RepoResult<ObjectId>;
+    async fn page_for_recipient(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<Message>>;
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Message>>;
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct EventChanges {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "optional_bson_date")]
+    pub starts_at: Option<DateTime>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "optional_bson_date")]
+    pub ends_at: Option<DateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<EventType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<EventStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub featured: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub register_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recap_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speakers: Option<Vec<EventSpeaker>>,
+}
+
+#[async_trait]
+pub trait EventRepository: Debug + Send + Sync {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Event>>;
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Event>>;
+    async fn insert(&self, event: &Event) -> RepoResult<ObjectId>;
+    // False if there's no such event
+    async fn update(&self, id: ObjectId, changes: &EventChanges) -> RepoResult<bool>;
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+    async fn count(&self) -> RepoResult<u64>;
+    async fn count_by_type(&self, event_type: EventType) -> RepoResult<u64>;
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct GalleryChanges {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub featured: Option<bool>,
+}
+
+#[async_trait]
+pub trait GalleryRepository: Debug + Send + Sync {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<GalleryItem>>;
+    async fn insert(&self, item: &GalleryItem) -> RepoResult<ObjectId>;
+    // False if there's no such item
+    async fn update(&self, id: ObjectId, changes: &GalleryChanges) -> RepoResult<bool>;
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+    async fn count(&self) -> RepoResult<u64>;
+}
+
+#[async_trait]
+pub trait BlogRepository: Debug + Send + Sync {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Blog>>;
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Blog>>;
+    async fn find_by_slug(&self, slug: &str) -> RepoResult<Option<Blog>>;
+    // Duplicate if the slug is taken
+    async fn insert(&self, blog: &Blog) -> RepoResult<ObjectId>;
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+}
+
+#[async_trait]
+pub trait InviteRepository: Debug + Send + Sync {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Invite>>;
+    // Duplicate if the email was already invited
+    async fn insert(&self, invite: &Invite) -> RepoResult<ObjectId>;
+    // Accepts an outstanding invite for one of the (lowercase) emails; false if there's none
+    async fn accept(&self, emails: &[String]) -> RepoResult<bool>;
+}
+
+// Short-lived records of a sign-in in progress. Mongo's TTL indexes drop expired
+// ones only once a minute, so their expiry is checked on use as well.
+
+#[async_trait]
+pub trait OAuthStateRepository: Debug + Send + Sync {
+    async fn insert(&self, state: &OAuthState) -> RepoResult<ObjectId>;
+    // Removes the pending state as it's read, so each one is used once
+    async fn take(&self, state: &str) -> RepoResult<Option<OAuthState>>;
+}
+
+#[async_trait]
+pub trait PasswordResetRepository: Debug + Send + Sync {
+    async fn insert(&self, reset: &PasswordReset) -> RepoResult<ObjectId>;
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<PasswordReset>>;
+    // Deletes the unexpired reset; false if another request used it first
+    async fn consume(&self, token_hash: &str) -> RepoResult<bool>;
+    async fn delete_for_user(&self, user_id: ObjectId) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait TwoFactorChallengeRepository: Debug + Send + Sync {
+    async fn insert(&self, challenge: &TwoFactorChallenge) -> RepoResult<ObjectId>;
+    // Counts an attempt on a live challenge that has fewer than `max_attempts`, in one
+    // step so parallel guesses can't exceed the limit; None if there's no such challenge
+    async fn record_attempt(&self, token_hash: &str, max_attempts: i32) -> RepoResult<Option<TwoFactorChallenge>>;
+    // False if it was already gone
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool>;
+}
+
+#[async_trait]
+pub trait AccessTokenRepository: Debug + Send + Sync {
+    async fn insert(&self, token: &AccessToken) -> RepoResult<ObjectId>;
+    // Not revoked and not expired
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<AccessToken>>;
+    async fn get_for_user(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<AccessToken>>;
+    // Newest first
+    async fn list_for_user(&self, user_id: ObjectId) -> RepoResult<Vec<AccessToken>>;
+    // Sets `last_used_at` to `now` unless it's already later than `recent`
+    async fn touch(&self, id: ObjectId, now: DateTime, recent: DateTime) -> RepoResult<()>;
+    // Revokes one live token of `user_id`; false if there was none
+    async fn revoke(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<bool>;
+    // Returns how many were revoked
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64>;
+}
+
+// Only entries matching every field given
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub actor_id: Option<ObjectId>,
+    pub target_id: Option<ObjectId>,
+}
+
+#[async_trait]
+pub trait AuditRepository: Debug + Send + Sync {
+    async fn insert(&self, entry: &AuditEntry) -> RepoResult<ObjectId>;
+    // Newest first, at most `limit`
+    async fn list(&self, filter: &AuditFilter, limit: i64) -> RepoResult<Vec<AuditEntry>>;
+}
+
+// A backend for every aggregate, which is what `AppState::new` takes
+pub trait Store:
+    UserRepository + SessionRepository + ProjectRepository + JoinRequestRepository + CoinRepository
+    + MessageRepository + EventRepository + GalleryRepository + BlogRepository + InviteRepository
+    + OAuthStateRepository + PasswordResetRepository + TwoFactorChallengeRepository + AccessTokenRepository
+    + AuditRepository
+{
+}
+
+impl<T> Store for T where
+    T: UserRepository + SessionRepository + ProjectRepository + JoinRequestRepository + CoinRepository
+        + MessageRepository + EventRepository + GalleryRepository + BlogRepository + InviteRepository
+        + OAuthStateRepository + PasswordResetRepository + TwoFactorChallengeRepository + AccessTokenRepository
+        + AuditRepository
+{
+}
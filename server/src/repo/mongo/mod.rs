@@ -0,0 +1,958 @@
+pub mod transaction;
+
+use async_trait::async_trait;
+use futures_util::stream::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::results::InsertOneResult;
+use mongodb::{Client, Collection, Database};
+use serde::{de::DeserializeOwned, Serialize};
+
+use self::transaction::transaction;
+use super::{
+    AccessTokenRepository, AuditFilter, AuditRepository, BlogRepository, CoinRepository, EventChanges, EventRepository, GalleryChanges, GalleryRepository,
+    InviteRepository, JoinRequestRepository, ListParams, MessageRepository, OAuthStateRepository, Page,
+    PasswordResetRepository, ProjectChanges, ProjectRepository, RepoError, RepoResult, SessionRepository,
+    TwoFactorChallengeRepository, UserRepository,
+};
+use crate::models::dates::to_stored_document;
+use crate::models::{
+    AccessToken, AccountStatus, AuditEntry, Blog, CoinTransaction, Event, EventType, GalleryItem, Invite,
+    JoinRequestStatus,
+    LinkedIdentity, Message, OAuthState, PasswordReset, Project, ProjectFile, ProjectJoinRequest, ProjectStatus, Role, Session,
+    TwoFactor, TwoFactorChallenge, User, WeeklyLeaderboard,
+};
+
+// Every repository on one database. Writes that touch two collections run in a
+// transaction (see `transaction`), which needs a replica set or mongos.
+#[derive(Clone, Debug)]
+pub struct MongoStore {
+    client: Client,
+    users: Collection<User>,
+    sessions: Collection<Session>,
+    projects: Collection<Project>,
+    project_join_requests: Collection<ProjectJoinRequest>,
+    coin_transactions: Collection<CoinTransaction>,
+    leaderboards: Collection<WeeklyLeaderboard>,
+    messages: Collection<Message>,
+    events: Collection<Event>,
+    gallery: Collection<GalleryItem>,
+    blogs: Collection<Blog>,
+    invites: Collection<Invite>,
+    oauth_states: Collection<OAuthState>,
+    password_resets: Collection<PasswordReset>,
+    two_factor_challenges: Collection<TwoFactorChallenge>,
+    access_tokens: Collection<AccessToken>,
+    audit_log: Collection<AuditEntry>,
+}
+
+impl MongoStore {
+    pub fn new(database: &Database) -> MongoStore {
+        MongoStore {
+            client: database.client().clone(),
+            users: database.collection("users"),
+            sessions: database.collection("sessions"),
+            projects: database.collection("projects"),
+            project_join_requests: database.collection("project_join_requests"),
+            coin_transactions: database.collection("coin_transactions"),
+            leaderboards: database.collection("leaderboards"),
+            messages: database.collection("messages"),
+            events: database.collection("events"),
+            gallery: database.collection("gallery"),
+            blogs: database.collection("blogs"),
+            invites: database.collection("invites"),
+            oauth_states: database.collection("oauth_states"),
+            password_resets: database.collection("password_resets"),
+            two_factor_challenges: database.collection("two_factor_challenges"),
+            access_tokens: database.collection("access_tokens"),
+            audit_log: database.collection("audit_log"),
+        }
+    }
+}
+
+// Models leave `_id` out when it's None, so the driver always generates an ObjectId
+fn inserted_id(result: InsertOneResult) -> RepoResult<ObjectId> {
+    result
+        .inserted_id
+        .as_object_id()
+        .ok_or_else(|| RepoError::Database(mongodb::error::Error::custom("insert returned no ObjectId")))
+}
+
+// `$sum` gives an int32 or an int64 depending on the size of the total
+fn total(documents: &[Document]) -> i64 {
+    documents.first().map_or(0, |total| match total.get("total") {
+        Some(mongodb::bson::Bson::Int32(n)) => *n as i64,
+        Some(mongodb::bson::Bson::Int64(n)) => *n,
+        _ => 0,
+    })
+}
+
+// One page of `collection` within `scope`; `total` ignores the cursor
+async fn page<T>(collection: &Collection<T>, scope: Document, params: &ListParams) -> RepoResult<Page<T>>
+where
+    T: DeserializeOwned + Serialize + Send + Sync,
+{
+    let mut filter = scope;
+    for (field, value) in &params.filters {
+        filter.insert(*field, value.clone());
+    }
+    let total = collection.count_documents(filter.clone()).await?;
+
+    let field = params.sort.field;
+    let (direction, past) = if params.sort.descending { (-1, "$lt") } else { (1, "$gt") };
+    if let Some(after) = &params.after {
+        let beyond = doc! { "$or": [
+            { field: { past: after.value.clone() } },
+            { field: after.value.clone(), "_id": { past: after.id } },
+        ] };
+        filter = doc! { "$and": [filter, beyond] };
+    }
+
+    let batch = collection
+        .find(filter)
+        .sort(doc! { field: direction, "_id": direction })
+        .limit(params.limit as i64 + 1)
+        .await?
+        .try_collect()
+        .await?;
+    Page::from_batch(batch, params, total)
+}
+
+#[async_trait]
+impl UserRepository for MongoStore {
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<User>> {
+        Ok(self.users.find_one(doc! { "_id": id }).await?)
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>> {
+        Ok(self.users.find_one(doc! { "email": email }).await?)
+    }
+
+    async fn find_by_login(&self, email: &str, username: &str) -> RepoResult<Option<User>> {
+        Ok(self.users.find_one(doc! { "$or": [ { "email": email }, { "username": username } ] }).await?)
+    }
+
+    async fn find_by_identity(&self, key: &str) -> RepoResult<Option<User>> {
+        Ok(self.users.find_one(doc! { "linked_identities.key": key }).await?)
+    }
+
+    async fn find_unlinked_github_account(&self, emails: &[String]) -> RepoResult<Option<User>> {
+        let user = self.users
+            .find_one(doc! {
+                "email": { "$in": emails },
+                "password_hash": "",
+                "linked_identities": { "$size": 0 },
+                "legacy_github_unresolved": true,
+            })
+            .await?;
+        Ok(user)
+    }
+
+    async fn list(&self) -> RepoResult<Vec<User>> {
+        Ok(self.users.find(doc! {}).await?.try_collect().await?)
+    }
+
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<User>> {
+        page(&self.users, doc! {}, params).await
+    }
+
+    async fn page_by_status(&self, status: AccountStatus, params: &ListParams) -> RepoResult<Page<User>> {
+        page(&self.users, doc! { "status": to_bson(&status)? }, params).await
+    }
+
+    async fn list_by_roles(&self, roles: &[Role]) -> RepoResult<Vec<User>> {
+        Ok(self.users.find(doc! { "role": { "$in": to_bson(roles)? } }).await?.try_collect().await?)
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.users.count_documents(doc! {}).await?)
+    }
+
+    async fn total_coins(&self) -> RepoResult<i64> {
+        let totals: Vec<Document> = self.users
+            .aggregate(vec![doc! { "$group": { "_id": null, "total": { "$sum": "$coins" } } }])
+            .await?
+            .try_collect()
+            .await?;
+        Ok(total(&totals))
+    }
+
+    async fn insert(&self, user: &User) -> RepoResult<ObjectId> {
+        inserted_id(self.users.insert_one(user).await?)
+    }
+
+    async fn set_role(&self, id: ObjectId, role: Role) -> RepoResult<bool> {
+        let result = self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "role": to_bson(&role)?, "updated_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn set_status(&self, id: ObjectId, from: Option<AccountStatus>, to: AccountStatus) -> RepoResult<bool> {
+        let mut filter = doc! { "_id": id };
+        if let Some(from) = from {
+            filter.insert("status", to_bson(&from)?);
+        }
+        let result = self.users
+            .update_one(filter, doc! { "$set": { "status": to_bson(&to)?, "updated_at": DateTime::now() } })
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn record_failed_login(&self, id: ObjectId, max_attempts: i32, lock_until: DateTime) -> RepoResult<bool> {
+        let reached = doc! { "$gte": ["$failed_login_attempts", max_attempts] };
+        let update = vec![
+            doc! { "$set": { "failed_login_attempts": { "$add": [{ "$ifNull": ["$failed_login_attempts", 0] }, 1] } } },
+            doc! { "$set": {
+                "locked_until": { "$cond": [reached.clone(), lock_until, "$locked_until"] },
+                "failed_login_attempts": { "$cond": [reached, 0, "$failed_login_attempts"] },
+            } },
+        ];
+        let user = self.users
+            .find_one_and_update(doc! { "_id": id, "locked_until": { "$not": { "$gt": DateTime::now() } } }, update)
+            .return_document(ReturnDocument::After)
+            .await?;
+        Ok(user.is_some_and(|user| user.locked_until == Some(lock_until)))
+    }
+
+    async fn clear_failed_logins(&self, id: ObjectId) -> RepoResult<()> {
+        self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "failed_login_attempts": 0 }, "$unset": { "locked_until": "" } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn set_password(&self, id: ObjectId, password_hash: &str) -> RepoResult<()> {
+        self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$set": {
+                        "password_hash": password_hash,
+                        "failed_login_attempts": 0,
+                        "updated_at": DateTime::now(),
+                    },
+                    "$unset": { "locked_until": "" },
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn link_identity(&self, id: ObjectId, identity: &LinkedIdentity) -> RepoResult<bool> {
+        let result = self.users
+            .update_one(
+                doc! { "_id": id, "linked_identities.provider": { "$ne": &identity.provider } },
+                doc! {
+                    "$push": { "linked_identities": to_bson(identity)? },
+                    "$set": { "updated_at": DateTime::now(), "legacy_github_unresolved": false }
+                },
+            )
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn unlink_identity(&self, id: ObjectId, provider: &str) -> RepoResult<()> {
+        self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$pull": { "linked_identities": { "provider": provider } },
+                    "$set": { "updated_at": DateTime::now() }
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn start_two_factor(&self, id: ObjectId, pending: &TwoFactor) -> RepoResult<()> {
+        self.users.update_one(doc! { "_id": id }, doc! { "$set": { "two_factor": to_bson(pending)? } }).await?;
+        Ok(())
+    }
+
+    async fn enable_two_factor(&self, id: ObjectId, secret: &str, recovery_code_hashes: &[String], step: i64) -> RepoResult<bool> {
+        let result = self.users
+            .update_one(
+                doc! { "_id": id, "two_factor.secret": secret, "two_factor.enabled": false },
+                doc! { "$set": {
+                    "two_factor.enabled": true,
+                    "two_factor.recovery_code_hashes": recovery_code_hashes,
+                    "two_factor.last_used_step": step,
+                    "two_factor.enabled_at": DateTime::now(),
+                    "updated_at": DateTime::now(),
+                } },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn set_recovery_codes(&self, id: ObjectId, recovery_code_hashes: &[String]) -> RepoResult<()> {
+        self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "two_factor.recovery_code_hashes": recovery_code_hashes } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn disable_two_factor(&self, id: ObjectId) -> RepoResult<()> {
+        self.users
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$unset": { "two_factor": "" },
+                    "$set": { "updated_at": DateTime::now() }
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, id: ObjectId, step: i64) -> RepoResult<bool> {
+        let result = self.users
+            .update_one(
+                doc! {
+                    "_id": id,
+                    "$or": [
+                        { "two_factor.last_used_step": null },
+                        { "two_factor.last_used_step": { "$lt": step } },
+                    ]
+                },
+                doc! { "$set": { "two_factor.last_used_step": step } },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn use_recovery_code(&self, id: ObjectId, code_hash: &str) -> RepoResult<bool> {
+        let result = self.users
+            .update_one(
+                doc! { "_id": id, "two_factor.recovery_code_hashes": code_hash },
+                doc! { "$pull": { "two_factor.recovery_code_hashes": code_hash } },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        transaction(self, |session, store| Box::pin(async move {
+            let deleted = store.users.delete_one(doc! { "_id": id }).session(&mut *session).await?;
+            if deleted.deleted_count == 0 {
+                return Ok(false);
+            }
+            store.projects
+                .update_many(doc! { "member_ids": id }, doc! { "$pull": { "member_ids": id } })
+                .session(&mut *session)
+                .await?;
+            Ok(true)
+        }))
+        .await
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MongoStore {
+    async fn insert(&self, session: &Session) -> RepoResult<ObjectId> {
+        inserted_id(self.sessions.insert_one(session).await?)
+    }
+
+    async fn find_active(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<Session>> {
+        let session = self.sessions
+            .find_one(doc! {
+                "_id": id,
+                "user_id": user_id,
+                "revoked_at": null,
+                "expires_at": { "$gt": DateTime::now() },
+            })
+            .await?;
+        Ok(session)
+    }
+
+    async fn find_by_refresh_hash(&self, hash: &str) -> RepoResult<Option<Session>> {
+        let session = self.sessions
+            .find_one(doc! {
+                "$or": [
+                    { "refresh_token_hash": hash },
+                    { "previous_token_hash": hash },
+                ]
+            })
+            .await?;
+        Ok(session)
+    }
+
+    async fn list_active(&self, user_id: ObjectId) -> RepoResult<Vec<Session>> {
+        let sessions = self.sessions
+            .find(doc! {
+                "user_id": user_id,
+                "revoked_at": null,
+                "expires_at": { "$gt": DateTime::now() },
+            })
+            .sort(doc! { "last_used_at": -1 })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(sessions)
+    }
+
+    async fn rotate(&self, id: ObjectId, presented_hash: &str, new_hash: &str, expires_at: DateTime) -> RepoResult<bool> {
+        let result = self.sessions
+            .update_one(
+                doc! { "_id": id, "refresh_token_hash": presented_hash },
+                doc! {
+                    "$set": {
+                        "refresh_token_hash": new_hash,
+                        "previous_token_hash": presented_hash,
+                        "last_used_at": DateTime::now(),
+                        "expires_at": expires_at,
+                    }
+                },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn mark_two_factor_verified(&self, id: ObjectId) -> RepoResult<()> {
+        self.sessions.update_one(doc! { "_id": id }, doc! { "$set": { "two_factor_verified": true } }).await?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: ObjectId, user_id: Option<ObjectId>) -> RepoResult<u64> {
+        let mut filter = doc! { "_id": id, "revoked_at": null };
+        if let Some(user_id) = user_id {
+            filter.insert("user_id", user_id);
+        }
+        let result = self.sessions.update_many(filter, doc! { "$set": { "revoked_at": DateTime::now() } }).await?;
+        Ok(result.modified_count)
+    }
+
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64> {
+        let result = self.sessions
+            .update_many(
+                doc! { "user_id": user_id, "revoked_at": null },
+                doc! { "$set": { "revoked_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.modified_count)
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for MongoStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Project>> {
+        page(&self.projects, doc! {}, params).await
+    }
+
+    async fn list_for_member(&self, user_id: ObjectId) -> RepoResult<Vec<Project>> {
+        Ok(self.projects.find(doc! { "member_ids": user_id }).await?.try_collect().await?)
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Project>> {
+        Ok(self.projects.find_one(doc! { "_id": id }).await?)
+    }
+
+    async fn insert(&self, project: &Project) -> RepoResult<ObjectId> {
+        inserted_id(self.projects.insert_one(project).await?)
+    }
+
+    async fn update(&self, id: ObjectId, changes: &ProjectChanges) -> RepoResult<bool> {
+        let mut set = to_stored_document(changes)?;
+        set.insert("updated_at", DateTime::now());
+        let result = self.projects.update_one(doc! { "_id": id }, doc! { "$set": set }).await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn set_lead(&self, id: ObjectId, lead_id: ObjectId) -> RepoResult<bool> {
+        let result = self.projects
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "project_lead_id": lead_id, "updated_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn add_file(&self, id: ObjectId, file: &ProjectFile) -> RepoResult<()> {
+        self.projects
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$push": { "files": to_stored_document(file)? },
+                    "$set": { "updated_at": DateTime::now() }
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_file(&self, id: ObjectId, file_id: ObjectId) -> RepoResult<()> {
+        self.projects
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$pull": { "files": { "_id": file_id } },
+                    "$set": { "updated_at": DateTime::now() }
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn add_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()> {
+        transaction(self, |session, store| Box::pin(async move {
+            let result = store.projects
+                .update_one(
+                    doc! { "_id": id },
+                    doc! {
+                        "$addToSet": { "member_ids": user_id },
+                        "$set": { "updated_at": DateTime::now() }
+                    },
+                )
+                .session(&mut *session)
+                .await?;
+            if result.matched_count == 0 {
+                return Err(RepoError::NotFound("Project"));
+            }
+
+            let result = store.users
+                .update_one(
+                    doc! { "_id": user_id },
+                    doc! {
+                        "$addToSet": { "project_ids": id },
+                        "$set": { "updated_at": DateTime::now() }
+                    },
+                )
+                .session(&mut *session)
+                .await?;
+            if result.matched_count == 0 {
+                return Err(RepoError::NotFound("User"));
+            }
+            Ok(())
+        }))
+        .await
+    }
+
+    async fn remove_member(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<()> {
+        transaction(self, |session, store| Box::pin(async move {
+            store.projects
+                .update_one(
+                    doc! { "_id": id },
+                    doc! {
+                        "$pull": { "member_ids": user_id },
+                        "$set": { "updated_at": DateTime::now() }
+                    },
+                )
+                .session(&mut *session)
+                .await?;
+            store.users
+                .update_one(
+                    doc! { "_id": user_id },
+                    doc! {
+                        "$pull": { "project_ids": id },
+                        "$set": { "updated_at": DateTime::now() }
+                    },
+                )
+                .session(&mut *session)
+                .await?;
+            Ok(())
+        }))
+        .await
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        transaction(self, |session, store| Box::pin(async move {
+            let deleted = store.projects.delete_one(doc! { "_id": id }).session(&mut *session).await?;
+            if deleted.deleted_count == 0 {
+                return Ok(false);
+            }
+            store.users
+                .update_many(doc! { "project_ids": id }, doc! { "$pull": { "project_ids": id } })
+                .session(&mut *session)
+                .await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.projects.count_documents(doc! {}).await?)
+    }
+
+    async fn count_by_status(&self, status: ProjectStatus) -> RepoResult<u64> {
+        Ok(self.projects.count_documents(doc! { "status": to_bson(&status)? }).await?)
+    }
+}
+
+#[async_trait]
+impl JoinRequestRepository for MongoStore {
+    async fn insert(&self, request: &ProjectJoinRequest) -> RepoResult<ObjectId> {
+        inserted_id(self.project_join_requests.insert_one(request).await?)
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<ProjectJoinRequest>> {
+        Ok(self.project_join_requests.find_one(doc! { "_id": id }).await?)
+    }
+
+    async fn list_pending(&self, project_id: ObjectId) -> RepoResult<Vec<ProjectJoinRequest>> {
+        let requests = self.project_join_requests
+            .find(doc! { "project_id": project_id, "status": "pending" })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(requests)
+    }
+
+    async fn count_pending(&self) -> RepoResult<u64> {
+        Ok(self.project_join_requests.count_documents(doc! { "status": "pending" }).await?)
+    }
+
+    async fn decide(&self, request: &ProjectJoinRequest, status: JoinRequestStatus) -> RepoResult<bool> {
+        let (request_id, project_id, user_id) = (request.id, request.project_id, request.user_id);
+        let approved = status == JoinRequestStatus::Approved;
+        let status = to_bson(&status)?;
+
+        transaction(self, |session, store| {
+            let status = status.clone();
+            Box::pin(async move {
+                let decided = store.project_join_requests
+                    .update_one(
+                        doc! { "_id": request_id, "status": "pending" },
+                        doc! { "$set": { "status": status, "updated_at": DateTime::now() } },
+                    )
+                    .session(&mut *session)
+                    .await?;
+                // A concurrent decision got there first
+                if decided.matched_count == 0 {
+                    return Ok(false);
+                }
+
+                if approved {
+                    store.projects
+                        .update_one(doc! { "_id": project_id }, doc! { "$addToSet": { "member_ids": user_id } })
+                        .session(&mut *session)
+                        .await?;
+                    store.users
+                        .update_one(doc! { "_id": user_id }, doc! { "$addToSet": { "project_ids": project_id } })
+                        .session(&mut *session)
+                        .await?;
+                }
+                Ok(true)
+            })
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl CoinRepository for MongoStore {
+    async fn record(&self, entry: &CoinTransaction) -> RepoResult<()> {
+        transaction(self, |session, store| {
+            let entry = entry.clone();
+            Box::pin(async move {
+                if store.users.find_one(doc! { "_id": entry.user_id }).session(&mut *session).await?.is_none() {
+                    return Err(RepoError::NotFound("User"));
+                }
+                store.coin_transactions.insert_one(&entry).session(&mut *session).await?;
+                store.users
+                    .update_one(
+                        doc! { "_id": entry.user_id },
+                        doc! {
+                            "$inc": { "coins": entry.amount },
+                            "$set": { "updated_at": DateTime::now() }
+                        },
+                    )
+                    .session(&mut *session)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn page_for_user(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<CoinTransaction>> {
+        page(&self.coin_transactions, doc! { "user_id": user_id }, params).await
+    }
+
+    async fn save_leaderboard(&self, leaderboard: &WeeklyLeaderboard) -> RepoResult<()> {
+        self.leaderboards.insert_one(leaderboard).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageRepository for MongoStore {
+    async fn insert(&self, message: &Message) -> RepoResult<ObjectId> {
+        inserted_id(self.messages.insert_one(message).await?)
+    }
+
+    async fn page_for_recipient(&self, user_id: ObjectId, params: &ListParams) -> RepoResult<Page<Message>> {
+        page(&self.messages, doc! { "recipient_ids": user_id }, params).await
+    }
+
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Message>> {
+        page(&self.messages, doc! {}, params).await
+    }
+}
+
+#[async_trait]
+impl EventRepository for MongoStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Event>> {
+        page(&self.events, doc! {}, params).await
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Event>> {
+        Ok(self.events.find_one(doc! { "_id": id }).await?)
+    }
+
+    async fn insert(&self, event: &Event) -> RepoResult<ObjectId> {
+        inserted_id(self.events.insert_one(event).await?)
+    }
+
+    async fn update(&self, id: ObjectId, changes: &EventChanges) -> RepoResult<bool> {
+        let mut set = to_stored_document(changes)?;
+        set.insert("updated_at", DateTime::now());
+        let result = self.events.update_one(doc! { "_id": id }, doc! { "$set": set }).await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.events.delete_one(doc! { "_id": id }).await?.deleted_count == 1)
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.events.count_documents(doc! {}).await?)
+    }
+
+    async fn count_by_type(&self, event_type: EventType) -> RepoResult<u64> {
+        Ok(self.events.count_documents(doc! { "event_type": to_bson(&event_type)? }).await?)
+    }
+}
+
+#[async_trait]
+impl GalleryRepository for MongoStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<GalleryItem>> {
+        page(&self.gallery, doc! {}, params).await
+    }
+
+    async fn insert(&self, item: &GalleryItem) -> RepoResult<ObjectId> {
+        inserted_id(self.gallery.insert_one(item).await?)
+    }
+
+    async fn update(&self, id: ObjectId, changes: &GalleryChanges) -> RepoResult<bool> {
+        let set = to_stored_document(changes)?;
+        let result = self.gallery.update_one(doc! { "_id": id }, doc! { "$set": set }).await?;
+        Ok(result.matched_count == 1)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.gallery.delete_one(doc! { "_id": id }).await?.deleted_count == 1)
+    }
+
+    async fn count(&self) -> RepoResult<u64> {
+        Ok(self.gallery.count_documents(doc! {}).await?)
+    }
+}
+
+#[async_trait]
+impl BlogRepository for MongoStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Blog>> {
+        page(&self.blogs, doc! {}, params).await
+    }
+
+    async fn get(&self, id: ObjectId) -> RepoResult<Option<Blog>> {
+        Ok(self.blogs.find_one(doc! { "_id": id }).await?)
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> RepoResult<Option<Blog>> {
+        Ok(self.blogs.find_one(doc! { "slug": slug }).await?)
+    }
+
+    async fn insert(&self, blog: &Blog) -> RepoResult<ObjectId> {
+        inserted_id(self.blogs.insert_one(blog).await?)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.blogs.delete_one(doc! { "_id": id }).await?.deleted_count == 1)
+    }
+}
+
+#[async_trait]
+impl InviteRepository for MongoStore {
+    async fn page(&self, params: &ListParams) -> RepoResult<Page<Invite>> {
+        page(&self.invites, doc! {}, params).await
+    }
+
+    async fn insert(&self, invite: &Invite) -> RepoResult<ObjectId> {
+        inserted_id(self.invites.insert_one(invite).await?)
+    }
+
+    async fn accept(&self, emails: &[String]) -> RepoResult<bool> {
+        let accepted = self.invites
+            .find_one_and_update(
+                doc! { "email": { "$in": emails }, "accepted_at": null },
+                doc! { "$set": { "accepted_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(accepted.is_some())
+    }
+}
+
+#[async_trait]
+impl OAuthStateRepository for MongoStore {
+    async fn insert(&self, state: &OAuthState) -> RepoResult<ObjectId> {
+        inserted_id(self.oauth_states.insert_one(state).await?)
+    }
+
+    async fn take(&self, state: &str) -> RepoResult<Option<OAuthState>> {
+        Ok(self.oauth_states.find_one_and_delete(doc! { "state": state }).await?)
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for MongoStore {
+    async fn insert(&self, reset: &PasswordReset) -> RepoResult<ObjectId> {
+        inserted_id(self.password_resets.insert_one(reset).await?)
+    }
+
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<PasswordReset>> {
+        let filter = doc! { "token_hash": token_hash, "expires_at": { "$gt": DateTime::now() } };
+        Ok(self.password_resets.find_one(filter).await?)
+    }
+
+    async fn consume(&self, token_hash: &str) -> RepoResult<bool> {
+        let filter = doc! { "token_hash": token_hash, "expires_at": { "$gt": DateTime::now() } };
+        Ok(self.password_resets.delete_one(filter).await?.deleted_count == 1)
+    }
+
+    async fn delete_for_user(&self, user_id: ObjectId) -> RepoResult<()> {
+        self.password_resets.delete_many(doc! { "user_id": user_id }).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactorChallengeRepository for MongoStore {
+    async fn insert(&self, challenge: &TwoFactorChallenge) -> RepoResult<ObjectId> {
+        inserted_id(self.two_factor_challenges.insert_one(challenge).await?)
+    }
+
+    async fn record_attempt(&self, token_hash: &str, max_attempts: i32) -> RepoResult<Option<TwoFactorChallenge>> {
+        let challenge = self.two_factor_challenges
+            .find_one_and_update(
+                doc! {
+                    "token_hash": token_hash,
+                    "attempts": { "$lt": max_attempts },
+                    "expires_at": { "$gt": DateTime::now() },
+                },
+                doc! { "$inc": { "attempts": 1 } },
+            )
+            .await?;
+        Ok(challenge)
+    }
+
+    async fn delete(&self, id: ObjectId) -> RepoResult<bool> {
+        Ok(self.two_factor_challenges.delete_one(doc! { "_id": id }).await?.deleted_count == 1)
+    }
+}
+
+#[async_trait]
+impl AccessTokenRepository for MongoStore {
+    async fn insert(&self, token: &AccessToken) -> RepoResult<ObjectId> {
+        inserted_id(self.access_tokens.insert_one(token).await?)
+    }
+
+    async fn find_active(&self, token_hash: &str) -> RepoResult<Option<AccessToken>> {
+        let token = self.access_tokens
+            .find_one(doc! {
+                "token_hash": token_hash,
+                "revoked_at": null,
+                "expires_at": { "$gt": DateTime::now() },
+            })
+            .await?;
+        Ok(token)
+    }
+
+    async fn get_for_user(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<Option<AccessToken>> {
+        Ok(self.access_tokens.find_one(doc! { "_id": id, "user_id": user_id }).await?)
+    }
+
+    async fn list_for_user(&self, user_id: ObjectId) -> RepoResult<Vec<AccessToken>> {
+        let tokens = self.access_tokens
+            .find(doc! { "user_id": user_id })
+            .sort(doc! { "created_at": -1, "_id": -1 })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(tokens)
+    }
+
+    async fn touch(&self, id: ObjectId, now: DateTime, recent: DateTime) -> RepoResult<()> {
+        self.access_tokens
+            .update_one(
+                doc! {
+                    "_id": id,
+                    "$or": [
+                        { "last_used_at": null },
+                        { "last_used_at": { "$lt": recent } },
+                    ]
+                },
+                doc! { "$set": { "last_used_at": now } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: ObjectId, user_id: ObjectId) -> RepoResult<bool> {
+        let result = self.access_tokens
+            .update_one(
+                doc! { "_id": id, "user_id": user_id, "revoked_at": null },
+                doc! { "$set": { "revoked_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+
+    async fn revoke_all(&self, user_id: ObjectId) -> RepoResult<u64> {
+        let result = self.access_tokens
+            .update_many(
+                doc! { "user_id": user_id, "revoked_at": null },
+                doc! { "$set": { "revoked_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.modified_count)
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MongoStore {
+    async fn insert(&self, entry: &AuditEntry) -> RepoResult<ObjectId> {
+        inserted_id(self.audit_log.insert_one(entry).await?)
+    }
+
+    async fn list(&self, filter: &AuditFilter, limit: i64) -> RepoResult<Vec<AuditEntry>> {
+        let mut query = doc! {};
+        if let Some(action) = &filter.action {
+            query.insert("action", action);
+        }
+        for (field, id) in [("actor_id", filter.actor_id), ("target_id", filter.target_id)] {
+            if let Some(id) = id {
+                query.insert(field, id);
+            }
+        }
+
+        let entries = self.audit_log
+            .find(query)
+            .sort(doc! { "created_at": -1, "_id": -1 })
+            .limit(limit)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,75 @@
+use futures_util::future::BoxFuture;
+use mongodb::error::{Error, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT};
+use mongodb::ClientSession;
+use std::time::Duration;
+
+use super::MongoStore;
+use crate::repo::{RepoError, RepoResult};
+
+// A write conflict or failover can abort a transaction that would succeed on
+// a second try; past this many attempts the error goes to the client.
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(25);
+
+// Runs `body` in a transaction and commits it, so writes that span collections
+// land together or not at all. Every write in `body` must pass `.session(&mut *session)`.
+// `body` runs again from the start when Mongo labels the failure transient, so it
+// must not have side effects outside the session. Needs a replica set or mongos.
+//
+//     transaction(self, |session, store| Box::pin(async move {
+//         store.projects.update_one(filter, update).session(&mut *session).await?;
+//         Ok(())
+//     })).await?;
+pub async fn transaction<T, F>(store: &MongoStore, mut body: F) -> RepoResult<T>
+where
+    F: for<'s> FnMut(&'s mut ClientSession, &'s MongoStore) -> BoxFuture<'s, RepoResult<T>>,
+{
+    let mut session = store.client.start_session().await?;
+    let mut attempt = 1;
+    loop {
+        session.start_transaction().await?;
+        let error = match body(&mut session, store).await {
+            Ok(value) => match commit(&mut session).await {
+                Ok(()) => return Ok(value),
+                Err(e) => e,
+            },
+            Err(RepoError::Database(e)) => {
+                abort(&mut session).await;
+                e
+            }
+            Err(e) => {
+                abort(&mut session).await;
+                return Err(e);
+            }
+        };
+
+        if !error.contains_label(TRANSIENT_TRANSACTION_ERROR) || attempt == MAX_ATTEMPTS {
+            return Err(error.into());
+        }
+        tracing::warn!(attempt, error = %error, "transient transaction error, retrying");
+        tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+        attempt += 1;
+    }
+}
+
+// A commit whose outcome is unknown (say the primary stepped down mid-commit)
+// is safe to send again; the server applies it at most once
+async fn commit(session: &mut ClientSession) -> Result<(), Error> {
+    let mut attempt = 1;
+    loop {
+        match session.commit_transaction().await {
+            Err(e) if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) && attempt < MAX_ATTEMPTS => {
+                tracing::warn!(attempt, error = %e, "unknown commit result, retrying commit");
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// The server may already have aborted it; either way nothing was written
+async fn abort(session: &mut ClientSession) {
+    if let Err(e) = session.abort_transaction().await {
+        tracing::debug!(error = %e, "abort after a failed transaction");
+    }
+}
@@ -0,0 +1,36 @@
+use axum::{extract::{Query, State}, Json};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{db::AppState, models::AuditEntry};
+use crate::error::{parse_id, AppResult};
+use crate::repo::AuditFilter;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    pub action: Option<String>,
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    pub limit: Option<i64>, // Default 100, at most 500
+}
+
+// GET /api/v1/audit-log - Admin: audit log entries, newest first
+#[utoipa::path(
+    get, path = "/api/v1/audit-log", tag = "users", security(("bearer" = [])),
+    params(AuditLogQuery),
+    responses((status = 200, body = Vec<AuditEntry>))
+)]
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditLogQuery>,
+) -> AppResult<Json<Vec<AuditEntry>>> {
+    let filter = AuditFilter {
+        action: query.action,
+        actor_id: query.actor_id.map(|id| parse_id(&id, "actor_id")).transpose()?,
+        target_id: query.target_id.map(|id| parse_id(&id, "target_id")).transpose()?,
+    };
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    Ok(Json(state.audit_log.list(&filter, limit).await?))
+}
@@ -1,26 +1,33 @@
-use axum::{extract::State, Json, http::StatusCode, response::IntoResponse};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::{Path, State}, Json, http::StatusCode};
+use mongodb::bson::DateTime;
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
 
 use crate::db::AppState;
+use crate::error::{found, AppError, AppResult, ErrorBody};
 use crate::models::Blog;
-use crate::middleware::auth::Claims;
+use crate::models::user::Permission;
+use crate::middleware::auth::{AuthUser, MaybeAuthUser};
+use crate::repo::list::Sort;
+use crate::repo::{Page, RepoError};
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{http_url, not_blank, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateBlogRequest {
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: String,
+    #[validate(length(max = 500), custom(function = "not_blank"))]
     pub description: String,
+    #[validate(length(max = 100_000), custom(function = "not_blank"))]
     pub content: String,
+    #[validate(custom(function = "http_url"))]
     pub image_url: Option<String>,
+    #[validate(length(max = 50))]
     pub category: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct DeleteBlogRequest {
-    pub blog_id: String,
-}
-
 fn slugify(title: &str) -> String {
     title
         .to_lowercase()
@@ -32,105 +39,135 @@ fn slugify(title: &str) -> String {
         .join("-")
 }
 
-// GET /blogs - Public: get all blogs
-pub async fn get_all_blogs(State(state): State<AppState>) -> Json<Vec<Blog>> {
-    let mut cursor = state.blogs.find(doc! {}).await.unwrap();
-    let mut blogs = Vec::new();
-    while let Some(blog) = cursor.try_next().await.unwrap() {
-        blogs.push(blog);
-    }
-    // Sort by created_at descending (newest first)
-    blogs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-    Json(blogs)
+pub struct BlogList;
+
+impl ListSpec for BlogList {
+    const SORTS: &'static [&'static str] = &["created_at", "title"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: true };
+    const FILTERS: &'static [(&'static str, Filter)] = &[("category", Filter::Text), ("author_id", Filter::Id)];
 }
 
-// GET /blogs/:slug - Public: get a single blog by slug
+// GET /api/v1/blogs - Public: get blogs, newest first
+#[utoipa::path(
+    get, path = "/api/v1/blogs", tag = "blogs",
+    params(ListQuery<BlogList>),
+    responses((status = 200, body = Page<Blog>))
+)]
+pub async fn get_all_blogs(
+    State(state): State<AppState>,
+    query: ListQuery<BlogList>,
+) -> AppResult<Json<Page<Blog>>> {
+    Ok(Json(state.blogs.page(&query.params).await?))
+}
+
+fn can_delete(blog: &Blog, user: &AuthUser) -> bool {
+    blog.author_id == user.id || user.can(Permission::BlogsModerate)
+}
+
+// GET /api/v1/blogs/{slug} - Public: get a single blog by slug
+#[utoipa::path(
+    get, path = "/api/v1/blogs/{slug}", tag = "blogs", security((), ("bearer" = [])),
+    params(("slug" = String, Path)),
+    responses(
+        (status = 200, description = "The blog, plus `can_delete` for the signed-in viewer", body = Blog),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn get_blog_by_slug(
     State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    match state.blogs.find_one(doc! { "slug": &slug }).await.unwrap() {
-        Some(blog) => (StatusCode::OK, Json(serde_json::to_value(blog).unwrap())).into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "Blog not found"})),
-        ).into_response(),
-    }
+    MaybeAuthUser(viewer): MaybeAuthUser,
+    Path(slug): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let blog = found(state.blogs.find_by_slug(&slug).await?, "Blog")?;
+
+    // Lets the page show a delete button to the author and moderators
+    let can_delete = viewer.is_some_and(|user| can_delete(&blog, &user));
+    let mut body = serde_json::to_value(blog).map_err(|e| AppError::Internal(e.to_string()))?;
+    body["can_delete"] = serde_json::json!(can_delete);
+    Ok(Json(body))
 }
 
-// POST /blogs - Authenticated: create a blog
+// POST /api/v1/blogs - Authenticated: create a blog
+#[utoipa::path(
+    post, path = "/api/v1/blogs", tag = "blogs", security(("bearer" = [])),
+    request_body = CreateBlogRequest,
+    responses(
+        (status = 201, body = Object, example = json!({"message": "Blog created", "slug": "building-a-line-follower"}))
+    )
+)]
 pub async fn create_blog(
     State(state): State<AppState>,
-    axum::Extension(claims): axum::Extension<Claims>,
-    Json(payload): Json<CreateBlogRequest>,
-) -> impl IntoResponse {
-    let author_id = match ObjectId::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid user ID"}))).into_response(),
-    };
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateBlogRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let author_id = auth_user.id;
 
     // Look up author name
-    let author_name = match state.users.find_one(doc! { "_id": author_id }).await.unwrap() {
+    let author_name = match state.users.get(author_id).await? {
         Some(user) => user.full_name,
-        None => claims.sub.clone(),
+        None => auth_user.username.clone(),
     };
 
     let base_slug = slugify(&payload.title);
-    
-    // Check for slug uniqueness, append number if needed
-    let mut slug = base_slug.clone();
-    let mut counter = 1;
-    while state.blogs.find_one(doc! { "slug": &slug }).await.unwrap().is_some() {
-        slug = format!("{}-{}", base_slug, counter);
-        counter += 1;
-    }
-
-    let now = chrono::Utc::now().to_rfc3339();
-    let blog = Blog {
+    let now = DateTime::now();
+    let mut blog = Blog {
         id: None,
         title: payload.title,
-        slug: slug.clone(),
+        slug: base_slug.clone(),
         description: payload.description,
         content: payload.content,
         author_name,
         author_id,
         image_url: payload.image_url,
         category: payload.category,
-        created_at: now.clone(),
+        created_at: now,
         updated_at: now,
     };
 
-    match state.blogs.insert_one(blog).await {
-        Ok(_) => (StatusCode::CREATED, Json(serde_json::json!({"message": "Blog created", "slug": slug}))).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    // The unique slug index decides; on a clash append a number and try again
+    let mut counter = 1;
+    loop {
+        match state.blogs.insert(&blog).await {
+            Ok(_) => break,
+            Err(RepoError::Duplicate) => {
+                blog.slug = format!("{}-{}", base_slug, counter);
+                counter += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
     }
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({"message": "Blog created", "slug": blog.slug}))))
 }
 
-// DELETE /blogs - Authenticated: author can delete own, admin can delete any
+// DELETE /api/v1/blogs/{slug} - Authenticated: author can delete own, admin can delete any
+#[utoipa::path(
+    delete, path = "/api/v1/blogs/{slug}", tag = "blogs", security(("bearer" = [])),
+    params(("slug" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Blog deleted"})),
+        (status = 403, description = "Not the author or a moderator", body = ErrorBody),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn delete_blog(
     State(state): State<AppState>,
-    axum::Extension(claims): axum::Extension<Claims>,
-    Json(payload): Json<DeleteBlogRequest>,
-) -> impl IntoResponse {
-    let blog_id = match ObjectId::parse_str(&payload.blog_id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid blog ID"}))).into_response(),
-    };
+    auth_user: AuthUser,
+    Path(slug): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let blog = found(state.blogs.find_by_slug(&slug).await?, "Blog")?;
+    remove_blog(&state, &auth_user, blog).await
+}
 
-    // Find the blog
-    let blog = match state.blogs.find_one(doc! { "_id": blog_id }).await.unwrap() {
-        Some(b) => b,
-        None => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Blog not found"}))).into_response(),
-    };
+// Delete a blog the caller has found, if they may
+pub async fn remove_blog(state: &AppState, auth_user: &AuthUser, blog: Blog) -> AppResult<Json<serde_json::Value>> {
+    let blog_id = blog.id.ok_or_else(|| AppError::Internal("Blog without an id".to_string()))?;
 
-    // Check: must be author or admin
-    let user_id = ObjectId::parse_str(&claims.sub).unwrap_or_default();
-    if blog.author_id != user_id && claims.role != "Admin" {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "You can only delete your own blogs"}))).into_response();
+    // Check: must be author or a blog moderator
+    if !can_delete(&blog, auth_user) {
+        return Err(AppError::Forbidden("You can only delete your own blogs".to_string()));
     }
 
-    match state.blogs.delete_one(doc! { "_id": blog_id }).await {
-        Ok(_) => (StatusCode::OK, Json(serde_json::json!({"message": "Blog deleted"}))).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
-    }
+    state.blogs.delete(blog_id).await?;
+    Ok(Json(serde_json::json!({"message": "Blog deleted"})))
 }
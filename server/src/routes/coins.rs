@@ -1,138 +1,140 @@
-use axum::{extract::State, Json};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::{Path, State}, Json};
+use mongodb::bson::DateTime;
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 use crate::db::AppState;
+use crate::error::{parse_id, AppResult, ErrorBody};
 use crate::models::{CoinTransaction, WeeklyLeaderboard, LeaderboardEntry};
+use crate::middleware::auth::AuthUser;
+use crate::repo::list::Sort;
+use crate::repo::Page;
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{not_blank, ValidJson};
 
-#[derive(Deserialize)]
+// The most one transaction may grant or take away
+const MAX_COIN_AMOUNT: i32 = 10_000;
+
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CoinTransactionRequest {
-    pub user_id: String,
-    pub amount: i32,
-    pub admin_id: String,
+    #[validate(range(min = -MAX_COIN_AMOUNT, max = MAX_COIN_AMOUNT), custom(function = "not_zero"))]
+    pub amount: i32,   // Negative to take coins away
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub reason: String,
 }
 
+fn not_zero(amount: i32) -> Result<(), ValidationError> {
+    if amount == 0 {
+        return Err(ValidationError::new("zero").with_message("must not be zero".into()));
+    }
+    Ok(())
+}
+
 // Add/Remove coins (admin only)
+#[utoipa::path(
+    post, path = "/api/v1/users/{id}/coin-transactions", tag = "coins", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = CoinTransactionRequest,
+    responses(
+        (status = 200, body = String, example = json!("Coins updated successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn manage_coins(
     State(state): State<AppState>,
-    Json(payload): Json<CoinTransactionRequest>,
-) -> Json<String> {
-    let user_id = ObjectId::parse_str(&payload.user_id).unwrap();
-    let admin_id = ObjectId::parse_str(&payload.admin_id).unwrap();
-
-    // Create transaction record
-    let transaction = CoinTransaction {
+    auth_user: AuthUser,
+    Path(user_id): Path<String>,
+    ValidJson(payload): ValidJson<CoinTransactionRequest>,
+) -> AppResult<Json<String>> {
+    let user_id = parse_id(&user_id, "user_id")?;
+    let coin_transaction = CoinTransaction {
         id: None,
         user_id,
         amount: payload.amount,
-        admin_id,
+        admin_id: auth_user.id,
         reason: payload.reason,
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: DateTime::now(),
     };
 
-    state.coin_transactions.insert_one(transaction).await.unwrap();
-
-    // Update user's coin balance
-    state.users
-        .update_one(
-            doc! { "_id": user_id },
-            doc! { 
-                "$inc": { "coins": payload.amount },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-        .unwrap();
-
-    Json("Coins updated successfully".to_string())
-}
-
-// Get coin transaction history
-pub async fn get_coin_transactions(
-    State(state): State<AppState>,
-    Json(user_id): Json<String>,
-) -> Json<Vec<CoinTransaction>> {
-    let oid = ObjectId::parse_str(&user_id).unwrap();
-    
-    let mut cursor = state.coin_transactions
-        .find(doc! { "user_id": oid })
-        .await
-        .unwrap();
-    
-    let mut transactions = Vec::new();
-    while let Some(transaction) = cursor.try_next().await.unwrap() {
-        transactions.push(transaction);
-    }
+    // The ledger entry and the balance change commit together
+    state.coins.record(&coin_transaction).await?;
 
-    Json(transactions)
+    Ok(Json("Coins updated successfully".to_string()))
 }
 
-// Get weekly leaderboard
-pub async fn get_weekly_leaderboard(State(state): State<AppState>) -> Json<Vec<LeaderboardEntry>> {
-    // Get all users and sort by coins
-    let mut cursor = state.users
-        .find(doc! {})
-        .await
-        .unwrap();
-    
-    let mut users = Vec::new();
-    while let Some(user) = cursor.try_next().await.unwrap() {
-        users.push(LeaderboardEntry {
-            user_id: user.id.unwrap(),
-            username: user.username,
-            coins_earned: user.coins,
-            rank: 0,
-        });
-    }
-    
-    users.sort_by(|a, b| b.coins_earned.cmp(&a.coins_earned));
+pub struct CoinTransactionList;
 
-    for (index, user) in users.iter_mut().enumerate() {
-        user.rank = (index + 1) as i32;
-    }
+impl ListSpec for CoinTransactionList {
+    const SORTS: &'static [&'static str] = &["created_at", "amount"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[("admin_id", Filter::Id)];
+}
 
-    Json(users)
+// Get the current user's coin transaction history
+#[utoipa::path(
+    get, path = "/api/v1/users/me/coin-transactions", tag = "coins", security(("bearer" = [])),
+    params(ListQuery<CoinTransactionList>),
+    responses((status = 200, description = "The caller's transactions", body = Page<CoinTransaction>))
+)]
+pub async fn get_coin_transactions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    query: ListQuery<CoinTransactionList>,
+) -> AppResult<Json<Page<CoinTransaction>>> {
+    Ok(Json(state.coins.page_for_user(auth_user.id, &query.params).await?))
 }
 
-// Create/Save weekly leaderboard snapshot
-pub async fn save_weekly_leaderboard(State(state): State<AppState>) -> Json<String> {
-    // Get current leaderboard
-    let mut cursor = state.users
-        .find(doc! {})
-        .await
-        .unwrap();
-    
+// Every user ranked by coins, highest first
+async fn current_rankings(state: &AppState) -> AppResult<Vec<LeaderboardEntry>> {
     let mut rankings = Vec::new();
-    while let Some(user) = cursor.try_next().await.unwrap() {
+    for user in state.users.list().await? {
+        let Some(user_id) = user.id else { continue };
         rankings.push(LeaderboardEntry {
-            user_id: user.id.unwrap(),
+            user_id,
             username: user.username,
             coins_earned: user.coins,
             rank: 0,
         });
     }
-
-    rankings.sort_by(|a, b| b.coins_earned.cmp(&a.coins_earned));
     
+    rankings.sort_by_key(|entry| std::cmp::Reverse(entry.coins_earned));
+
     for (index, entry) in rankings.iter_mut().enumerate() {
         entry.rank = (index + 1) as i32;
     }
 
-    let now = chrono::Utc::now();
-    let week_start = (now - chrono::Duration::weeks(1)).to_rfc3339();
-    let week_end = now.to_rfc3339();
+    Ok(rankings)
+}
+
+// Get weekly leaderboard
+#[utoipa::path(
+    get, path = "/api/v1/coins/leaderboard", tag = "coins",
+    responses((status = 200, body = Vec<LeaderboardEntry>))
+)]
+pub async fn get_weekly_leaderboard(State(state): State<AppState>) -> AppResult<Json<Vec<LeaderboardEntry>>> {
+    Ok(Json(current_rankings(&state).await?))
+}
+
+// Create/Save weekly leaderboard snapshot
+#[utoipa::path(
+    post, path = "/api/v1/coins/leaderboard/snapshots", tag = "coins", security(("bearer" = [])),
+    responses((status = 200, body = String, example = json!("Weekly leaderboard saved successfully")))
+)]
+pub async fn save_weekly_leaderboard(State(state): State<AppState>) -> AppResult<Json<String>> {
+    // Get current leaderboard
+    let rankings = current_rankings(&state).await?;
 
+    let now = DateTime::now();
     let leaderboard = WeeklyLeaderboard {
         id: None,
-        week_start,
-        week_end,
+        week_start: DateTime::from_millis(now.timestamp_millis() - chrono::Duration::weeks(1).num_milliseconds()),
+        week_end: now,
         rankings,
-        created_at: now.to_rfc3339(),
+        created_at: now,
     };
 
-    state.leaderboards.insert_one(leaderboard).await.unwrap();
+    state.coins.save_leaderboard(&leaderboard).await?;
 
-    Json("Weekly leaderboard saved successfully".to_string())
+    Ok(Json("Weekly leaderboard saved successfully".to_string()))
 }
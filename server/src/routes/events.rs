@@ -1,76 +1,143 @@
-use axum::{extract::State, Json, http::StatusCode, response::IntoResponse};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::{Path, State}, Json, http::StatusCode};
+use chrono::{NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 use crate::db::AppState;
+use crate::error::{parse_id, AppError, AppResult, ErrorBody};
 use crate::models::{Event, EventType, EventStatus, EventSpeaker, Message, MessageType};
 use crate::middleware::auth::AuthUser;
+use crate::models::user::{Permission, Role};
+use crate::repo::list::Sort;
+use crate::repo::{EventChanges, Page};
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{http_url, invalid_field, not_blank, not_one_of, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct SpeakerInput {
+    #[validate(length(max = 100), custom(function = "not_blank"))]
     pub name: String,
+    #[validate(length(max = 100))]
     pub role: String,
+    #[validate(custom(function = "http_url"))]
     pub avatar: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateEventRequest {
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: String,
-    pub date: String,
-    pub time: String,
-    pub end_date: Option<String>,
+    pub starts_at: String,        // RFC 3339, or "2025-03-14T14:00" local to `timezone`
+    pub ends_at: Option<String>,
+    pub timezone: Option<String>, // IANA name; defaults to EVENT_TIMEZONE
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub location: String,
-    pub event_type: String,       // "Workshop", "Competition", "Hackathon", "Meetup", "Other"
-    pub status: String,           // "Upcoming", "Ongoing", "Completed"
+    #[validate(custom(function = "known_event_type"))]
+    pub event_type: String,       // "Workshop", "Competition", "Hackathon", "Meetup", "Other"; any case
+    #[validate(custom(function = "known_event_status"))]
+    pub status: String,           // "Upcoming", "Ongoing", "Completed"; any case
+    #[validate(length(max = 10000), custom(function = "not_blank"))]
     pub description: String,
+    #[validate(custom(function = "http_url"))]
     pub image: Option<String>,
     pub featured: Option<bool>,
+    #[validate(custom(function = "http_url"))]
     pub register_link: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub recap_link: Option<String>,
+    #[validate(nested)]
     pub speakers: Option<Vec<SpeakerInput>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateEventRequest {
-    pub id: String,
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: Option<String>,
-    pub date: Option<String>,
-    pub time: Option<String>,
-    pub end_date: Option<String>,
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    pub timezone: Option<String>,
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub location: Option<String>,
+    #[validate(custom(function = "known_event_type"))]
     pub event_type: Option<String>,
+    #[validate(custom(function = "known_event_status"))]
     pub status: Option<String>,
+    #[validate(length(max = 10000), custom(function = "not_blank"))]
     pub description: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub image: Option<String>,
     pub featured: Option<bool>,
+    #[validate(custom(function = "http_url"))]
     pub register_link: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub recap_link: Option<String>,
+    #[validate(nested)]
     pub speakers: Option<Vec<SpeakerInput>>,
 }
 
-#[derive(Deserialize)]
-pub struct DeleteEventRequest {
-    pub id: String,
-}
+const EVENT_TYPES: &[&str] = &["Workshop", "Competition", "Hackathon", "Meetup", "Other"];
+const EVENT_STATUSES: &[&str] = &["Upcoming", "Ongoing", "Completed"];
 
-fn parse_event_type(s: &str) -> EventType {
+fn parse_event_type(s: &str) -> Result<EventType, ValidationError> {
     match s.to_lowercase().as_str() {
-        "workshop" => EventType::Workshop,
-        "competition" => EventType::Competition,
-        "hackathon" => EventType::Hackathon,
-        "meetup" => EventType::Meetup,
-        _ => EventType::Other,
+        "workshop" => Ok(EventType::Workshop),
+        "competition" => Ok(EventType::Competition),
+        "hackathon" => Ok(EventType::Hackathon),
+        "meetup" => Ok(EventType::Meetup),
+        "other" => Ok(EventType::Other),
+        _ => Err(not_one_of(EVENT_TYPES)),
     }
 }
 
-fn parse_event_status(s: &str) -> EventStatus {
+fn parse_event_status(s: &str) -> Result<EventStatus, ValidationError> {
     match s.to_lowercase().as_str() {
-        "upcoming" => EventStatus::Upcoming,
-        "ongoing" => EventStatus::Ongoing,
-        "completed" => EventStatus::Completed,
-        _ => EventStatus::Upcoming,
+        "upcoming" => Ok(EventStatus::Upcoming),
+        "ongoing" => Ok(EventStatus::Ongoing),
+        "completed" => Ok(EventStatus::Completed),
+        _ => Err(not_one_of(EVENT_STATUSES)),
+    }
+}
+
+fn known_event_type(value: &str) -> Result<(), ValidationError> {
+    parse_event_type(value).map(drop)
+}
+
+fn known_event_status(value: &str) -> Result<(), ValidationError> {
+    parse_event_status(value).map(drop)
+}
+
+fn parse_timezone(name: &str) -> AppResult<Tz> {
+    name.parse().map_err(|_| {
+        invalid_field("timezone", ValidationError::new("timezone").with_message("must be an IANA time zone like Asia/Kolkata".into()))
+    })
+}
+
+// An instant with an explicit offset, or a wall-clock time in the event's zone
+fn parse_event_time(value: &str, timezone: Tz, field: &str) -> AppResult<DateTime> {
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(DateTime::from_millis(at.timestamp_millis()));
     }
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .map_err(|_| {
+            invalid_field(field, ValidationError::new("time").with_message("must be RFC 3339 or YYYY-MM-DDTHH:MM".into()))
+        })?;
+    // In a DST gap there's no such wall-clock time; in an overlap take the earlier one
+    let at = timezone.from_local_datetime(&local).earliest().ok_or_else(|| {
+        let message = format!("doesn't exist in {}", timezone);
+        invalid_field(field, ValidationError::new("time").with_message(message.into()))
+    })?;
+    Ok(DateTime::from_millis(at.timestamp_millis()))
+}
+
+fn check_schedule(starts_at: DateTime, ends_at: Option<DateTime>) -> AppResult<()> {
+    if ends_at.is_some_and(|ends_at| ends_at < starts_at) {
+        return Err(invalid_field("ends_at", ValidationError::new("schedule").with_message("must not be before starts_at".into())));
+    }
+    Ok(())
 }
 
 fn convert_speakers(speakers: Option<Vec<SpeakerInput>>) -> Option<Vec<EventSpeaker>> {
@@ -83,155 +150,195 @@ fn convert_speakers(speakers: Option<Vec<SpeakerInput>>) -> Option<Vec<EventSpea
     })
 }
 
-// GET /events - Public: get all events
-pub async fn get_all_events(State(state): State<AppState>) -> Json<Vec<Event>> {
-    let mut cursor = state.events.find(doc! {}).await.unwrap();
-    let mut events = Vec::new();
-    while let Some(event) = cursor.try_next().await.unwrap() {
-        events.push(event);
-    }
-    Json(events)
+pub struct EventList;
+
+impl ListSpec for EventList {
+    const SORTS: &'static [&'static str] = &["starts_at", "created_at", "title"];
+    const DEFAULT_SORT: Sort = Sort { field: "starts_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[
+        ("event_type", Filter::OneOf(EVENT_TYPES)),
+        ("status", Filter::OneOf(EVENT_STATUSES)),
+        ("featured", Filter::Bool),
+    ];
 }
 
-// POST /events/admin - Admin: create event
-pub async fn create_event(
+// GET /api/v1/events - Public: get events, soonest first
+#[utoipa::path(
+    get, path = "/api/v1/events", tag = "events",
+    params(ListQuery<EventList>),
+    responses((status = 200, body = Page<Event>))
+)]
+pub async fn get_all_events(
     State(state): State<AppState>,
-    axum::Extension(auth_user): axum::Extension<AuthUser>,
-    Json(payload): Json<CreateEventRequest>,
-) -> impl IntoResponse {
-    let admin_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid user ID"}))),
-    };
+    query: ListQuery<EventList>,
+) -> AppResult<Json<Page<Event>>> {
+    Ok(Json(state.events.page(&query.params).await?))
+}
 
-    let now = chrono::Utc::now().to_rfc3339();
+// POST /api/v1/events - Admin: create event
+#[utoipa::path(
+    post, path = "/api/v1/events", tag = "events", security(("bearer" = [])),
+    request_body = CreateEventRequest,
+    responses(
+        (status = 201, body = Object, example = json!({"id": "507f1f77bcf86cd799439011"})),
+        (status = 400, description = "Unknown time zone, unreadable time, or an end before the start", body = ErrorBody)
+    )
+)]
+pub async fn create_event(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateEventRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let timezone = parse_timezone(payload.timezone.as_deref().unwrap_or(&state.config.events.timezone))?;
+    let starts_at = parse_event_time(&payload.starts_at, timezone, "starts_at")?;
+    let ends_at = payload.ends_at
+        .as_deref()
+        .map(|ends_at| parse_event_time(ends_at, timezone, "ends_at"))
+        .transpose()?;
+    check_schedule(starts_at, ends_at)?;
+    let event_type = parse_event_type(&payload.event_type).map_err(|e| invalid_field("event_type", e))?;
+    let status = parse_event_status(&payload.status).map_err(|e| invalid_field("status", e))?;
+    let now = DateTime::now();
 
     let event = Event {
         id: None,
         title: payload.title,
-        date: payload.date,
-        time: payload.time,
-        end_date: payload.end_date,
+        starts_at,
+        ends_at,
+        timezone: timezone.name().to_string(),
         location: payload.location,
-        event_type: parse_event_type(&payload.event_type),
-        status: parse_event_status(&payload.status),
+        event_type,
+        status,
         description: payload.description,
         image: payload.image,
         featured: payload.featured.unwrap_or(false),
         register_link: payload.register_link,
         recap_link: payload.recap_link,
         speakers: convert_speakers(payload.speakers),
-        created_by: admin_id,
-        created_at: now.clone(),
+        created_by: auth_user.id,
+        created_at: now,
         updated_at: now,
     };
 
-    match state.events.insert_one(event).await {
-        Ok(result) => (StatusCode::CREATED, Json(serde_json::json!({"id": result.inserted_id.to_string()}))),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
-    }
+    let id = state.events.insert(&event).await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({"id": id.to_hex()}))))
 }
 
-// PATCH /events/admin - Admin: update event
+// PATCH /api/v1/events/{id} - Admin: update event
+#[utoipa::path(
+    patch, path = "/api/v1/events/{id}", tag = "events", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = UpdateEventRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Updated"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn update_event(
     State(state): State<AppState>,
-    Json(payload): Json<UpdateEventRequest>,
-) -> impl IntoResponse {
-    let oid = match ObjectId::parse_str(&payload.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid event ID"}))),
-    };
+    Path(id): Path<String>,
+    ValidJson(payload): ValidJson<UpdateEventRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let oid = parse_id(&id, "event ID")?;
+    let event_type = payload.event_type
+        .map(|event_type| parse_event_type(&event_type).map_err(|e| invalid_field("event_type", e)))
+        .transpose()?;
+    let status = payload.status
+        .map(|status| parse_event_status(&status).map_err(|e| invalid_field("status", e)))
+        .transpose()?;
 
-    let mut update_doc = doc! {};
-    if let Some(title) = payload.title { update_doc.insert("title", title); }
-    if let Some(date) = payload.date { update_doc.insert("date", date); }
-    if let Some(time) = payload.time { update_doc.insert("time", time); }
-    if let Some(end_date) = payload.end_date { update_doc.insert("end_date", end_date); }
-    if let Some(location) = payload.location { update_doc.insert("location", location); }
-    if let Some(event_type) = payload.event_type {
-        update_doc.insert("event_type", mongodb::bson::to_bson(&parse_event_type(&event_type)).unwrap());
-    }
-    if let Some(status) = payload.status {
-        update_doc.insert("status", mongodb::bson::to_bson(&parse_event_status(&status)).unwrap());
-    }
-    if let Some(description) = payload.description { update_doc.insert("description", description); }
-    if let Some(image) = payload.image { update_doc.insert("image", image); }
-    if let Some(featured) = payload.featured { update_doc.insert("featured", featured); }
-    if let Some(register_link) = payload.register_link { update_doc.insert("register_link", register_link); }
-    if let Some(recap_link) = payload.recap_link { update_doc.insert("recap_link", recap_link); }
-    if let Some(speakers) = payload.speakers {
-        let speakers_bson = mongodb::bson::to_bson(&convert_speakers(Some(speakers))).unwrap();
-        update_doc.insert("speakers", speakers_bson);
+    let mut changes = EventChanges {
+        title: payload.title,
+        location: payload.location,
+        event_type,
+        status,
+        description: payload.description,
+        image: payload.image,
+        featured: payload.featured,
+        register_link: payload.register_link,
+        recap_link: payload.recap_link,
+        speakers: payload.speakers.and_then(|speakers| convert_speakers(Some(speakers))),
+        ..EventChanges::default()
+    };
+    if payload.starts_at.is_some() || payload.ends_at.is_some() || payload.timezone.is_some() {
+        // Wall-clock times are read in the new zone if one is given, else the stored one
+        let event = state.events
+            .get(oid)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Event not found".to_string()))?;
+        let timezone = parse_timezone(payload.timezone.as_deref().unwrap_or(&event.timezone))?;
+        let starts_at = match payload.starts_at.as_deref() {
+            Some(starts_at) => parse_event_time(starts_at, timezone, "starts_at")?,
+            None => event.starts_at,
+        };
+        let ends_at = match payload.ends_at.as_deref() {
+            Some(ends_at) => Some(parse_event_time(ends_at, timezone, "ends_at")?),
+            None => event.ends_at,
+        };
+        check_schedule(starts_at, ends_at)?;
+        changes.starts_at = Some(starts_at);
+        changes.ends_at = ends_at;
+        changes.timezone = Some(timezone.name().to_string());
     }
-    update_doc.insert("updated_at", chrono::Utc::now().to_rfc3339());
-
-    match state.events.update_one(doc! {"_id": oid}, doc! {"$set": update_doc}).await {
-        Ok(result) => {
-            if result.matched_count == 0 {
-                (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Event not found"})))
-            } else {
-                (StatusCode::OK, Json(serde_json::json!({"message": "Updated"})))
-            }
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
+
+    if !state.events.update(oid, &changes).await? {
+        return Err(AppError::NotFound("Event not found".to_string()));
     }
+    Ok(Json(serde_json::json!({"message": "Updated"})))
 }
 
-// DELETE /events/admin - Admin: delete event
+// DELETE /api/v1/events/{id} - Admin: delete event
+#[utoipa::path(
+    delete, path = "/api/v1/events/{id}", tag = "events", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Deleted"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn delete_event(
     State(state): State<AppState>,
-    Json(payload): Json<DeleteEventRequest>,
-) -> impl IntoResponse {
-    let oid = match ObjectId::parse_str(&payload.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid event ID"}))),
-    };
+    Path(id): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let oid = parse_id(&id, "event ID")?;
 
-    match state.events.delete_one(doc! {"_id": oid}).await {
-        Ok(result) => {
-            if result.deleted_count == 0 {
-                (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Event not found"})))
-            } else {
-                (StatusCode::OK, Json(serde_json::json!({"message": "Deleted"})))
-            }
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
+    if !state.events.delete(oid).await? {
+        return Err(AppError::NotFound("Event not found".to_string()));
     }
+    Ok(Json(serde_json::json!({"message": "Deleted"})))
 }
 
-// POST /events/propose - Authenticated users: propose an event idea to admins
-#[derive(Deserialize)]
+// POST /api/v1/events/proposals - Authenticated users: propose an event idea to admins
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct ProposeEventRequest {
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: String,
+    #[validate(custom(function = "known_event_type"))]
     pub event_type: String,
+    #[validate(length(max = 5000), custom(function = "not_blank"))]
     pub description: String,
+    #[validate(length(max = 100))]
     pub preferred_date: Option<String>,
 }
 
+#[utoipa::path(
+    post, path = "/api/v1/events/proposals", tag = "events", security(("bearer" = [])),
+    request_body = ProposeEventRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Event proposal submitted successfully! Admins will review your idea."}))
+    )
+)]
 pub async fn propose_event(
     State(state): State<AppState>,
-    axum::Extension(auth_user): axum::Extension<AuthUser>,
-    Json(payload): Json<ProposeEventRequest>,
-) -> impl IntoResponse {
-    let sender_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid user ID"}))),
-    };
-
-    // Find all admin users
-    let mut cursor = state.users
-        .find(doc! { "role": "Admin" })
-        .await
-        .unwrap();
-    let mut admin_ids: Vec<ObjectId> = Vec::new();
-    while let Some(user) = cursor.try_next().await.unwrap() {
-        if let Some(uid) = user.id {
-            admin_ids.push(uid);
-        }
-    }
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<ProposeEventRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    // Find everyone who can act on event proposals
+    let organisers = state.users.list_by_roles(&Role::with_permission(Permission::EventsWrite)).await?;
+    let admin_ids: Vec<ObjectId> = organisers.into_iter().filter_map(|user| user.id).collect();
 
     if admin_ids.is_empty() {
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": "No admins found"})));
+        return Err(AppError::Internal("No event organisers to receive the proposal".to_string()));
     }
 
     let preferred = payload.preferred_date
@@ -245,18 +352,47 @@ pub async fn propose_event(
 
     let message = Message {
         id: None,
-        sender_id,
+        sender_id: auth_user.id,
         recipient_ids: Some(admin_ids),
         project_id: None,
         subject: format!("[Event Proposal] {}", payload.title),
         content,
         message_type: MessageType::Individual,
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: DateTime::now(),
         read: false,
     };
 
-    match state.messages.insert_one(message).await {
-        Ok(_) => (StatusCode::OK, Json(serde_json::json!({"message": "Event proposal submitted successfully! Admins will review your idea."}))),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
+    state.messages.insert(&message).await?;
+    Ok(Json(serde_json::json!({"message": "Event proposal submitted successfully! Admins will review your idea."})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_wall_clock_times_in_the_event_zone() {
+        let kolkata = parse_timezone("Asia/Kolkata").unwrap();
+        let local = parse_event_time("2025-03-14T14:00", kolkata, "starts_at").unwrap();
+        let explicit = parse_event_time("2025-03-14T08:30:00Z", kolkata, "starts_at").unwrap();
+        assert_eq!(local, explicit);
+
+        assert!(parse_event_time("14 March, 2pm", kolkata, "starts_at").is_err());
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+    }
+
+    #[test]
+    fn rejects_times_skipped_by_daylight_saving() {
+        let new_york = parse_timezone("America/New_York").unwrap();
+        assert!(parse_event_time("2025-03-09T02:30", new_york, "starts_at").is_err());
+        assert!(parse_event_time("2025-03-09T03:30", new_york, "starts_at").is_ok());
+    }
+
+    #[test]
+    fn an_event_cannot_end_before_it_starts() {
+        let starts_at = DateTime::from_millis(1_000);
+        assert!(check_schedule(starts_at, Some(DateTime::from_millis(999))).is_err());
+        assert!(check_schedule(starts_at, Some(starts_at)).is_ok());
+        assert!(check_schedule(starts_at, None).is_ok());
     }
 }
@@ -1,59 +1,76 @@
-use axum::{extract::State, Json, http::StatusCode, response::IntoResponse};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::{Path, State}, Json, http::StatusCode};
+use mongodb::bson::DateTime;
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
 
 use crate::db::AppState;
+use crate::error::{parse_id, AppError, AppResult, ErrorBody};
 use crate::models::GalleryItem;
 use crate::middleware::auth::AuthUser;
+use crate::repo::list::Sort;
+use crate::repo::{GalleryChanges, Page};
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{http_url, not_blank, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateGalleryItemRequest {
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: String,
+    #[validate(length(max = 50), custom(function = "not_blank"))]
     pub category: String,
+    #[validate(custom(function = "http_url"))]
     pub image_url: String,
+    #[validate(length(max = 2000))]
     pub description: String,
+    #[validate(custom(function = "http_url"))]
     pub thumbnail_url: Option<String>,
     pub featured: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateGalleryItemRequest {
-    pub id: String,
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub title: Option<String>,
+    #[validate(length(max = 50), custom(function = "not_blank"))]
     pub category: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub image_url: Option<String>,
+    #[validate(length(max = 2000))]
     pub description: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub thumbnail_url: Option<String>,
     pub featured: Option<bool>,
 }
 
-#[derive(Deserialize)]
-pub struct DeleteGalleryItemRequest {
-    pub id: String,
+pub struct GalleryList;
+
+impl ListSpec for GalleryList {
+    const SORTS: &'static [&'static str] = &["created_at", "title"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[("category", Filter::Text), ("featured", Filter::Bool)];
 }
 
-// GET /gallery - Public: get all gallery items
-pub async fn get_all_gallery(State(state): State<AppState>) -> Json<Vec<GalleryItem>> {
-    let mut cursor = state.gallery.find(doc! {}).await.unwrap();
-    let mut items = Vec::new();
-    while let Some(item) = cursor.try_next().await.unwrap() {
-        items.push(item);
-    }
-    Json(items)
+// GET /api/v1/gallery - Public: get gallery items
+#[utoipa::path(get, path = "/api/v1/gallery", tag = "gallery", params(ListQuery<GalleryList>), responses((status = 200, body = Page<GalleryItem>)))]
+pub async fn get_all_gallery(
+    State(state): State<AppState>,
+    query: ListQuery<GalleryList>,
+) -> AppResult<Json<Page<GalleryItem>>> {
+    Ok(Json(state.gallery.page(&query.params).await?))
 }
 
-// POST /gallery/admin - Admin: create gallery item
+// POST /api/v1/gallery - Admin: create gallery item
+#[utoipa::path(
+    post, path = "/api/v1/gallery", tag = "gallery", security(("bearer" = [])),
+    request_body = CreateGalleryItemRequest,
+    responses((status = 201, body = Object, example = json!({"id": "507f1f77bcf86cd799439011"})))
+)]
 pub async fn create_gallery_item(
     State(state): State<AppState>,
-    axum::Extension(auth_user): axum::Extension<AuthUser>,
-    Json(payload): Json<CreateGalleryItemRequest>,
-) -> impl IntoResponse {
-    let admin_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid user ID"}))),
-    };
-
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateGalleryItemRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let item = GalleryItem {
         id: None,
         title: payload.title,
@@ -61,69 +78,67 @@ pub async fn create_gallery_item(
         image_url: payload.image_url,
         description: payload.description,
         thumbnail_url: payload.thumbnail_url,
-        uploaded_by: admin_id,
+        uploaded_by: auth_user.id,
         featured: payload.featured.unwrap_or(false),
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: DateTime::now(),
     };
 
-    match state.gallery.insert_one(item).await {
-        Ok(result) => (StatusCode::CREATED, Json(serde_json::json!({"id": result.inserted_id.to_string()}))),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
-    }
+    let id = state.gallery.insert(&item).await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({"id": id.to_hex()}))))
 }
 
-// PATCH /gallery/admin - Admin: update gallery item
+// PATCH /api/v1/gallery/{id} - Admin: update gallery item
+#[utoipa::path(
+    patch, path = "/api/v1/gallery/{id}", tag = "gallery", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = UpdateGalleryItemRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Updated"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn update_gallery_item(
     State(state): State<AppState>,
-    Json(payload): Json<UpdateGalleryItemRequest>,
-) -> impl IntoResponse {
-    let oid = match ObjectId::parse_str(&payload.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid gallery item ID"}))),
-    };
-
-    let mut update_doc = doc! {};
-    if let Some(title) = payload.title { update_doc.insert("title", title); }
-    if let Some(category) = payload.category { update_doc.insert("category", category); }
-    if let Some(image_url) = payload.image_url { update_doc.insert("image_url", image_url); }
-    if let Some(description) = payload.description { update_doc.insert("description", description); }
-    if let Some(thumbnail_url) = payload.thumbnail_url { update_doc.insert("thumbnail_url", thumbnail_url); }
-    if let Some(featured) = payload.featured { update_doc.insert("featured", featured); }
+    Path(id): Path<String>,
+    ValidJson(payload): ValidJson<UpdateGalleryItemRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let oid = parse_id(&id, "gallery item ID")?;
 
-    if update_doc.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "No fields to update"})));
+    let changes = GalleryChanges {
+        title: payload.title,
+        category: payload.category,
+        image_url: payload.image_url,
+        description: payload.description,
+        thumbnail_url: payload.thumbnail_url,
+        featured: payload.featured,
+    };
+    if changes == GalleryChanges::default() {
+        return Err(AppError::BadRequest("No fields to update".to_string()));
     }
 
-    match state.gallery.update_one(doc! {"_id": oid}, doc! {"$set": update_doc}).await {
-        Ok(result) => {
-            if result.matched_count == 0 {
-                (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Gallery item not found"})))
-            } else {
-                (StatusCode::OK, Json(serde_json::json!({"message": "Updated"})))
-            }
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
+    if !state.gallery.update(oid, &changes).await? {
+        return Err(AppError::NotFound("Gallery item not found".to_string()));
     }
+    Ok(Json(serde_json::json!({"message": "Updated"})))
 }
 
-// DELETE /gallery/admin - Admin: delete gallery item
+// DELETE /api/v1/gallery/{id} - Admin: delete gallery item
+#[utoipa::path(
+    delete, path = "/api/v1/gallery/{id}", tag = "gallery", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Deleted"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn delete_gallery_item(
     State(state): State<AppState>,
-    Json(payload): Json<DeleteGalleryItemRequest>,
-) -> impl IntoResponse {
-    let oid = match ObjectId::parse_str(&payload.id) {
-        Ok(oid) => oid,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid gallery item ID"}))),
-    };
+    Path(id): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let oid = parse_id(&id, "gallery item ID")?;
 
-    match state.gallery.delete_one(doc! {"_id": oid}).await {
-        Ok(result) => {
-            if result.deleted_count == 0 {
-                (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Gallery item not found"})))
-            } else {
-                (StatusCode::OK, Json(serde_json::json!({"message": "Deleted"})))
-            }
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))),
+    if !state.gallery.delete(oid).await? {
+        return Err(AppError::NotFound("Gallery item not found".to_string()));
     }
+    Ok(Json(serde_json::json!({"message": "Deleted"})))
 }
@@ -0,0 +1,123 @@
+use axum::{extract::State, http::StatusCode, Json};
+use mongodb::bson::doc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::db::{self, AppState};
+
+// A dependency that doesn't answer within this counts as down, so the
+// probe returns well before the orchestrator's own timeout
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize)]
+struct Check {
+    status: CheckStatus,
+    latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// Runs one check under the timeout. `Ok(None)` is up, `Ok(Some(details))` is down
+// with an explanation, and an error or timeout is down with the error.
+async fn check<F>(probe: F) -> Check
+where
+    F: Future<Output = mongodb::error::Result<Option<Value>>>,
+{
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(CHECK_TIMEOUT, probe).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (status, details, error) = match outcome {
+        Ok(Ok(None)) => (CheckStatus::Up, None, None),
+        Ok(Ok(Some(details))) => (CheckStatus::Down, Some(details), None),
+        Ok(Err(e)) => (CheckStatus::Down, None, Some(e.to_string())),
+        Err(_) => (CheckStatus::Down, None, Some(format!("no answer within {}s", CHECK_TIMEOUT.as_secs()))),
+    };
+    Check { status, latency_ms, details, error }
+}
+
+async fn ping(state: &AppState) -> mongodb::error::Result<Option<Value>> {
+    state.database.run_command(doc! { "ping": 1 }).await?;
+    Ok(None)
+}
+
+async fn missing_indexes(state: &AppState) -> mongodb::error::Result<Option<Value>> {
+    let required = db::migrations::required_indexes();
+    let collections: HashSet<&str> = required.iter().map(|index| index.collection).collect();
+
+    let mut existing = HashSet::new();
+    for collection in collections {
+        let names = state.database.collection::<mongodb::bson::Document>(collection).list_index_names().await?;
+        existing.extend(names.into_iter().map(|name| (collection, name)));
+    }
+
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|index| !existing.contains(&(index.collection, index.name())))
+        .map(|index| format!("{}.{}", index.collection, index.name()))
+        .collect();
+    Ok((!missing.is_empty()).then(|| json!({ "missing": missing })))
+}
+
+async fn pending_migrations(state: &AppState) -> mongodb::error::Result<Option<Value>> {
+    let pending = db::migrations::pending(state).await?;
+    Ok((!pending.is_empty()).then(|| json!({ "pending": pending })))
+}
+
+// GET /health/live - Public: the process is up and serving; never touches the database
+#[utoipa::path(
+    get, path = "/health/live", tag = "operations",
+    responses(
+        (status = 200, body = Object, example = json!({"status": "alive", "timestamp": "2025-11-16T14:30:00+00:00"}))
+    )
+)]
+pub async fn get_live() -> Json<Value> {
+    Json(json!({
+        "status": "alive",
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
+// GET /health/ready - Public: 200 when MongoDB answers and its indexes and migrations
+// are in place, 503 otherwise, with each dependency's status and latency
+#[utoipa::path(
+    get, path = "/health/ready", tag = "operations",
+    responses(
+        (status = 200, description = "MongoDB, indexes and migrations are in place", body = Object),
+        (status = 503, description = "`not_ready`, with each check's status, latency and error", body = Object)
+    )
+)]
+pub async fn get_ready(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let database = check(ping(&state)).await;
+    // Without a database the other checks would only repeat its error
+    let checks: BTreeMap<&str, Check> = if matches!(database.status, CheckStatus::Up) {
+        let (indexes, migrations) = tokio::join!(check(missing_indexes(&state)), check(pending_migrations(&state)));
+        BTreeMap::from([("database", database), ("indexes", indexes), ("migrations", migrations)])
+    } else {
+        BTreeMap::from([("database", database)])
+    };
+
+    let ready = checks.values().all(|check| matches!(check.status, CheckStatus::Up));
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    if !ready {
+        tracing::warn!(checks = %json!(checks), "readiness check failed");
+    }
+
+    (status, Json(json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "checks": checks,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })))
+}
@@ -0,0 +1,656 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
+
+use crate::auth;
+use crate::db::AppState;
+use crate::error::{found, parse_id, AppResult, ErrorBody};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::{auth_middleware, deprecation, rate_limit, require_permission};
+use crate::models::user::Permission;
+use crate::rate_limit::Policies;
+use crate::models::CreateJoinRequest;
+use crate::validate::ValidJson;
+use crate::routes::{
+    audit, blogs, coins, events, gallery, messages, project_join_requests, projects, stats, users,
+};
+
+// Every pre-/api/v1 route and what replaces it, as ("METHOD path", "METHOD path").
+// The OpenAPI spec marks these deprecated and points each at its successor;
+// routes that kept their request shape are documented by copying the successor.
+pub const SUCCESSORS: &[(&str, &str)] = &[
+    ("GET /auth/github", "GET /api/v1/auth/github"),
+    ("GET /auth/github/callback", "GET /api/v1/auth/github/callback"),
+    ("GET /auth/providers", "GET /api/v1/auth/providers"),
+    ("POST /auth/2fa/verify", "POST /api/v1/auth/2fa/verify"),
+    ("GET /auth/{provider}", "GET /api/v1/auth/{provider}"),
+    ("GET /auth/{provider}/callback", "GET /api/v1/auth/{provider}/callback"),
+    ("POST /auth/refresh", "POST /api/v1/auth/refresh"),
+    ("POST /auth/register", "POST /api/v1/auth/register"),
+    ("POST /auth/login", "POST /api/v1/auth/login"),
+    ("POST /auth/password/forgot", "POST /api/v1/auth/password/forgot"),
+    ("POST /auth/password/reset", "POST /api/v1/auth/password/reset"),
+    ("POST /auth/test-login", "POST /api/v1/auth/test-login"),
+    ("GET /auth/sessions", "GET /api/v1/auth/sessions"),
+    ("POST /auth/logout", "POST /api/v1/auth/logout"),
+    ("POST /auth/link/{provider}", "POST /api/v1/auth/link/{provider}"),
+    ("GET /auth/identities", "GET /api/v1/auth/identities"),
+    ("DELETE /auth/identities/{provider}", "DELETE /api/v1/auth/identities/{provider}"),
+    ("GET /auth/2fa", "GET /api/v1/auth/2fa"),
+    ("POST /auth/2fa/enroll", "POST /api/v1/auth/2fa/enroll"),
+    ("POST /auth/2fa/confirm", "POST /api/v1/auth/2fa/confirm"),
+    ("POST /auth/2fa/recovery-codes", "POST /api/v1/auth/2fa/recovery-codes"),
+    ("POST /auth/2fa/disable", "POST /api/v1/auth/2fa/disable"),
+    ("GET /auth/tokens", "GET /api/v1/auth/tokens"),
+    ("POST /auth/tokens", "POST /api/v1/auth/tokens"),
+    ("DELETE /auth/tokens/{id}", "DELETE /api/v1/auth/tokens/{id}"),
+    ("GET /auth/tokens/{id}/activity", "GET /api/v1/auth/tokens/{id}/activity"),
+    ("GET /users", "GET /api/v1/users"),
+    ("POST /users", "POST /api/v1/users"),
+    ("DELETE /users", "DELETE /api/v1/users/{id}"),
+    ("GET /users/{id}", "GET /api/v1/users/{id}"),
+    ("GET /members", "GET /api/v1/members"),
+    ("POST /users/role", "PUT /api/v1/users/{id}/role"),
+    ("POST /users/force-logout", "DELETE /api/v1/users/{id}/sessions"),
+    ("GET /users/pending", "GET /api/v1/users/pending"),
+    ("POST /users/approve", "POST /api/v1/users/{id}/approve"),
+    ("GET /users/invites", "GET /api/v1/invites"),
+    ("POST /users/invites", "POST /api/v1/invites"),
+    ("POST /users/impersonate", "POST /api/v1/users/{id}/impersonate"),
+    ("GET /audit-log", "GET /api/v1/audit-log"),
+    ("GET /projects", "GET /api/v1/projects"),
+    ("GET /projects/user", "GET /api/v1/users/me/projects"),
+    ("POST /projects/user", "GET /api/v1/users/me/projects"),
+    ("POST /projects/admin", "POST /api/v1/projects"),
+    ("PATCH /projects/admin", "PATCH /api/v1/projects/{id}"),
+    ("DELETE /projects/admin", "DELETE /api/v1/projects/{id}"),
+    ("POST /projects/assign", "PUT /api/v1/projects/{id}/members/{member_id}"),
+    ("POST /projects/remove", "DELETE /api/v1/projects/{id}/members/{member_id}"),
+    ("POST /projects/remove-member", "DELETE /api/v1/projects/{id}/members/{member_id}"),
+    ("POST /projects/lead", "PUT /api/v1/projects/{id}/lead"),
+    ("POST /projects/files", "POST /api/v1/projects/{id}/files"),
+    ("DELETE /projects/files", "DELETE /api/v1/projects/{id}/files/{file_id}"),
+    ("POST /projects/join-request", "POST /api/v1/projects/{id}/join-requests"),
+    ("GET /projects/{id}/join-requests", "GET /api/v1/projects/{id}/join-requests"),
+    ("PATCH /projects/join-request/{id}", "PATCH /api/v1/join-requests/{id}"),
+    ("POST /coins/manage", "POST /api/v1/users/{id}/coin-transactions"),
+    ("GET /coins/transactions", "GET /api/v1/users/me/coin-transactions"),
+    ("POST /coins/transactions", "GET /api/v1/users/me/coin-transactions"),
+    ("GET /coins/leaderboard", "GET /api/v1/coins/leaderboard"),
+    ("POST /coins/leaderboard/save", "POST /api/v1/coins/leaderboard/snapshots"),
+    ("POST /messages/send", "POST /api/v1/messages"),
+    ("GET /messages/user", "GET /api/v1/users/me/messages"),
+    ("POST /messages/user", "GET /api/v1/users/me/messages"),
+    ("GET /messages", "GET /api/v1/messages"),
+    ("GET /gallery", "GET /api/v1/gallery"),
+    ("POST /gallery/admin", "POST /api/v1/gallery"),
+    ("PATCH /gallery/admin", "PATCH /api/v1/gallery/{id}"),
+    ("DELETE /gallery/admin", "DELETE /api/v1/gallery/{id}"),
+    ("GET /events", "GET /api/v1/events"),
+    ("POST /events/admin", "POST /api/v1/events"),
+    ("PATCH /events/admin", "PATCH /api/v1/events/{id}"),
+    ("DELETE /events/admin", "DELETE /api/v1/events/{id}"),
+    ("POST /events/propose", "POST /api/v1/events/proposals"),
+    ("GET /stats", "GET /api/v1/stats"),
+    ("GET /blogs", "GET /api/v1/blogs"),
+    ("GET /blogs/{slug}", "GET /api/v1/blogs/{slug}"),
+    ("POST /blogs/create", "POST /api/v1/blogs"),
+    ("POST /blogs/delete", "DELETE /api/v1/blogs/{slug}"),
+];
+
+// The routes the frontend was built against. Handlers that still fit are shared
+// with /api/v1; the rest are adapters below that take ids from the body.
+// Every response carries `Deprecation`.
+pub fn routes(state: &AppState, limits: &Policies) -> Router<AppState> {
+    // Sign-in, signup and account recovery, limited per IP
+    let auth_routes = Router::new()
+        .route("/auth/github", get(auth::github_login))
+        .route("/auth/github/callback", get(auth::github_callback))
+        .route("/auth/2fa/verify", post(auth::two_factor::verify_challenge))
+        .route("/auth/{provider}", get(auth::oidc::oidc_login))
+        .route("/auth/{provider}/callback", get(auth::oidc::oidc_callback))
+        .route("/auth/register", post(auth::password::register))
+        .route("/auth/login", post(auth::password::login))
+        .route("/auth/password/forgot", post(auth::password::forgot_password))
+        .route("/auth/password/reset", post(auth::password::reset_password));
+
+    #[cfg(feature = "dev-login")]
+    let auth_routes = auth_routes.route("/auth/test-login", post(auth::test_login));
+
+    let auth_routes = auth_routes.layer(middleware::from_fn_with_state(state.clone(), rate_limit(limits.auth)));
+
+    // Public routes
+    let public_routes = Router::new()
+        .route("/auth/providers", get(auth::get_providers))
+        .route("/auth/refresh", post(auth::sessions::refresh_session))
+        .route("/coins/leaderboard", get(coins::get_weekly_leaderboard))
+        .route("/projects", get(projects::get_all_projects))
+        .route("/gallery", get(gallery::get_all_gallery))
+        .route("/events", get(events::get_all_events))
+        .route("/stats", get(stats::get_stats))
+        .route("/blogs", get(blogs::get_all_blogs))
+        .route("/blogs/{slug}", get(blogs::get_blog_by_slug));
+
+    // Requests that reach other members, limited per user
+    let outreach_routes = Router::new()
+        .route("/projects/join-request", post(create_join_request))
+        .route("/messages/send", post(messages::send_message))
+        .route("/events/propose", post(events::propose_event))
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit(limits.outreach)));
+
+    // Protected routes
+    let protected_routes = Router::new()
+        .route("/auth/sessions", get(auth::sessions::get_sessions))
+        .route("/auth/logout", post(auth::sessions::logout))
+        .route("/auth/link/{provider}", post(auth::link_provider))
+        .route("/auth/identities", get(auth::identities::get_identities))
+        .route("/auth/identities/{provider}", axum::routing::delete(auth::identities::unlink_identity))
+        .route("/auth/2fa", get(auth::two_factor::get_two_factor_status))
+        .route("/auth/2fa/enroll", post(auth::two_factor::enroll))
+        .route("/auth/2fa/confirm", post(auth::two_factor::confirm))
+        .route("/auth/2fa/recovery-codes", post(auth::two_factor::regenerate_recovery_codes))
+        .route("/auth/2fa/disable", post(auth::two_factor::disable))
+        .route("/auth/tokens", get(auth::access_tokens::get_access_tokens).post(auth::access_tokens::create_access_token))
+        .route("/auth/tokens/{id}", axum::routing::delete(auth::access_tokens::revoke_access_token))
+        .route("/auth/tokens/{id}/activity", get(auth::access_tokens::get_access_token_activity))
+        .route("/users/{id}", get(users::get_user_by_id))
+        .route("/projects/user", get(projects::get_user_projects).post(projects::get_user_projects))
+        .route("/projects/{id}/join-requests", get(project_join_requests::get_project_join_requests))
+        .route("/projects/join-request/{id}", axum::routing::patch(project_join_requests::update_join_request_status))
+        .route("/projects/remove-member", post(remove_member_by_lead))
+        .route("/projects/files", post(add_file_to_project).delete(delete_file_from_project))
+        .route("/coins/transactions", get(coins::get_coin_transactions).post(coins::get_coin_transactions))
+        .route("/messages/user", get(messages::get_user_messages).post(messages::get_user_messages))
+        .route("/blogs/create", post(blogs::create_blog))
+        .route("/blogs/delete", post(delete_blog))
+        .merge(outreach_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    // Privileged routes, each group gated by the permission it needs
+    let user_admin_routes = Router::new()
+        .route("/users", get(users::get_users).post(users::add_user).delete(delete_user))
+        .route("/members", get(users::get_members))
+        .route("/users/role", post(update_user_role))
+        .route("/users/force-logout", post(force_logout_user))
+        .route("/users/pending", get(users::get_pending_users))
+        .route("/users/approve", post(approve_user))
+        .route("/users/invites", get(users::get_invites).post(users::create_invite))
+        .route("/users/impersonate", post(impersonate_user))
+        .route("/audit-log", get(audit::get_audit_log))
+        .layer(middleware::from_fn(require_permission(Permission::UsersManage)));
+
+    let project_admin_routes = Router::new()
+        .route("/projects/admin", post(projects::create_project).delete(delete_project).patch(update_project))
+        .route("/projects/assign", post(assign_member_to_project))
+        .route("/projects/remove", post(remove_member_from_project))
+        .route("/projects/lead", post(set_project_lead))
+        .layer(middleware::from_fn(require_permission(Permission::ProjectsManage)));
+
+    let coin_admin_routes = Router::new()
+        .route("/coins/manage", post(manage_coins))
+        .route("/coins/leaderboard/save", post(coins::save_weekly_leaderboard))
+        .layer(middleware::from_fn(require_permission(Permission::CoinsGrant)));
+
+    let message_admin_routes = Router::new()
+        .route("/messages", get(messages::get_all_messages))
+        .layer(middleware::from_fn(require_permission(Permission::MessagesReadAll)));
+
+    let gallery_admin_routes = Router::new()
+        .route("/gallery/admin", post(gallery::create_gallery_item).patch(update_gallery_item).delete(delete_gallery_item))
+        .layer(middleware::from_fn(require_permission(Permission::GalleryWrite)));
+
+    let event_admin_routes = Router::new()
+        .route("/events/admin", post(events::create_event).patch(update_event).delete(delete_event))
+        .layer(middleware::from_fn(require_permission(Permission::EventsWrite)));
+
+    let admin_routes = Router::new()
+        .merge(user_admin_routes)
+        .merge(project_admin_routes)
+        .merge(coin_admin_routes)
+        .merge(message_admin_routes)
+        .merge(gallery_admin_routes)
+        .merge(event_admin_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    Router::new()
+        .merge(auth_routes)
+        .merge(public_routes)
+        .merge(protected_routes)
+        .merge(admin_routes)
+        .layer(middleware::from_fn(deprecation))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteUserRequest {
+    pub user_id: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ApproveUserRequest {
+    pub user_id: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ForceLogoutRequest {
+    pub user_id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyUpdateRoleRequest {
+    pub user_id: String,
+    #[serde(flatten)]
+    pub body: users::UpdateRoleRequest,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyImpersonateRequest {
+    pub user_id: String,
+    #[serde(flatten)]
+    pub body: auth::impersonation::ImpersonateRequest,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteProjectRequest {
+    pub project_id: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct AssignMemberRequest {
+    pub project_id: String,
+    pub member_id: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteFileRequest {
+    pub project_id: String,
+    pub file_id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyUpdateProjectRequest {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub body: projects::UpdateProjectRequest,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacySetProjectLeadRequest {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub body: projects::SetProjectLeadRequest,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyAddFileRequest {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub body: projects::AddFileRequest,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyCreateJoinRequest {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub body: CreateJoinRequest,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyCoinTransactionRequest {
+    pub user_id: String,
+    #[serde(flatten)]
+    pub body: coins::CoinTransactionRequest,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteGalleryItemRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyUpdateGalleryItemRequest {
+    pub id: String,
+    #[serde(flatten)]
+    pub body: gallery::UpdateGalleryItemRequest,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteEventRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LegacyUpdateEventRequest {
+    pub id: String,
+    #[serde(flatten)]
+    pub body: events::UpdateEventRequest,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteBlogRequest {
+    pub blog_id: String,
+}
+
+// The flattened v1 body carries the rules; its errors name fields as sent
+macro_rules! validate_body {
+    ($($request:ty),* $(,)?) => {
+        $(impl Validate for $request {
+            fn validate(&self) -> Result<(), ValidationErrors> {
+                self.body.validate()
+            }
+        })*
+    };
+}
+
+validate_body!(
+    LegacyUpdateRoleRequest,
+    LegacyImpersonateRequest,
+    LegacyUpdateProjectRequest,
+    LegacySetProjectLeadRequest,
+    LegacyAddFileRequest,
+    LegacyCreateJoinRequest,
+    LegacyCoinTransactionRequest,
+    LegacyUpdateGalleryItemRequest,
+    LegacyUpdateEventRequest,
+);
+
+// DELETE /users - Admin: delete a user
+#[utoipa::path(
+    delete, path = "/users", tag = "users", security(("bearer" = [])),
+    request_body = DeleteUserRequest,
+    responses(
+        (status = 200, body = String, example = json!("User deleted successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn delete_user(state: State<AppState>, ValidJson(payload): ValidJson<DeleteUserRequest>) -> AppResult<Json<String>> {
+    users::delete_user(state, Path(payload.user_id)).await
+}
+
+// POST /users/role - Admin: change a user's role
+#[utoipa::path(
+    post, path = "/users/role", tag = "users", security(("bearer" = [])),
+    request_body = LegacyUpdateRoleRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "User role updated successfully"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn update_user_role(state: State<AppState>, ValidJson(payload): ValidJson<LegacyUpdateRoleRequest>) -> AppResult<Json<Value>> {
+    users::update_user_role(state, Path(payload.user_id), ValidJson(payload.body)).await
+}
+
+// POST /users/approve - Admin: approve a pending account
+#[utoipa::path(
+    post, path = "/users/approve", tag = "users", security(("bearer" = [])),
+    request_body = ApproveUserRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "User approved successfully"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn approve_user(state: State<AppState>, ValidJson(payload): ValidJson<ApproveUserRequest>) -> AppResult<Json<Value>> {
+    users::approve_user(state, Path(payload.user_id)).await
+}
+
+// POST /users/force-logout - Admin: revoke every session and access token of an account
+#[utoipa::path(
+    post, path = "/users/force-logout", tag = "users", security(("bearer" = [])),
+    request_body = ForceLogoutRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "revoked": 2, "revoked_tokens": 1}))
+    )
+)]
+pub async fn force_logout_user(state: State<AppState>, ValidJson(payload): ValidJson<ForceLogoutRequest>) -> AppResult<Json<Value>> {
+    auth::sessions::force_logout_user(state, Path(payload.user_id)).await
+}
+
+// POST /users/impersonate - Admin: act as a user for support
+#[utoipa::path(
+    post, path = "/users/impersonate", tag = "users", security(("bearer" = [])),
+    request_body = LegacyImpersonateRequest,
+    responses(
+        (status = 200, description = "A short-lived access token acting as the user, without a refresh token", body = Object)
+    )
+)]
+pub async fn impersonate_user(
+    state: State<AppState>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<LegacyImpersonateRequest>,
+) -> AppResult<Json<Value>> {
+    auth::impersonation::impersonate_user(state, headers, auth_user, Path(payload.user_id), ValidJson(payload.body)).await
+}
+
+// PATCH /projects/admin - Admin: update a project
+#[utoipa::path(
+    patch, path = "/projects/admin", tag = "projects", security(("bearer" = [])),
+    request_body = LegacyUpdateProjectRequest,
+    responses(
+        (status = 200, body = String, example = json!("Project updated successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn update_project(state: State<AppState>, ValidJson(payload): ValidJson<LegacyUpdateProjectRequest>) -> AppResult<Json<String>> {
+    projects::update_project(state, Path(payload.project_id), ValidJson(payload.body)).await
+}
+
+// DELETE /projects/admin - Admin: delete a project
+#[utoipa::path(
+    delete, path = "/projects/admin", tag = "projects", security(("bearer" = [])),
+    request_body = DeleteProjectRequest,
+    responses(
+        (status = 200, body = String, example = json!("Project deleted successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn delete_project(state: State<AppState>, ValidJson(payload): ValidJson<DeleteProjectRequest>) -> AppResult<Json<String>> {
+    projects::delete_project(state, Path(payload.project_id)).await
+}
+
+// POST /projects/assign - Admin: add a member to a project
+#[utoipa::path(
+    post, path = "/projects/assign", tag = "projects", security(("bearer" = [])),
+    request_body = AssignMemberRequest,
+    responses(
+        (status = 200, body = String, example = json!("Member assigned to project successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn assign_member_to_project(state: State<AppState>, ValidJson(payload): ValidJson<AssignMemberRequest>) -> AppResult<Json<String>> {
+    projects::assign_member_to_project(state, Path((payload.project_id, payload.member_id))).await
+}
+
+// POST /projects/remove - Admin: remove a member from a project
+#[utoipa::path(
+    post, path = "/projects/remove", tag = "projects", security(("bearer" = [])),
+    request_body = AssignMemberRequest,
+    responses(
+        (status = 200, body = String, example = json!("Member removed from project successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn remove_member_from_project(
+    state: State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<AssignMemberRequest>,
+) -> AppResult<Json<String>> {
+    projects::remove_member_from_project(state, auth_user, Path((payload.project_id, payload.member_id))).await
+}
+
+// POST /projects/remove-member - Project lead or admin: remove a member from a project
+#[utoipa::path(
+    post, path = "/projects/remove-member", tag = "projects", security(("bearer" = [])),
+    request_body = AssignMemberRequest,
+    responses(
+        (status = 200, body = String, example = json!("Member removed from project successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
+pub async fn remove_member_by_lead(
+    state: State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<AssignMemberRequest>,
+) -> AppResult<Json<String>> {
+    projects::remove_member_from_project(state, auth_user, Path((payload.project_id, payload.member_id))).await
+}
+
+// POST /projects/lead - Admin: set a project's lead
+#[utoipa::path(
+    post, path = "/projects/lead", tag = "projects", security(("bearer" = [])),
+    request_body = LegacySetProjectLeadRequest,
+    responses(
+        (status = 200, body = String, example = json!("Project lead assigned successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn set_project_lead(state: State<AppState>, ValidJson(payload): ValidJson<LegacySetProjectLeadRequest>) -> AppResult<Json<String>> {
+    projects::set_project_lead(state, Path(payload.project_id), ValidJson(payload.body)).await
+}
+
+// POST /projects/files - Project lead or admin: attach a file
+#[utoipa::path(
+    post, path = "/projects/files", tag = "projects", security(("bearer" = [])),
+    request_body = LegacyAddFileRequest,
+    responses(
+        (status = 200, body = String, example = json!("File added successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
+pub async fn add_file_to_project(
+    state: State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<LegacyAddFileRequest>,
+) -> AppResult<Json<String>> {
+    projects::add_file_to_project(state, auth_user, Path(payload.project_id), ValidJson(payload.body)).await
+}
+
+// DELETE /projects/files - Project lead or admin: remove a file
+#[utoipa::path(
+    delete, path = "/projects/files", tag = "projects", security(("bearer" = [])),
+    request_body = DeleteFileRequest,
+    responses(
+        (status = 200, body = String, example = json!("File deleted successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
+pub async fn delete_file_from_project(
+    state: State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<DeleteFileRequest>,
+) -> AppResult<Json<String>> {
+    projects::delete_file_from_project(state, auth_user, Path((payload.project_id, payload.file_id))).await
+}
+
+// POST /projects/join-request - Authenticated: ask to join a project
+#[utoipa::path(
+    post, path = "/projects/join-request", tag = "join-requests", security(("bearer" = [])),
+    request_body = LegacyCreateJoinRequest,
+    responses(
+        (status = 201, body = Object, example = json!({"message": "Join request sent successfully"})),
+        (status = 400, description = "Already a member, or a request is pending", body = ErrorBody)
+    )
+)]
+pub async fn create_join_request(
+    auth_user: AuthUser,
+    state: State<AppState>,
+    ValidJson(payload): ValidJson<LegacyCreateJoinRequest>,
+) -> AppResult<(StatusCode, Json<Value>)> {
+    project_join_requests::create_join_request(auth_user, state, Path(payload.project_id), ValidJson(payload.body)).await
+}
+
+// POST /coins/manage - Admin: add or remove coins
+#[utoipa::path(
+    post, path = "/coins/manage", tag = "coins", security(("bearer" = [])),
+    request_body = LegacyCoinTransactionRequest,
+    responses(
+        (status = 200, body = String, example = json!("Coins updated successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn manage_coins(
+    state: State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<LegacyCoinTransactionRequest>,
+) -> AppResult<Json<String>> {
+    coins::manage_coins(state, auth_user, Path(payload.user_id), ValidJson(payload.body)).await
+}
+
+// PATCH /gallery/admin - Admin: update a gallery item
+#[utoipa::path(
+    patch, path = "/gallery/admin", tag = "gallery", security(("bearer" = [])),
+    request_body = LegacyUpdateGalleryItemRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Updated"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn update_gallery_item(state: State<AppState>, ValidJson(payload): ValidJson<LegacyUpdateGalleryItemRequest>) -> AppResult<Json<Value>> {
+    gallery::update_gallery_item(state, Path(payload.id), ValidJson(payload.body)).await
+}
+
+// DELETE /gallery/admin - Admin: delete a gallery item
+#[utoipa::path(
+    delete, path = "/gallery/admin", tag = "gallery", security(("bearer" = [])),
+    request_body = DeleteGalleryItemRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Deleted"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn delete_gallery_item(state: State<AppState>, ValidJson(payload): ValidJson<DeleteGalleryItemRequest>) -> AppResult<Json<Value>> {
+    gallery::delete_gallery_item(state, Path(payload.id)).await
+}
+
+// PATCH /events/admin - Admin: update an event
+#[utoipa::path(
+    patch, path = "/events/admin", tag = "events", security(("bearer" = [])),
+    request_body = LegacyUpdateEventRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Updated"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn update_event(state: State<AppState>, ValidJson(payload): ValidJson<LegacyUpdateEventRequest>) -> AppResult<Json<Value>> {
+    events::update_event(state, Path(payload.id), ValidJson(payload.body)).await
+}
+
+// DELETE /events/admin - Admin: delete an event
+#[utoipa::path(
+    delete, path = "/events/admin", tag = "events", security(("bearer" = [])),
+    request_body = DeleteEventRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Deleted"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn delete_event(state: State<AppState>, ValidJson(payload): ValidJson<DeleteEventRequest>) -> AppResult<Json<Value>> {
+    events::delete_event(state, Path(payload.id)).await
+}
+
+// POST /blogs/delete - Authenticated: author can delete own, admin can delete any
+#[utoipa::path(
+    post, path = "/blogs/delete", tag = "blogs", security(("bearer" = [])),
+    request_body = DeleteBlogRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Blog deleted"})),
+        (status = 403, description = "Not the author or a moderator", body = ErrorBody),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn delete_blog(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<DeleteBlogRequest>,
+) -> AppResult<Json<Value>> {
+    let blog_id = parse_id(&payload.blog_id, "blog ID")?;
+    let blog = found(state.blogs.get(blog_id).await?, "Blog")?;
+    blogs::remove_blog(&state, &auth_user, blog).await
+}
@@ -0,0 +1,236 @@
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use mongodb::bson::Bson;
+use std::marker::PhantomData;
+use utoipa::openapi::path::{Parameter, ParameterBuilder, ParameterIn};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::Required;
+use utoipa::IntoParams;
+
+use crate::error::{parse_id, AppError};
+use crate::repo::list::{Cursor, ListParams, Sort};
+
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 100;
+
+// What a list endpoint lets clients sort and filter on. Names are the stored
+// field names, so they go down to the repository unchanged.
+pub trait ListSpec {
+    const SORTS: &'static [&'static str];
+    const DEFAULT_SORT: Sort;
+    const FILTERS: &'static [(&'static str, Filter)];
+}
+
+// How a filter's query value becomes the stored value it must equal
+#[derive(Debug, Clone, Copy)]
+pub enum Filter {
+    Text,
+    Bool,
+    Id,
+    OneOf(&'static [&'static str]),   // Enum variants, stored by name; matched in any case like request bodies
+}
+
+// `?limit=&cursor=&sort=` plus `<field>=<value>` per filter, e.g.
+// `GET /gallery?category=Workshop&featured=true&sort=-created_at&limit=24`.
+// `sort` takes a field from the spec, `-` first for descending; `cursor` is the
+// previous page's `next_cursor`. Anything the spec doesn't name is a 400.
+#[derive(Debug)]
+pub struct ListQuery<S> {
+    pub params: ListParams,
+    spec: PhantomData<S>,
+}
+
+impl<S: ListSpec, State: Send + Sync> FromRequestParts<State> for ListQuery<S> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &State) -> Result<Self, Self::Rejection> {
+        let Query(pairs) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+            .map_err(|e| invalid("query", e.body_text()))?;
+        Ok(ListQuery { params: parse::<S>(&pairs)?, spec: PhantomData })
+    }
+}
+
+// Documents the query a spec accepts, so `params(ListQuery<GalleryList>)` in
+// `#[utoipa::path]` lists the same sorts and filters the parser allows
+impl<S: ListSpec> IntoParams for ListQuery<S> {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        let sorts = S::SORTS.iter().flat_map(|field| [field.to_string(), format!("-{}", field)]);
+        let default_sort = match S::DEFAULT_SORT {
+            Sort { field, descending: true } => format!("-{}", field),
+            Sort { field, descending: false } => field.to_string(),
+        };
+        let mut params = vec![
+            query_param(
+                "limit",
+                ObjectBuilder::new().schema_type(Type::Integer).minimum(Some(1)).maximum(Some(MAX_LIMIT as u64)),
+                format!("Page size, default {}", DEFAULT_LIMIT),
+            ),
+            query_param("cursor", ObjectBuilder::new().schema_type(Type::String), "The previous page's `next_cursor`".to_string()),
+            query_param(
+                "sort",
+                ObjectBuilder::new().schema_type(Type::String).enum_values(Some(sorts)).default(Some(default_sort.into())),
+                "Field to sort by, `-` first for descending".to_string(),
+            ),
+        ];
+        params.extend(S::FILTERS.iter().map(|&(field, filter)| {
+            let schema = match filter {
+                Filter::Text => ObjectBuilder::new().schema_type(Type::String),
+                Filter::Bool => ObjectBuilder::new().schema_type(Type::Boolean),
+                Filter::Id => ObjectBuilder::new().schema_type(Type::String).pattern(Some("^[0-9a-fA-F]{24}$")),
+                Filter::OneOf(allowed) => ObjectBuilder::new().schema_type(Type::String).enum_values(Some(allowed.iter().copied())),
+            };
+            query_param(field, schema, format!("Only items whose `{}` equals this", field))
+        }));
+        params
+    }
+}
+
+fn query_param(name: &str, schema: ObjectBuilder, description: String) -> Parameter {
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Query)
+        .required(Required::False)
+        .description(Some(description))
+        .schema(Some(schema.build()))
+        .build()
+}
+
+fn invalid(field: &str, message: String) -> AppError {
+    AppError::BadRequest(message)
+        .with_code("invalid_query")
+        .with_details(serde_json::json!({ "field": field }))
+}
+
+fn parse<S: ListSpec>(pairs: &[(String, String)]) -> Result<ListParams, AppError> {
+    let mut params = ListParams { filters: Vec::new(), sort: S::DEFAULT_SORT, after: None, limit: DEFAULT_LIMIT };
+
+    for (key, value) in pairs {
+        match key.as_str() {
+            "limit" => {
+                params.limit = value
+                    .parse()
+                    .ok()
+                    .filter(|limit| (1..=MAX_LIMIT).contains(limit))
+                    .ok_or_else(|| invalid("limit", format!("limit must be between 1 and {}", MAX_LIMIT)))?;
+            }
+            "cursor" => {
+                let cursor = Cursor::decode(value).ok_or_else(|| invalid("cursor", "Invalid cursor".to_string()))?;
+                params.after = Some(cursor);
+            }
+            "sort" => params.sort = sort::<S>(value)?,
+            key => {
+                let &(field, filter) = S::FILTERS
+                    .iter()
+                    .find(|(field, _)| *field == key)
+                    .ok_or_else(|| invalid(key, format!("Unknown query parameter '{}'", key)))?;
+                if params.filters.iter().any(|(other, _)| *other == field) {
+                    return Err(invalid(field, format!("'{}' is given more than once", field)));
+                }
+                params.filters.push((field, filter_value(field, filter, value)?));
+            }
+        }
+    }
+    Ok(params)
+}
+
+fn sort<S: ListSpec>(value: &str) -> Result<Sort, AppError> {
+    let (name, descending) = match value.strip_prefix('-') {
+        Some(name) => (name, true),
+        None => (value, false),
+    };
+    let field = S::SORTS.iter().find(|field| **field == name).ok_or_else(|| {
+        invalid("sort", format!("Can't sort by '{}'; use one of: {}", name, S::SORTS.join(", ")))
+    })?;
+    Ok(Sort { field, descending })
+}
+
+fn filter_value(field: &'static str, filter: Filter, value: &str) -> Result<Bson, AppError> {
+    match filter {
+        Filter::Text => Ok(Bson::String(value.to_string())),
+        Filter::Bool => match value {
+            "true" => Ok(Bson::Boolean(true)),
+            "false" => Ok(Bson::Boolean(false)),
+            _ => Err(invalid(field, format!("'{}' must be true or false", field))),
+        },
+        Filter::Id => Ok(Bson::ObjectId(parse_id(value, field)?)),
+        Filter::OneOf(allowed) => match allowed.iter().find(|name| name.eq_ignore_ascii_case(value)) {
+            Some(name) => Ok(Bson::String(name.to_string())),
+            None => Err(invalid(field, format!("'{}' must be one of: {}", field, allowed.join(", ")))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    struct Things;
+
+    impl ListSpec for Things {
+        const SORTS: &'static [&'static str] = &["created_at", "name"];
+        const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+        const FILTERS: &'static [(&'static str, Filter)] = &[
+            ("featured", Filter::Bool),
+            ("owner_id", Filter::Id),
+            ("status", Filter::OneOf(&["Active", "OnHold"])),
+        ];
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Result<ListParams, AppError> {
+        let pairs: Vec<(String, String)> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        parse::<Things>(&pairs)
+    }
+
+    #[test]
+    fn defaults_to_the_first_page_in_the_default_order() {
+        let params = query(&[]).unwrap();
+        assert_eq!(params.sort, Things::DEFAULT_SORT);
+        assert_eq!(params.limit, DEFAULT_LIMIT);
+        assert!(params.filters.is_empty() && params.after.is_none());
+    }
+
+    #[test]
+    fn reads_sort_limit_cursor_and_filters() {
+        let owner = ObjectId::new();
+        let cursor = Cursor { value: Bson::String("m".to_string()), id: ObjectId::new() };
+        let params = query(&[
+            ("sort", "-name"),
+            ("limit", "10"),
+            ("cursor", &cursor.encode()),
+            ("featured", "true"),
+            ("owner_id", &owner.to_hex()),
+            ("status", "onhold"),
+        ])
+        .unwrap();
+
+        assert_eq!(params.sort, Sort { field: "name", descending: true });
+        assert_eq!(params.limit, 10);
+        assert_eq!(params.after, Some(cursor));
+        assert_eq!(
+            params.filters,
+            vec![
+                ("featured", Bson::Boolean(true)),
+                ("owner_id", Bson::ObjectId(owner)),
+                ("status", Bson::String("OnHold".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_what_the_spec_does_not_allow() {
+        for pairs in [
+            vec![("sort", "password_hash")],
+            vec![("limit", "0")],
+            vec![("limit", "1000")],
+            vec![("cursor", "zz")],
+            vec![("featured", "yes")],
+            vec![("owner_id", "nope")],
+            vec![("status", "Deleted")],
+            vec![("email", "a@example.com")],
+            vec![("featured", "true"), ("featured", "false")],
+        ] {
+            assert!(query(&pairs).is_err(), "{:?} was accepted", pairs);
+        }
+    }
+}
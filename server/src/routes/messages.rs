@@ -1,86 +1,95 @@
-use axum::{extract::State, Json, Extension};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::State, Json};
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 use crate::db::AppState;
+use crate::error::{found, parse_id, AppError, AppResult, ErrorBody};
 use crate::models::{Message, MessageType};
 use crate::middleware::auth::AuthUser;
-use crate::models::user::Role;
+use crate::models::user::Permission;
+use crate::repo::list::Sort;
+use crate::repo::Page;
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{invalid_field, not_blank, not_one_of, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct SendMessageRequest {
     pub recipient_ids: Option<Vec<String>>,  // For individual messages
     pub project_id: Option<String>,          // For project team messages
+    #[validate(length(max = 200), custom(function = "not_blank"))]
     pub subject: String,
+    #[validate(length(max = 10000), custom(function = "not_blank"))]
     pub content: String,
+    #[validate(custom(function = "known_message_type"))]
     pub message_type: String,  // "individual", "project_team", or "broadcast"
 }
 
-#[derive(Deserialize)]
-pub struct GetMessagesRequest {
-    pub user_id: String,
+const MESSAGE_TYPES: &[&str] = &["individual", "project_team", "broadcast"];
+
+fn parse_message_type(s: &str) -> Result<MessageType, ValidationError> {
+    match s {
+        "individual" => Ok(MessageType::Individual),
+        "project_team" => Ok(MessageType::ProjectTeam),
+        "broadcast" => Ok(MessageType::Broadcast),
+        _ => Err(not_one_of(MESSAGE_TYPES)),
+    }
+}
+
+fn known_message_type(value: &str) -> Result<(), ValidationError> {
+    parse_message_type(value).map(drop)
 }
 
 // Send message (admin to individual, project team, or broadcast)
+#[utoipa::path(
+    post, path = "/api/v1/messages", tag = "messages", security(("bearer" = [])),
+    request_body = SendMessageRequest,
+    responses(
+        (status = 200, body = String, example = json!("Message sent successfully")),
+        (status = 403, description = "Project team and broadcast messages need messages:broadcast", body = ErrorBody)
+    )
+)]
 pub async fn send_message(
     State(state): State<AppState>,
-    Extension(user): Extension<AuthUser>,
-    Json(payload): Json<SendMessageRequest>,
-) -> Result<Json<String>, Json<String>> {
-    let sender_id = ObjectId::parse_str(&user.id).unwrap();
-    
-    // Check permissions based on message type
-    match payload.message_type.as_str() {
-        "project_team" | "broadcast" => {
-            // Only admins can send project team or broadcast messages
-            if user.role != Role::Admin {
-                return Err(Json("Only admins can send project team or broadcast messages".to_string()));
-            }
-        },
-        "individual" => {
-            // Anyone can send individual messages
-        },
-        _ => {
-            return Err(Json("Invalid message type".to_string()));
-        }
+    user: AuthUser,
+    ValidJson(payload): ValidJson<SendMessageRequest>,
+) -> AppResult<Json<String>> {
+    let sender_id = user.id;
+    let message_type = parse_message_type(&payload.message_type).map_err(|e| invalid_field("message_type", e))?;
+
+    // Anyone can send individual messages; only organisers can reach a project team or everyone
+    if !matches!(message_type, MessageType::Individual) && !user.can(Permission::MessagesBroadcast) {
+        return Err(AppError::Forbidden("Only admins can send project team or broadcast messages".to_string()));
     }
-    
-    // Determine message type and recipients
-    let (message_type, recipient_ids, project_id) = match payload.message_type.as_str() {
-        "individual" => {
+
+    // Determine recipients
+    let (recipient_ids, project_id) = match message_type {
+        MessageType::Individual => {
             // Individual message - use provided recipient_ids
-            let recipients: Vec<ObjectId> = payload.recipient_ids
+            let recipients = payload.recipient_ids
                 .unwrap_or_default()
                 .iter()
-                .filter_map(|id| ObjectId::parse_str(id).ok())
-                .collect();
-            (MessageType::Individual, Some(recipients), None)
+                .map(|id| parse_id(id, "recipient_ids"))
+                .collect::<AppResult<Vec<ObjectId>>>()?;
+            (Some(recipients), None)
         },
-        "project_team" => {
+        MessageType::ProjectTeam => {
             // Project team message - get all members from project
-            let project_id_obj = ObjectId::parse_str(payload.project_id.as_ref().unwrap()).unwrap();
-            let project = state.projects
-                .find_one(doc! { "_id": project_id_obj })
-                .await
-                .unwrap()
-                .unwrap();
+            let project_id = payload.project_id
+                .as_deref()
+                .ok_or_else(|| AppError::BadRequest("project_id is required for project team messages".to_string()))?;
+            let project_id_obj = parse_id(project_id, "project_id")?;
+            let project = found(state.projects.get(project_id_obj).await?, "Project")?;
             
             let recipients = project.member_ids.unwrap_or_default();
-            (MessageType::ProjectTeam, Some(recipients), Some(project_id_obj))
+            (Some(recipients), Some(project_id_obj))
         },
-        "broadcast" => {
+        MessageType::Broadcast => {
             // Broadcast message - get all users
-            let mut cursor = state.users.find(doc! {}).await.unwrap();
-            let mut recipients = Vec::new();
-            while let Some(user) = cursor.try_next().await.unwrap() {
-                recipients.push(user.id.unwrap());
-            }
-            (MessageType::Broadcast, Some(recipients), None)
+            let recipients = state.users.list().await?.into_iter().filter_map(|user| user.id).collect();
+            (Some(recipients), None)
         },
-        _ => {
-            return Err(Json("Invalid message type".to_string()));
-        }
     };
 
     let message = Message {
@@ -91,44 +100,59 @@ pub async fn send_message(
         subject: payload.subject,
         content: payload.content,
         message_type,
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: DateTime::now(),
         read: false,
     };
 
-    state.messages.insert_one(message).await.unwrap();
+    state.messages.insert(&message).await?;
 
     Ok(Json("Message sent successfully".to_string()))
 }
 
-// Get messages for a user
-pub async fn get_user_messages(
-    State(state): State<AppState>,
-    Json(payload): Json<GetMessagesRequest>,
-) -> Json<Vec<Message>> {
-    let user_id = ObjectId::parse_str(&payload.user_id).unwrap();
-    
-    let mut cursor = state.messages
-        .find(doc! { "recipient_ids": user_id })
-        .sort(doc! { "created_at": -1 })
-        .await
-        .unwrap();
-    
-    let mut messages = Vec::new();
-    while let Some(message) = cursor.try_next().await.unwrap() {
-        messages.push(message);
-    }
+pub struct MessageList;
 
-    Json(messages)
+impl ListSpec for MessageList {
+    const SORTS: &'static [&'static str] = &["created_at"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[
+        ("message_type", Filter::OneOf(&["Individual", "ProjectTeam", "Broadcast"])),
+        ("sender_id", Filter::Id),
+        ("project_id", Filter::Id),
+    ];
 }
 
-// Get all messages (admin)
-pub async fn get_all_messages(State(state): State<AppState>) -> Json<Vec<Message>> {
-    let mut cursor = state.messages.find(doc! {}).await.unwrap();
-    let mut messages = Vec::new();
+// A user's inbox reads newest first
+pub struct InboxList;
 
-    while let Some(message) = cursor.try_next().await.unwrap() {
-        messages.push(message);
-    }
+impl ListSpec for InboxList {
+    const SORTS: &'static [&'static str] = MessageList::SORTS;
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: true };
+    const FILTERS: &'static [(&'static str, Filter)] = MessageList::FILTERS;
+}
+
+// Get messages for the current user
+#[utoipa::path(
+    get, path = "/api/v1/users/me/messages", tag = "messages", security(("bearer" = [])),
+    params(ListQuery<InboxList>),
+    responses((status = 200, description = "Messages sent to the caller", body = Page<Message>))
+)]
+pub async fn get_user_messages(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    query: ListQuery<InboxList>,
+) -> AppResult<Json<Page<Message>>> {
+    Ok(Json(state.messages.page_for_recipient(auth_user.id, &query.params).await?))
+}
 
-    Json(messages)
+// Get all messages (admin)
+#[utoipa::path(
+    get, path = "/api/v1/messages", tag = "messages", security(("bearer" = [])),
+    params(ListQuery<MessageList>),
+    responses((status = 200, body = Page<Message>))
+)]
+pub async fn get_all_messages(
+    State(state): State<AppState>,
+    query: ListQuery<MessageList>,
+) -> AppResult<Json<Page<Message>>> {
+    Ok(Json(state.messages.page(&query.params).await?))
 }
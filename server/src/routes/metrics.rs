@@ -0,0 +1,54 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+};
+use crate::auth::sessions::hash_token;
+use crate::db::AppState;
+use crate::error::{AppError, AppResult, ErrorBody};
+use crate::middleware::auth::bearer_token;
+use crate::models::ProjectStatus;
+use crate::repo::RepoResult;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// Domain gauges are cheap counts, so they are read fresh on every scrape
+async fn refresh_domain_gauges(state: &AppState) -> RepoResult<()> {
+    let metrics = &state.metrics;
+
+    let pending = state.project_join_requests.count_pending().await?;
+    metrics.pending_join_requests.set(pending as i64);
+
+    let active = state.projects.count_by_status(ProjectStatus::Active).await?;
+    metrics.active_projects.set(active as i64);
+
+    let coins = state.users.total_coins().await?;
+    metrics.coins_in_circulation.set(coins);
+
+    Ok(())
+}
+
+// GET /metrics - Public, or bearer METRICS_TOKEN when set: Prometheus scrape target
+#[utoipa::path(
+    get, path = "/metrics", tag = "operations",
+    responses(
+        (status = 200, description = "Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "METRICS_TOKEN is set and wasn't given as a bearer token", body = ErrorBody)
+    )
+)]
+pub async fn get_metrics(State(state): State<AppState>, headers: HeaderMap) -> AppResult<impl IntoResponse> {
+    if let Some(expected) = &state.config.metrics.token {
+        // Compare digests so the check takes the same time however much of the token matches
+        let given = bearer_token(&headers).map(hash_token);
+        if given.as_deref() != Some(hash_token(expected).as_str()) {
+            return Err(AppError::Unauthorized("A valid metrics token is required".to_string()).with_code("invalid_token"));
+        }
+    }
+
+    // A failing database shouldn't hide the HTTP series; the gauges keep their last value
+    if let Err(e) = refresh_domain_gauges(&state).await {
+        tracing::warn!(error = %e, "failed to refresh domain gauges");
+    }
+
+    Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], state.metrics.render()))
+}
@@ -1,28 +1,21 @@
-use axum::{Router, routing::{get, post}, middleware};
+use axum::{Router, routing::get, middleware, http::header::LINK};
 use tower_http::cors::{CorsLayer, Any};
 
 use crate::db::AppState;
-use crate::middleware::{auth_middleware, admin_middleware};
-
-use crate::routes::users::{get_users, get_members, add_user, update_user_role, delete_user, get_user_by_id};
-use crate::routes::projects::{
-    get_all_projects, get_user_projects, create_project, 
-    assign_member_to_project, remove_member_from_project, delete_project,
-    set_project_lead, update_project, remove_member_by_lead,
-    add_file_to_project, delete_file_from_project
-};
-use crate::routes::project_join_requests::{
-    create_join_request, get_project_join_requests, update_join_request_status
-};
-use crate::routes::coins::{manage_coins, get_coin_transactions, get_weekly_leaderboard, save_weekly_leaderboard};
-use crate::routes::messages::{send_message, get_user_messages, get_all_messages};
-use crate::routes::gallery::{get_all_gallery, create_gallery_item, update_gallery_item, delete_gallery_item};
-use crate::routes::events::{get_all_events, create_event, update_event, delete_event, propose_event};
-use crate::routes::stats::get_stats;
-use crate::routes::blogs::{get_all_blogs, get_blog_by_slug, create_blog, delete_blog};
-
-use crate::auth::{github_login, github_callback, test_login};
+use crate::middleware::{rate_limit, request_id, track_metrics};
+use crate::middleware::deprecation::DEPRECATION;
+use crate::middleware::request_id::X_REQUEST_ID;
+use crate::openapi::{get_docs, get_openapi};
+use crate::rate_limit::Policies;
+use crate::routes::metrics::get_metrics;
+use crate::routes::health::{get_live, get_ready};
 
+#[utoipa::path(
+    get, path = "/", tag = "operations",
+    responses(
+        (status = 200, body = Object, example = json!({"message": "IRIS Server API", "version": "1.0.0"}))
+    )
+)]
 async fn root_handler() -> axum::Json<serde_json::Value> {
     axum::Json(serde_json::json!({
         "message": "IRIS Server API",
@@ -30,79 +23,55 @@ async fn root_handler() -> axum::Json<serde_json::Value> {
     }))
 }
 
-async fn health_handler() -> axum::Json<serde_json::Value> {
-    axum::Json(serde_json::json!({
-        "status": "healthy",
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    }))
-}
-
 pub fn create_routes(state: AppState) -> Router {
-    // Public routes
-    let public_routes = Router::new()
+    // Unversioned: probes, metrics and the API description
+    let operational_routes = Router::new()
         .route("/", get(root_handler))
-        .route("/health", get(health_handler))
-        .route("/auth/github", get(github_login))
-        .route("/auth/github/callback", get(github_callback))
-        .route("/auth/test-login", post(test_login))
-        .route("/coins/leaderboard", get(get_weekly_leaderboard))
-        .route("/projects", get(get_all_projects))
-        .route("/gallery", get(get_all_gallery))
-        .route("/events", get(get_all_events))
-        .route("/stats", get(get_stats))
-        .route("/blogs", get(get_all_blogs))
-        .route("/blogs/{slug}", get(get_blog_by_slug));
+        // `/health` predates the split and stays a liveness probe
+        .route("/health", get(get_live))
+        .route("/health/live", get(get_live))
+        .route("/health/ready", get(get_ready))
+        .route("/openapi.json", get(get_openapi))
+        .route("/docs", get(get_docs));
 
-    // Protected routes
-    let protected_routes = Router::new()
-        .route("/users/{user_id}", get(get_user_by_id))
-        .route("/projects/user", post(get_user_projects))
-        .route("/projects/join-request", post(create_join_request))
-        .route("/projects/{id}/join-requests", get(get_project_join_requests))
-        .route("/projects/join-request/{id}", axum::routing::patch(update_join_request_status))
-        .route("/projects/remove-member", post(remove_member_by_lead))
-        .route("/projects/files", post(add_file_to_project).delete(delete_file_from_project))
-        .route("/coins/transactions", post(get_coin_transactions))
-        .route("/messages/user", post(get_user_messages))
-        .route("/messages/send", post(send_message))
-        .route("/events/propose", post(propose_event))
-        .route("/blogs/create", post(create_blog))
-        .route("/blogs/delete", post(delete_blog))
-        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+    // Prometheus scrape target, optionally behind METRICS_TOKEN
+    let operational_routes = if state.config.metrics.enabled {
+        operational_routes.route("/metrics", get(get_metrics))
+    } else {
+        operational_routes
+    };
 
-    // Admin-only routes
-    let admin_routes = Router::new()
-        .route("/users", get(get_users).post(add_user).delete(delete_user))
-        .route("/members", get(get_members))
-        .route("/users/role", post(update_user_role))
-        .route("/projects/admin", post(create_project).delete(delete_project).patch(update_project))
-        .route("/projects/assign", post(assign_member_to_project))
-        .route("/projects/remove", post(remove_member_from_project))
-        .route("/projects/lead", post(set_project_lead))
-        .route("/coins/manage", post(manage_coins))
-        .route("/coins/leaderboard/save", post(save_weekly_leaderboard))
-        .route("/messages", get(get_all_messages))
-        .route("/gallery/admin", post(create_gallery_item).patch(update_gallery_item).delete(delete_gallery_item))
-        .route("/events/admin", post(create_event).patch(update_event).delete(delete_event))
-        .layer(middleware::from_fn(admin_middleware))
-        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+    // A token bucket per route group and caller: every API request per IP, with
+    // tighter ones on sign-in (per IP) and on outreach to other members (per user).
+    // Probes and scrapes aren't limited.
+    let limits = Policies::from_config(&state.config.rate_limit);
+    let api_routes = Router::new()
+        .nest("/api/v1", v1::routes(&state, &limits))
+        .merge(legacy::routes(&state, &limits))
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit(limits.general)));
 
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([X_REQUEST_ID, DEPRECATION, LINK]);
 
-    // Combine all routes
+    // Combine all routes; the pre-/api/v1 paths stay as deprecated aliases
     Router::new()
-        .merge(public_routes)
-        .merge(protected_routes)
-        .merge(admin_routes)
+        .merge(operational_routes)
+        .merge(api_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), track_metrics))
+        .layer(middleware::from_fn(request_id))
         .layer(cors)
         .with_state(state)
 }
 
+pub mod list;
+pub mod v1;
+pub mod legacy;
 pub mod users;
+pub mod audit;
 pub mod projects;
 pub mod project_join_requests;
 pub mod coins;
@@ -110,4 +79,8 @@ pub mod messages;
 pub mod gallery;
 pub mod events;
 pub mod stats;
-pub mod blogs;
\ No newline at end of file
+pub mod metrics;
+pub mod health;
+pub mod blogs;
+#[cfg(test)]
+mod tests;
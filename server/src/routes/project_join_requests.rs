@@ -2,56 +2,44 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::Json,
-    Extension,
 };
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::DateTime;
 use serde_json::{json, Value};
-use chrono::Utc;
-use std::str::FromStr;
-use futures_util::stream::TryStreamExt;
 
-use crate::models::{ProjectJoinRequest, JoinRequestStatus, CreateJoinRequest, UpdateJoinRequestStatus};
+use crate::models::{ProjectJoinRequest, JoinRequestStatus, CreateJoinRequest, UpdateJoinRequestStatus, parse_decision};
 use crate::db::AppState;
+use crate::repo::RepoError;
+use crate::error::{found, parse_id, AppError, AppResult, ErrorBody};
 use crate::middleware::auth::AuthUser;
+use crate::models::user::Permission;
+use crate::validate::{invalid_field, ValidJson};
 
 // Create join request
+#[utoipa::path(
+    post, path = "/api/v1/projects/{id}/join-requests", tag = "join-requests", security(("bearer" = [])),
+    params(("id" = String, Path, description = "Project id")),
+    request_body = CreateJoinRequest,
+    responses(
+        (status = 201, body = Object, example = json!({"message": "Join request sent successfully"})),
+        (status = 400, description = "Already a member, or a request is pending", body = ErrorBody)
+    )
+)]
 pub async fn create_join_request(
-    Extension(auth_user): Extension<AuthUser>,
+    auth_user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<CreateJoinRequest>,
-) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
-    let user_id = ObjectId::from_str(&auth_user.id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid user ID"}))))?;
+    Path(project_id): Path<String>,
+    ValidJson(payload): ValidJson<CreateJoinRequest>,
+) -> AppResult<(StatusCode, Json<Value>)> {
+    let user_id = auth_user.id;
 
-    let project_id = ObjectId::from_str(&payload.project_id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid project ID"}))))?;
+    let project_id = parse_id(&project_id, "project ID")?;
 
     // Check if project exists
-    let project = state.projects
-        .find_one(doc! {"_id": project_id})
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?
-        .ok_or((StatusCode::NOT_FOUND, Json(json!({"error": "Project not found"}))))?;
+    let project = found(state.projects.get(project_id).await?, "Project")?;
 
     // Check if user is already a member
-    if let Some(members) = &project.member_ids {
-        if members.contains(&user_id) {
-            return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "You are already a member of this project"}))));
-        }
-    }
-
-    // Check if there's already a pending request
-    let existing_request = state.project_join_requests
-        .find_one(doc! {
-            "project_id": project_id,
-            "user_id": user_id,
-            "status": "pending"
-        })
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?;
-
-    if existing_request.is_some() {
-        return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "You already have a pending request for this project"}))));
+    if project.member_ids.as_ref().is_some_and(|members| members.contains(&user_id)) {
+        return Err(AppError::BadRequest("You are already a member of this project".to_string()));
     }
 
     // Create the join request
@@ -61,61 +49,61 @@ pub async fn create_join_request(
         user_id,
         message: payload.message,
         status: JoinRequestStatus::Pending,
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
+        created_at: DateTime::now(),
+        updated_at: DateTime::now(),
     };
 
-    state.project_join_requests
-        .insert_one(&new_request)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to create join request"}))))?;
+    // One pending request per user and project, enforced by a unique index
+    match state.project_join_requests.insert(&new_request).await {
+        Ok(_) => {}
+        Err(RepoError::Duplicate) => {
+            return Err(AppError::BadRequest("You already have a pending request for this project".to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    }
 
     Ok((StatusCode::CREATED, Json(json!({"message": "Join request sent successfully"}))))
 }
 
 // Get join requests for a project (only for project lead or admin)
+#[utoipa::path(
+    get, path = "/api/v1/projects/{id}/join-requests", tag = "join-requests", security(("bearer" = [])),
+    params(("id" = String, Path, description = "Project id")),
+    responses(
+        (status = 200, description = "Pending requests, each with the requesting `user`", body = Object),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
 pub async fn get_project_join_requests(
-    Extension(auth_user): Extension<AuthUser>,
+    auth_user: AuthUser,
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ObjectId::from_str(&auth_user.id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid user ID"}))))?;
+) -> AppResult<Json<Value>> {
+    let user_id = auth_user.id;
 
-    let project_oid = ObjectId::from_str(&project_id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid project ID"}))))?;
+    let project_oid = parse_id(&project_id, "project ID")?;
 
     // Get project and verify user is project lead or admin
-    let project = state.projects
-        .find_one(doc! {"_id": project_oid})
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?
-        .ok_or((StatusCode::NOT_FOUND, Json(json!({"error": "Project not found"}))))?;
+    let project = found(state.projects.get(project_oid).await?, "Project")?;
 
     // Check if user is project lead
     let is_lead = project.project_lead_id.as_ref().map(|lead_id| lead_id == &user_id).unwrap_or(false);
     
     // Check if user is admin
-    let is_admin = matches!(auth_user.role, crate::models::Role::Admin);
+    let is_admin = auth_user.can(Permission::ProjectsManage);
 
     if !is_lead && !is_admin {
-        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "You don't have permission to view join requests for this project"}))));
+        return Err(AppError::Forbidden("You don't have permission to view join requests for this project".to_string()));
     }
 
     // Get all pending join requests for this project with user details
-    let mut cursor = state.project_join_requests
-        .find(doc! {"project_id": project_oid, "status": "pending"})
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?;
+    let pending = state.project_join_requests.list_pending(project_oid).await?;
 
     let mut requests_with_users = Vec::new();
     
-    while let Some(result) = cursor.try_next().await.map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to fetch requests"}))))? {
+    for result in pending {
         // Get user details
-        let user = state.users
-            .find_one(doc! {"_id": result.user_id})
-            .await
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?;
+        let user = state.users.get(result.user_id).await?;
 
         if let Some(user) = user {
             requests_with_users.push(json!({
@@ -124,7 +112,7 @@ pub async fn get_project_join_requests(
                 "user_id": result.user_id,
                 "message": result.message,
                 "status": result.status,
-                "created_at": result.created_at,
+                "created_at": result.created_at.try_to_rfc3339_string().ok(),
                 "user": {
                     "_id": user.id,
                     "username": user.username,
@@ -139,77 +127,47 @@ pub async fn get_project_join_requests(
 }
 
 // Update join request status (approve/reject) - only for project lead or admin
+#[utoipa::path(
+    patch, path = "/api/v1/join-requests/{id}", tag = "join-requests", security(("bearer" = [])),
+    params(("id" = String, Path, description = "Join request id")),
+    request_body = UpdateJoinRequestStatus,
+    responses(
+        (status = 200, body = Object, example = json!({"message": "Request approved successfully"})),
+        (status = 403, description = "Not the project lead", body = ErrorBody),
+        (status = 409, description = "Already approved or rejected", body = ErrorBody)
+    )
+)]
 pub async fn update_join_request_status(
-    Extension(auth_user): Extension<AuthUser>,
+    auth_user: AuthUser,
     State(state): State<AppState>,
     Path(request_id): Path<String>,
-    Json(payload): Json<UpdateJoinRequestStatus>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let user_id = ObjectId::from_str(&auth_user.id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid user ID"}))))?;
+    ValidJson(payload): ValidJson<UpdateJoinRequestStatus>,
+) -> AppResult<Json<Value>> {
+    let user_id = auth_user.id;
 
-    let request_oid = ObjectId::from_str(&request_id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid request ID"}))))?;
+    let request_oid = parse_id(&request_id, "request ID")?;
 
     // Get the join request
-    let join_request = state.project_join_requests
-        .find_one(doc! {"_id": request_oid})
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?
-        .ok_or((StatusCode::NOT_FOUND, Json(json!({"error": "Join request not found"}))))?;
+    let join_request = found(state.project_join_requests.get(request_oid).await?, "Join request")?;
 
     // Get project and verify user is project lead or admin
-    let project = state.projects
-        .find_one(doc! {"_id": join_request.project_id})
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Database error"}))))?
-        .ok_or((StatusCode::NOT_FOUND, Json(json!({"error": "Project not found"}))))?;
+    let project = found(state.projects.get(join_request.project_id).await?, "Project")?;
 
     // Check if user is project lead
     let is_lead = project.project_lead_id.as_ref().map(|lead_id| lead_id == &user_id).unwrap_or(false);
     
     // Check if user is admin
-    let is_admin = matches!(auth_user.role, crate::models::Role::Admin);
+    let is_admin = auth_user.can(Permission::ProjectsManage);
 
     if !is_lead && !is_admin {
-        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "You don't have permission to manage join requests for this project"}))));
+        return Err(AppError::Forbidden("You don't have permission to manage join requests for this project".to_string()));
     }
 
-    let new_status = match payload.status.as_str() {
-        "approved" => JoinRequestStatus::Approved,
-        "rejected" => JoinRequestStatus::Rejected,
-        _ => return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "Invalid status. Use 'approved' or 'rejected'"})))),
-    };
+    let new_status = parse_decision(&payload.status).map_err(|e| invalid_field("status", e))?;
 
-    // Update the request status
-    let now = mongodb::bson::DateTime::from_millis(Utc::now().timestamp_millis());
-    state.project_join_requests
-        .update_one(
-            doc! {"_id": request_oid},
-            doc! {"$set": {"status": payload.status.to_lowercase(), "updated_at": now}}
-        )
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to update request"}))))?;
-
-    // If approved, add user to project
-    if matches!(new_status, JoinRequestStatus::Approved) {
-        // Add user to project's member_ids
-        state.projects
-            .update_one(
-                doc! {"_id": join_request.project_id},
-                doc! {"$addToSet": {"member_ids": join_request.user_id}}
-            )
-            .await
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to add member to project"}))))?;
-
-        // Add project to user's project_ids
-        state.users
-            .update_one(
-                doc! {"_id": join_request.user_id},
-                doc! {"$addToSet": {"project_ids": join_request.project_id}}
-            )
-            .await
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Failed to update user projects"}))))?;
+    // If approved, the user joins the project in the same write
+    if !state.project_join_requests.decide(&join_request, new_status).await? {
+        return Err(AppError::Conflict("This join request has already been decided".to_string()).with_code("already_decided"));
     }
 
     Ok(Json(json!({"message": format!("Request {} successfully", payload.status)})))
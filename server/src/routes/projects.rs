@@ -1,93 +1,130 @@
-use axum::{extract::State, Json, Extension, http::StatusCode, response::IntoResponse, body::Bytes};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use axum::{extract::{Path, State}, Json};
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 use crate::db::AppState;
+use crate::error::{found, parse_id, AppError, AppResult, ErrorBody};
 use crate::models::{Project, ProjectStatus, ProjectFile};
 use crate::middleware::auth::AuthUser;
+use crate::models::user::Permission;
+use crate::repo::list::Sort;
+use crate::repo::{Page, ProjectChanges};
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::validate::{http_url, invalid_field, not_blank, not_one_of, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateProjectRequest {
+    #[validate(length(max = 100), custom(function = "not_blank"))]
     pub name: String,
+    #[validate(length(max = 5000), custom(function = "not_blank"))]
     pub description: String,
-    pub created_by: String, // ObjectId as string
-    pub status: Option<String>,
+    #[validate(custom(function = "known_project_status"))]
+    pub status: Option<String>,          // "Active" (default), "Completed" or "OnHold", any case
+    #[validate(custom(function = "http_url"))]
     pub github_link: Option<String>,
     pub project_lead_id: Option<String>, // ObjectId as string
 }
 
-#[derive(Deserialize)]
-pub struct AssignMemberRequest {
-    pub project_id: String,
-    pub member_id: String,
-}
-
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct SetProjectLeadRequest {
-    pub project_id: String,
     pub member_id: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateProjectRequest {
-    pub project_id: String,
+    #[validate(length(max = 100), custom(function = "not_blank"))]
     pub name: Option<String>,
+    #[validate(length(max = 5000), custom(function = "not_blank"))]
     pub description: Option<String>,
+    #[validate(custom(function = "known_project_status"))]
     pub status: Option<String>,
+    #[validate(custom(function = "http_url"))]
     pub github_link: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct DeleteProjectRequest {
-    pub project_id: String,
+const PROJECT_STATUSES: &[&str] = &["Active", "Completed", "OnHold"];
+
+fn parse_project_status(s: &str) -> Result<ProjectStatus, ValidationError> {
+    match s.to_lowercase().as_str() {
+        "active" => Ok(ProjectStatus::Active),
+        "completed" => Ok(ProjectStatus::Completed),
+        "onhold" => Ok(ProjectStatus::OnHold),
+        _ => Err(not_one_of(PROJECT_STATUSES)),
+    }
 }
 
-// Get all projects (admin)
-pub async fn get_all_projects(State(state): State<AppState>) -> Json<Vec<Project>> {
-    let mut cursor = state.projects.find(doc! {}).await.unwrap();
-    let mut projects = Vec::new();
+fn known_project_status(value: &str) -> Result<(), ValidationError> {
+    parse_project_status(value).map(drop)
+}
 
-    while let Some(project) = cursor.try_next().await.unwrap() {
-        projects.push(project);
+// A lead has to be a real account; a 422 on `field` otherwise
+async fn existing_user(state: &AppState, id: ObjectId, field: &str) -> AppResult<()> {
+    match state.users.get(id).await? {
+        Some(_) => Ok(()),
+        None => Err(invalid_field(field, ValidationError::new("unknown_user").with_message("must be an existing user".into()))),
     }
+}
+
+pub struct ProjectList;
 
-    Json(projects)
+impl ListSpec for ProjectList {
+    const SORTS: &'static [&'static str] = &["created_at", "updated_at", "name"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[
+        ("status", Filter::OneOf(PROJECT_STATUSES)),
+        ("project_lead_id", Filter::Id),
+    ];
 }
 
-// Get projects by user (member dashboard)
-pub async fn get_user_projects(
+// Get all projects (admin)
+#[utoipa::path(
+    get, path = "/api/v1/projects", tag = "projects",
+    params(ListQuery<ProjectList>),
+    responses((status = 200, body = Page<Project>))
+)]
+pub async fn get_all_projects(
     State(state): State<AppState>,
-    Json(user_id): Json<String>,
-) -> Json<Vec<Project>> {
-    let oid = ObjectId::parse_str(&user_id).unwrap();
-    
-    let mut cursor = state.projects
-        .find(doc! { "member_ids": oid })
-        .await
-        .unwrap();
-    
-    let mut projects = Vec::new();
-    while let Some(project) = cursor.try_next().await.unwrap() {
-        projects.push(project);
-    }
+    query: ListQuery<ProjectList>,
+) -> AppResult<Json<Page<Project>>> {
+    Ok(Json(state.projects.page(&query.params).await?))
+}
 
-    Json(projects)
+// Get the current user's projects (member dashboard)
+#[utoipa::path(
+    get, path = "/api/v1/users/me/projects", tag = "projects", security(("bearer" = [])),
+    responses((status = 200, description = "Projects the caller is a member of", body = Vec<Project>))
+)]
+pub async fn get_user_projects(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<Project>>> {
+    Ok(Json(state.projects.list_for_member(auth_user.id).await?))
 }
 
 // Create new project (admin)
+#[utoipa::path(
+    post, path = "/api/v1/projects", tag = "projects", security(("bearer" = [])),
+    request_body = CreateProjectRequest,
+    responses((status = 200, body = String, example = json!("Project created successfully")))
+)]
 pub async fn create_project(
     State(state): State<AppState>,
-    Json(payload): Json<CreateProjectRequest>,
-) -> Json<String> {
-    let status = match payload.status.as_deref() {
-        Some("completed") => ProjectStatus::Completed,
-        Some("onhold") => ProjectStatus::OnHold,
-        _ => ProjectStatus::Active,
-    };
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateProjectRequest>,
+) -> AppResult<Json<String>> {
+    let status = payload.status
+        .map(|status| parse_project_status(&status).map_err(|e| invalid_field("status", e)))
+        .transpose()?
+        .unwrap_or(ProjectStatus::Active);
 
     let project_lead_id = payload.project_lead_id
-        .and_then(|id| ObjectId::parse_str(&id).ok());
+        .map(|id| parse_id(&id, "project_lead_id"))
+        .transpose()?;
+    if let Some(lead_id) = project_lead_id {
+        existing_user(&state, lead_id, "project_lead_id").await?;
+    }
 
     let new_project = Project {
         id: None,
@@ -98,322 +135,198 @@ pub async fn create_project(
         project_lead_id,
         github_link: payload.github_link,
         files: Some(Vec::new()),
-        created_by: ObjectId::parse_str(&payload.created_by).unwrap(),
-        created_at: chrono::Utc::now().to_rfc3339(),
-        updated_at: chrono::Utc::now().to_rfc3339(),
+        created_by: auth_user.id,
+        created_at: DateTime::now(),
+        updated_at: DateTime::now(),
     };
 
-    state.projects.insert_one(new_project).await.unwrap();
-    Json("Project created successfully".to_string())
+    let project_id = state.projects.insert(&new_project).await?;
+    // The lead works on the project like any other member
+    if let Some(lead_id) = project_lead_id {
+        state.projects.add_member(project_id, lead_id).await?;
+    }
+    Ok(Json("Project created successfully".to_string()))
 }
 
 // Assign member to project (admin)
+#[utoipa::path(
+    put, path = "/api/v1/projects/{id}/members/{member_id}", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path), ("member_id" = String, Path)),
+    responses(
+        (status = 200, body = String, example = json!("Member assigned to project successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn assign_member_to_project(
     State(state): State<AppState>,
-    Json(payload): Json<AssignMemberRequest>,
-) -> Json<String> {
-    let project_id = ObjectId::parse_str(&payload.project_id).unwrap();
-    let member_id = ObjectId::parse_str(&payload.member_id).unwrap();
-
-    // Add member to project
-    state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! { 
-                "$addToSet": { "member_ids": member_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-        .unwrap();
-
-    // Add project to user
-    state.users
-        .update_one(
-            doc! { "_id": member_id },
-            doc! { 
-                "$addToSet": { "project_ids": project_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-        .unwrap();
-
-    Json("Member assigned to project successfully".to_string())
-}
+    Path((project_id, member_id)): Path<(String, String)>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    let member_id = parse_id(&member_id, "member_id")?;
 
-// Remove member from project (admin)
-pub async fn remove_member_from_project(
-    State(state): State<AppState>,
-    Json(payload): Json<AssignMemberRequest>,
-) -> Json<String> {
-    let project_id = ObjectId::parse_str(&payload.project_id).unwrap();
-    let member_id = ObjectId::parse_str(&payload.member_id).unwrap();
-
-    // Remove member from project
-    state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! { 
-                "$pull": { "member_ids": member_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-        .unwrap();
-
-    // Remove project from user
-    state.users
-        .update_one(
-            doc! { "_id": member_id },
-            doc! { 
-                "$pull": { "project_ids": project_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-        .unwrap();
-
-    Json("Member removed from project successfully".to_string())
+    state.projects.add_member(project_id, member_id).await?;
+
+    Ok(Json("Member assigned to project successfully".to_string()))
 }
 
 // Delete project (admin)
+#[utoipa::path(
+    delete, path = "/api/v1/projects/{id}", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = String, example = json!("Project deleted successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn delete_project(
     State(state): State<AppState>,
-    Json(payload): Json<DeleteProjectRequest>,
-) -> Json<String> {
-    let oid = ObjectId::parse_str(&payload.project_id).unwrap();
-    
-    // Delete project
-    state.projects.delete_one(doc! { "_id": oid }).await.unwrap();
-    
-    // Remove project from all users
-    state.users
-        .update_many(
-            doc! {},
-            doc! { "$pull": { "project_ids": oid } },
-        )
-        .await
-        .unwrap();
-
-    Json("Project deleted successfully".to_string())
+    Path(project_id): Path<String>,
+) -> AppResult<Json<String>> {
+    let oid = parse_id(&project_id, "project_id")?;
+
+    if !state.projects.delete(oid).await? {
+        return Err(AppError::NotFound("Project not found".to_string()));
+    }
+
+    Ok(Json("Project deleted successfully".to_string()))
 }
 
 // Set project lead (admin)
+#[utoipa::path(
+    put, path = "/api/v1/projects/{id}/lead", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = SetProjectLeadRequest,
+    responses(
+        (status = 200, body = String, example = json!("Project lead assigned successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn set_project_lead(
     State(state): State<AppState>,
-    Json(payload): Json<SetProjectLeadRequest>,
-) -> Json<String> {
-    let project_id = ObjectId::parse_str(&payload.project_id).unwrap();
-    let member_id = ObjectId::parse_str(&payload.member_id).unwrap();
-
-    state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! { 
-                "$set": { 
-                    "project_lead_id": member_id,
-                    "updated_at": chrono::Utc::now().to_rfc3339()
-                }
-            },
-        )
-        .await
-        .unwrap();
-
-    Json("Project lead assigned successfully".to_string())
+    Path(project_id): Path<String>,
+    ValidJson(payload): ValidJson<SetProjectLeadRequest>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    let member_id = parse_id(&payload.member_id, "member_id")?;
+    existing_user(&state, member_id, "member_id").await?;
+
+    if !state.projects.set_lead(project_id, member_id).await? {
+        return Err(AppError::NotFound("Project not found".to_string()));
+    }
+    // The lead works on the project like any other member
+    state.projects.add_member(project_id, member_id).await?;
+
+    Ok(Json("Project lead assigned successfully".to_string()))
 }
 
 // Update project (admin)
+#[utoipa::path(
+    patch, path = "/api/v1/projects/{id}", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = UpdateProjectRequest,
+    responses(
+        (status = 200, body = String, example = json!("Project updated successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn update_project(
     State(state): State<AppState>,
-    Json(payload): Json<UpdateProjectRequest>,
-) -> Json<String> {
-    let project_id = ObjectId::parse_str(&payload.project_id).unwrap();
-    let mut update_doc = doc! {};
+    Path(project_id): Path<String>,
+    ValidJson(payload): ValidJson<UpdateProjectRequest>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    let changes = ProjectChanges {
+        name: payload.name,
+        description: payload.description,
+        status: payload.status
+            .map(|status| parse_project_status(&status).map_err(|e| invalid_field("status", e)))
+            .transpose()?,
+        github_link: payload.github_link,
+    };
 
-    if let Some(name) = payload.name {
-        update_doc.insert("name", name);
-    }
-    if let Some(description) = payload.description {
-        update_doc.insert("description", description);
-    }
-    if let Some(status) = payload.status {
-        let status_str = match status.as_str() {
-            "completed" => "Completed",
-            "onhold" => "OnHold",
-            _ => "Active",
-        };
-        update_doc.insert("status", status_str);
-    }
-    if let Some(github_link) = payload.github_link {
-        update_doc.insert("github_link", github_link);
+    if !state.projects.update(project_id, &changes).await? {
+        return Err(AppError::NotFound("Project not found".to_string()));
     }
-    update_doc.insert("updated_at", chrono::Utc::now().to_rfc3339());
 
-    state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! { "$set": update_doc },
-        )
-        .await
-        .unwrap();
-
-    Json("Project updated successfully".to_string())
+    Ok(Json("Project updated successfully".to_string()))
 }
 
-// Remove member from project (project lead or admin)
-pub async fn remove_member_by_lead(
-    State(state): State<AppState>,
-    Extension(auth_user): Extension<AuthUser>,
-    body: Bytes,
-) -> impl IntoResponse {
-    println!("=== Remove Member Request ===");
-    println!("Auth User: {:?}", auth_user);
-    println!("Raw body: {:?}", String::from_utf8_lossy(&body));
-    
-    let payload: AssignMemberRequest = match serde_json::from_slice(&body) {
-        Ok(p) => p,
-        Err(e) => {
-            println!("Failed to parse JSON: {:?}", e);
-            return (StatusCode::BAD_REQUEST, Json(format!("Invalid JSON: {}", e))).into_response();
-        }
-    };
-    
-    println!("Parsed payload: project_id={}, member_id={}", payload.project_id, payload.member_id);
-    
-    let project_id = match ObjectId::parse_str(&payload.project_id) {
-        Ok(id) => id,
-        Err(e) => {
-            println!("Error parsing project_id: {:?}", e);
-            return (StatusCode::BAD_REQUEST, Json("Invalid project ID".to_string())).into_response();
-        }
-    };
-    
-    let member_id = match ObjectId::parse_str(&payload.member_id) {
-        Ok(id) => id,
-        Err(e) => {
-            println!("Error parsing member_id: {:?}", e);
-            return (StatusCode::BAD_REQUEST, Json("Invalid member ID".to_string())).into_response();
-        }
-    };
-    
-    let auth_user_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(id) => id,
-        Err(e) => {
-            println!("Error parsing auth_user_id: {:?}", e);
-            return (StatusCode::BAD_REQUEST, Json("Invalid user ID".to_string())).into_response();
-        }
-    };
-
-    println!("Parsed IDs - Project: {:?}, Member: {:?}, AuthUser: {:?}", project_id, member_id, auth_user_id);
-
-    // Get the project to check if user is the project lead
-    let project = match state.projects.find_one(doc! { "_id": project_id }).await {
-        Ok(Some(p)) => {
-            println!("Found project: {:?}", p.name);
-            p
-        },
-        Ok(None) => {
-            println!("Project not found");
-            return (StatusCode::NOT_FOUND, Json("Project not found".to_string())).into_response();
-        },
-        Err(e) => {
-            println!("Database error: {:?}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json("Database error".to_string())).into_response();
-        }
-    };
-
-    // Check if user is admin or project lead
-    let is_admin = auth_user.role == crate::models::user::Role::Admin;
-    let is_project_lead = project.project_lead_id == Some(auth_user_id);
-
-    println!("Is admin: {}, Is project lead: {}", is_admin, is_project_lead);
-    println!("Project lead ID: {:?}", project.project_lead_id);
+// Load a project and check the caller may manage it: its lead, or anyone with projects:manage
+async fn project_for_lead(state: &AppState, auth_user: &AuthUser, project_id: ObjectId, action: &str) -> AppResult<Project> {
+    let project = found(state.projects.get(project_id).await?, "Project")?;
 
+    let is_admin = auth_user.can(Permission::ProjectsManage);
+    let is_project_lead = project.project_lead_id == Some(auth_user.id);
     if !is_admin && !is_project_lead {
-        println!("Permission denied");
-        return (StatusCode::FORBIDDEN, Json("Only project lead or admin can remove members".to_string())).into_response();
+        return Err(AppError::Forbidden(format!("Only project lead or admin can {}", action)));
     }
 
-    // Remove member from project
-    if let Err(e) = state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! { 
-                "$pull": { "member_ids": member_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-    {
-        println!("Failed to update project: {:?}", e);
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json("Failed to update project".to_string())).into_response();
-    }
+    Ok(project)
+}
 
-    // Remove project from user
-    if let Err(e) = state.users
-        .update_one(
-            doc! { "_id": member_id },
-            doc! { 
-                "$pull": { "project_ids": project_id },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-    {
-        println!("Failed to update user: {:?}", e);
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json("Failed to update user".to_string())).into_response();
-    }
+// Remove member from project (project lead or admin)
+#[utoipa::path(
+    delete, path = "/api/v1/projects/{id}/members/{member_id}", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path), ("member_id" = String, Path)),
+    responses(
+        (status = 200, body = String, example = json!("Member removed from project successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn remove_member_from_project(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((project_id, member_id)): Path<(String, String)>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    let member_id = parse_id(&member_id, "member_id")?;
 
-    println!("Member removed successfully");
-    (StatusCode::OK, Json("Member removed from project successfully".to_string())).into_response()
+    project_for_lead(&state, &auth_user, project_id, "remove members").await?;
+    state.projects.remove_member(project_id, member_id).await?;
+
+    Ok(Json("Member removed from project successfully".to_string()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct AddFileRequest {
-    pub project_id: String,
+    #[validate(length(max = 255), custom(function = "not_blank"))]
     pub name: String,
+    #[validate(custom(function = "http_url"))]
     pub url: String,
-    pub file_type: String,
-    pub size: i64,
+    #[validate(custom(function = "known_file_type"))]
+    pub file_type: String, // "stl" or "dxf"
+    #[validate(range(min = 0))]
+    pub size: i64,         // Bytes
 }
 
-#[derive(Deserialize)]
-pub struct DeleteFileRequest {
-    pub project_id: String,
-    pub file_id: String,
+const FILE_TYPES: &[&str] = &["stl", "dxf"];
+
+fn known_file_type(value: &str) -> Result<(), ValidationError> {
+    if !FILE_TYPES.contains(&value) {
+        return Err(not_one_of(FILE_TYPES));
+    }
+    Ok(())
 }
 
 // Add file to project (project lead only)
+#[utoipa::path(
+    post, path = "/api/v1/projects/{id}/files", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = AddFileRequest,
+    responses(
+        (status = 200, body = String, example = json!("File added successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
 pub async fn add_file_to_project(
     State(state): State<AppState>,
-    Extension(auth_user): Extension<AuthUser>,
-    Json(payload): Json<AddFileRequest>,
-) -> impl IntoResponse {
-    let project_id = match ObjectId::parse_str(&payload.project_id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid project ID".to_string())).into_response(),
-    };
-
-    let auth_user_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid user ID".to_string())).into_response(),
-    };
-
-    // Get the project to check if user is the project lead
-    let project = match state.projects.find_one(doc! { "_id": project_id }).await {
-        Ok(Some(p)) => p,
-        Ok(None) => return (StatusCode::NOT_FOUND, Json("Project not found".to_string())).into_response(),
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, Json("Database error".to_string())).into_response(),
-    };
-
-    // Check if user is admin or project lead
-    let is_admin = auth_user.role == crate::models::user::Role::Admin;
-    let is_project_lead = project.project_lead_id == Some(auth_user_id);
-
-    if !is_admin && !is_project_lead {
-        return (StatusCode::FORBIDDEN, Json("Only project lead or admin can upload files".to_string())).into_response();
-    }
+    auth_user: AuthUser,
+    Path(project_id): Path<String>,
+    ValidJson(payload): ValidJson<AddFileRequest>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    project_for_lead(&state, &auth_user, project_id, "upload files").await?;
 
     // Create new file entry
     let new_file = ProjectFile {
@@ -422,76 +335,34 @@ pub async fn add_file_to_project(
         url: payload.url,
         file_type: payload.file_type,
         size: payload.size,
-        uploaded_by: auth_user_id,
-        uploaded_at: chrono::Utc::now().to_rfc3339(),
+        uploaded_by: auth_user.id,
+        uploaded_at: DateTime::now(),
     };
 
-    // Add file to project
-    if let Err(_) = state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! {
-                "$push": { "files": mongodb::bson::to_document(&new_file).unwrap() },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-    {
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json("Failed to add file".to_string())).into_response();
-    }
+    state.projects.add_file(project_id, &new_file).await?;
 
-    (StatusCode::OK, Json("File added successfully".to_string())).into_response()
+    Ok(Json("File added successfully".to_string()))
 }
 
 // Delete file from project (project lead only)
+#[utoipa::path(
+    delete, path = "/api/v1/projects/{id}/files/{file_id}", tag = "projects", security(("bearer" = [])),
+    params(("id" = String, Path), ("file_id" = String, Path)),
+    responses(
+        (status = 200, body = String, example = json!("File deleted successfully")),
+        (status = 403, description = "Not the project lead", body = ErrorBody)
+    )
+)]
 pub async fn delete_file_from_project(
     State(state): State<AppState>,
-    Extension(auth_user): Extension<AuthUser>,
-    Json(payload): Json<DeleteFileRequest>,
-) -> impl IntoResponse {
-    let project_id = match ObjectId::parse_str(&payload.project_id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid project ID".to_string())).into_response(),
-    };
-
-    let file_id = match ObjectId::parse_str(&payload.file_id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid file ID".to_string())).into_response(),
-    };
+    auth_user: AuthUser,
+    Path((project_id, file_id)): Path<(String, String)>,
+) -> AppResult<Json<String>> {
+    let project_id = parse_id(&project_id, "project_id")?;
+    let file_id = parse_id(&file_id, "file_id")?;
+    project_for_lead(&state, &auth_user, project_id, "delete files").await?;
 
-    let auth_user_id = match ObjectId::parse_str(&auth_user.id) {
-        Ok(id) => id,
-        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid user ID".to_string())).into_response(),
-    };
-
-    // Get the project to check if user is the project lead
-    let project = match state.projects.find_one(doc! { "_id": project_id }).await {
-        Ok(Some(p)) => p,
-        Ok(None) => return (StatusCode::NOT_FOUND, Json("Project not found".to_string())).into_response(),
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, Json("Database error".to_string())).into_response(),
-    };
-
-    // Check if user is admin or project lead
-    let is_admin = auth_user.role == crate::models::user::Role::Admin;
-    let is_project_lead = project.project_lead_id == Some(auth_user_id);
-
-    if !is_admin && !is_project_lead {
-        return (StatusCode::FORBIDDEN, Json("Only project lead or admin can delete files".to_string())).into_response();
-    }
-
-    // Remove file from project
-    if let Err(_) = state.projects
-        .update_one(
-            doc! { "_id": project_id },
-            doc! {
-                "$pull": { "files": { "_id": file_id } },
-                "$set": { "updated_at": chrono::Utc::now().to_rfc3339() }
-            },
-        )
-        .await
-    {
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json("Failed to delete file".to_string())).into_response();
-    }
+    state.projects.remove_file(project_id, file_id).await?;
 
-    (StatusCode::OK, Json("File deleted successfully".to_string())).into_response()
+    Ok(Json("File deleted successfully".to_string()))
 }
@@ -1,23 +1,29 @@
 use axum::{extract::State, Json};
-use mongodb::bson::doc;
-
 use crate::db::AppState;
+use crate::error::AppResult;
+use crate::models::EventType;
 
-// GET /stats - Public: get dynamic counts for the homepage
-pub async fn get_stats(State(state): State<AppState>) -> Json<serde_json::Value> {
-    let members_count = state.users.count_documents(doc! {}).await.unwrap_or(0);
-    let projects_count = state.projects.count_documents(doc! {}).await.unwrap_or(0);
-    let events_count = state.events.count_documents(doc! {}).await.unwrap_or(0);
-    let gallery_count = state.gallery.count_documents(doc! {}).await.unwrap_or(0);
+// GET /api/v1/stats - Public: get dynamic counts for the homepage
+#[utoipa::path(
+    get, path = "/api/v1/stats", tag = "stats",
+    responses(
+        (status = 200, body = Object, example = json!({"members": 42, "projects": 7, "events": 12, "gallery_photos": 80, "workshops": 5}))
+    )
+)]
+pub async fn get_stats(State(state): State<AppState>) -> AppResult<Json<serde_json::Value>> {
+    let members_count = state.users.count().await?;
+    let projects_count = state.projects.count().await?;
+    let events_count = state.events.count().await?;
+    let gallery_count = state.gallery.count().await?;
 
-    // Count workshops specifically (event_type = "Workshop")
-    let workshops_count = state.events.count_documents(doc! {"event_type": "Workshop"}).await.unwrap_or(0);
+    // Count workshops specifically
+    let workshops_count = state.events.count_by_type(EventType::Workshop).await?;
 
-    Json(serde_json::json!({
+    Ok(Json(serde_json::json!({
         "members": members_count,
         "projects": projects_count,
         "events": events_count,
         "gallery_photos": gallery_count,
         "workshops": workshops_count,
-    }))
+    })))
 }
@@ -0,0 +1,779 @@
+// The real router from `create_routes`, end to end against `MemoryStore`
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use super::create_routes;
+use crate::auth::consume_oauth_state;
+use crate::auth::password::hash_password;
+use crate::auth::sessions::{create_session, hash_token};
+use crate::config::{Config, RateLimitPolicy};
+use crate::db::{self, AppState};
+use crate::models::{
+    AccountStatus, LinkedIdentity, Event, EventStatus, EventType, JoinRequestStatus, OAuthState, PasswordReset, Project,
+    ProjectStatus, Role, TwoFactor, User,
+};
+use crate::repo::memory::MemoryStore;
+use crate::models::GalleryItem;
+use crate::repo::{
+    EventRepository, GalleryRepository, JoinRequestRepository, OAuthStateRepository, PasswordResetRepository,
+    ProjectRepository, UserRepository,
+};
+
+struct TestApp {
+    router: Router,
+    state: AppState,
+    store: Arc<MemoryStore>,
+}
+
+impl TestApp {
+    fn new() -> TestApp {
+        TestApp::with_config(Config::default())
+    }
+
+    fn with_config(config: Config) -> TestApp {
+        let (state, store) = db::in_memory(config);
+        TestApp { router: create_routes(state.clone()), state, store }
+    }
+
+    async fn user(&self, username: &str, role: Role) -> User {
+        let mut user = TestApp::account(username, role);
+        user.id = Some(UserRepository::insert(&*self.store, &user).await.unwrap());
+        user
+    }
+
+    // An active account, not yet stored
+    fn account(username: &str, role: Role) -> User {
+        let now = DateTime::now();
+        User {
+            id: None,
+            username: username.to_string(),
+            full_name: username.to_string(),
+            email: format!("{}@example.com", username),
+            password_hash: String::new(),
+            role,
+            status: AccountStatus::Active,
+            coins: 0,
+            project_ids: Some(Vec::new()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            linked_identities: Vec::new(),
+            legacy_github_unresolved: false,
+            two_factor: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    async fn token(&self, user: &User) -> String {
+        create_session(&self.state, user, None, false).await.unwrap().token
+    }
+
+    async fn project(&self, name: &str, created_by: ObjectId) -> ObjectId {
+        let now = DateTime::now();
+        let project = Project {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            status: ProjectStatus::Active,
+            member_ids: Some(Vec::new()),
+            project_lead_id: None,
+            github_link: None,
+            files: Some(Vec::new()),
+            created_by,
+            created_at: now,
+            updated_at: now,
+        };
+        ProjectRepository::insert(&*self.store, &project).await.unwrap()
+    }
+
+    async fn send(&self, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+        let response = self.request(method, uri, token, body).await;
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    async fn request(&self, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => request.body(Body::empty()).unwrap(),
+        };
+
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+}
+
+#[tokio::test]
+async fn public_reads_need_no_token() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let now = DateTime::now();
+    let workshop = Event {
+        id: None,
+        title: "Soldering 101".to_string(),
+        starts_at: now,
+        ends_at: None,
+        timezone: "UTC".to_string(),
+        location: "Lab".to_string(),
+        event_type: EventType::Workshop,
+        status: EventStatus::Upcoming,
+        description: String::new(),
+        image: None,
+        featured: false,
+        register_link: None,
+        recap_link: None,
+        speakers: None,
+        created_by: admin.id.unwrap(),
+        created_at: now,
+        updated_at: now,
+    };
+    EventRepository::insert(&*app.store, &workshop).await.unwrap();
+
+    let (status, events) = app.send(Method::GET, "/api/v1/events", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(events["total"], 1);
+    assert_eq!(events["items"][0]["title"], "Soldering 101");
+
+    let (status, stats) = app.send(Method::GET, "/api/v1/stats", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["members"], 1);
+    assert_eq!(stats["events"], 1);
+    assert_eq!(stats["workshops"], 1);
+}
+
+#[tokio::test]
+async fn protected_routes_need_a_live_session() {
+    let app = TestApp::new();
+    let member = app.user("member", Role::Member).await;
+
+    let (status, body) = app.send(Method::GET, "/api/v1/users/me/projects", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "missing_token");
+
+    let token = app.token(&member).await;
+    let (status, _) = app.send(Method::GET, "/api/v1/users/me/projects", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    app.state.sessions.revoke_all(member.id.unwrap()).await.unwrap();
+    let (status, body) = app.send(Method::GET, "/api/v1/users/me/projects", Some(&token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "session_revoked");
+}
+
+#[tokio::test]
+async fn admin_routes_check_permissions() {
+    let app = TestApp::new();
+    let member = app.user("member", Role::Member).await;
+    let treasurer = app.user("treasurer", Role::Treasurer).await;
+    let uri = format!("/api/v1/users/{}/coin-transactions", member.id.unwrap().to_hex());
+    let grant = json!({ "amount": 5, "reason": "Workshop" });
+
+    let token = app.token(&member).await;
+    let (status, _) = app.send(Method::POST, &uri, Some(&token), Some(grant.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Treasurers may grant coins but not manage projects
+    let token = app.token(&treasurer).await;
+    let (status, _) = app.send(Method::POST, &uri, Some(&token), Some(grant)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::POST, "/api/v1/projects", Some(&token), Some(json!({ "name": "Rover", "description": "" }))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn assigning_a_member_updates_project_and_user() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap();
+    let token = app.token(&admin).await;
+
+    let (status, _) = app
+        .send(Method::POST, "/api/v1/projects", Some(&token), Some(json!({ "name": "Rover", "description": "Mars rover" })))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, projects) = app.send(Method::GET, "/api/v1/projects", None, None).await;
+    let project_id = ObjectId::parse_str(projects["items"][0]["_id"]["$oid"].as_str().unwrap()).unwrap();
+
+    let member_uri = format!("/api/v1/projects/{}/members/{}", project_id.to_hex(), member_id.to_hex());
+    let (status, _) = app.send(Method::PUT, &member_uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let project = ProjectRepository::get(&*app.store, project_id).await.unwrap().unwrap();
+    assert_eq!(project.member_ids, Some(vec![member_id]));
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!(user.project_ids, Some(vec![project_id]));
+
+    let unknown = format!("/api/v1/projects/{}/members/{}", project_id.to_hex(), ObjectId::new().to_hex());
+    let (status, body) = app.send(Method::PUT, &unknown, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "User not found");
+
+    let (status, _) = app.send(Method::DELETE, &member_uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!(user.project_ids, Some(vec![]));
+}
+
+#[tokio::test]
+async fn coin_grants_move_the_balance_and_the_ledger_together() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap();
+    let token = app.token(&admin).await;
+
+    let uri = format!("/api/v1/users/{}/coin-transactions", member_id.to_hex());
+    for amount in [25, -5] {
+        let grant = json!({ "amount": amount, "reason": "Workshop" });
+        let (status, _) = app.send(Method::POST, &uri, Some(&token), Some(grant)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!(user.coins, 20);
+
+    let member_token = app.token(&member).await;
+    let (status, ledger) = app.send(Method::GET, "/api/v1/users/me/coin-transactions?sort=amount", Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ledger["total"], 2);
+    assert_eq!(ledger["items"][0]["amount"], -5);
+
+    // Nothing is written for a user that doesn't exist
+    let uri = format!("/api/v1/users/{}/coin-transactions", ObjectId::new().to_hex());
+    let grant = json!({ "amount": 10, "reason": "Typo" });
+    let (status, _) = app.send(Method::POST, &uri, Some(&token), Some(grant)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn approving_a_join_request_adds_the_member() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap();
+    let project_id = app.project("Rover", admin.id.unwrap()).await;
+    let member_token = app.token(&member).await;
+    let admin_token = app.token(&admin).await;
+
+    let uri = format!("/api/v1/projects/{}/join-requests", project_id.to_hex());
+    let request = json!({ "message": "I can solder" });
+    let (status, _) = app.send(Method::POST, &uri, Some(&member_token), Some(request.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.send(Method::POST, &uri, Some(&member_token), Some(request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only the lead or a project admin may see and decide requests
+    let (status, _) = app.send(Method::GET, &uri, Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, pending) = app.send(Method::GET, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pending[0]["user"]["username"], "member");
+
+    let request_id = app.store.list_pending(project_id).await.unwrap()[0].id.unwrap();
+    let uri = format!("/api/v1/join-requests/{}", request_id.to_hex());
+    let (status, _) = app.send(Method::PATCH, &uri, Some(&admin_token), Some(json!({ "status": "approved" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let decided = JoinRequestRepository::get(&*app.store, request_id).await.unwrap().unwrap();
+    assert_eq!(decided.status, JoinRequestStatus::Approved);
+    let (status, projects) = app.send(Method::GET, "/api/v1/users/me/projects", Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(projects[0]["name"], "Rover");
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!(user.project_ids, Some(vec![project_id]));
+}
+
+#[tokio::test]
+async fn a_join_request_is_decided_once() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let project_id = app.project("Rover", admin.id.unwrap()).await;
+    let admin_token = app.token(&admin).await;
+    let uri = format!("/api/v1/projects/{}/join-requests", project_id.to_hex());
+    let (status, _) = app.send(Method::POST, &uri, Some(&app.token(&member).await), Some(json!({ "message": "I can solder" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // An approval that read the request before the rejection landed changes nothing
+    let stale = app.store.list_pending(project_id).await.unwrap().remove(0);
+    let uri = format!("/api/v1/join-requests/{}", stale.id.unwrap().to_hex());
+    let (status, _) = app.send(Method::PATCH, &uri, Some(&admin_token), Some(json!({ "status": "rejected" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!app.store.decide(&stale, JoinRequestStatus::Approved).await.unwrap());
+    let (status, body) = app.send(Method::PATCH, &uri, Some(&admin_token), Some(json!({ "status": "approved" }))).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::CONFLICT, Some("already_decided")));
+
+    let decided = JoinRequestRepository::get(&*app.store, stale.id.unwrap()).await.unwrap().unwrap();
+    assert_eq!(decided.status, JoinRequestStatus::Rejected);
+    let project = ProjectRepository::get(&*app.store, project_id).await.unwrap().unwrap();
+    assert_eq!(project.member_ids, Some(vec![]));
+}
+
+#[tokio::test]
+async fn lists_page_with_cursors_and_filter() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    for (index, category) in ["Workshop", "Team", "Workshop", "Workshop", "Team"].iter().enumerate() {
+        let item = GalleryItem {
+            id: None,
+            title: format!("Photo {}", index),
+            category: category.to_string(),
+            image_url: format!("https://img.example.com/{}.jpg", index),
+            description: String::new(),
+            thumbnail_url: None,
+            uploaded_by: admin.id.unwrap(),
+            featured: index == 0,
+            created_at: DateTime::from_millis(1_700_000_000_000 + index as i64 * 1000),
+        };
+        GalleryRepository::insert(&*app.store, &item).await.unwrap();
+    }
+
+    // Newest first, two at a time, until the cursor runs out
+    let mut titles = Vec::new();
+    let mut uri = "/api/v1/gallery?sort=-created_at&limit=2".to_string();
+    loop {
+        let (status, page) = app.send(Method::GET, &uri, None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total"], 5);
+        titles.extend(page["items"].as_array().unwrap().iter().map(|item| item["title"].as_str().unwrap().to_string()));
+        match page["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/api/v1/gallery?sort=-created_at&limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(titles, ["Photo 4", "Photo 3", "Photo 2", "Photo 1", "Photo 0"]);
+
+    let (_, workshops) = app.send(Method::GET, "/api/v1/gallery?category=Workshop&limit=2", None, None).await;
+    assert_eq!(workshops["total"], 3);
+    assert_eq!(workshops["items"][0]["title"], "Photo 0");
+    assert!(workshops["next_cursor"].is_string());
+
+    let (_, featured) = app.send(Method::GET, "/api/v1/gallery?featured=true", None, None).await;
+    assert_eq!(featured["total"], 1);
+    assert!(featured["next_cursor"].is_null());
+
+    let (status, body) = app.send(Method::GET, "/api/v1/gallery?uploaded_by=someone", None, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_query");
+    assert_eq!(body["details"]["field"], "uploaded_by");
+}
+
+#[tokio::test]
+async fn methods_on_one_path_keep_their_own_permissions() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let other = app.user("other", Role::Member).await;
+    let member_token = app.token(&member).await;
+    let uri = format!("/api/v1/users/{}", other.id.unwrap().to_hex());
+
+    // Any member may look a user up, only user admins may delete one
+    let (status, body) = app.send(Method::GET, &uri, Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["username"], "other");
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let admin_token = app.token(&admin).await;
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/api/v1/projects/{}", ObjectId::new().to_hex());
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Any member may send a message, only organisers may read everyone's
+    let message = json!({
+        "recipient_ids": [admin.id.unwrap().to_hex()],
+        "subject": "Hi",
+        "content": "Hello",
+        "message_type": "individual"
+    });
+    let (status, _) = app.send(Method::POST, "/api/v1/messages", Some(&member_token), Some(message)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::GET, "/api/v1/messages", Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn legacy_routes_still_work_and_say_they_are_deprecated() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap();
+    let project_id = app.project("Rover", admin.id.unwrap()).await;
+    let token = app.token(&admin).await;
+
+    // Ids in the body reach the same handlers as ids in the path
+    let assign = json!({ "project_id": project_id.to_hex(), "member_id": member_id.to_hex() });
+    let (status, _) = app.send(Method::POST, "/projects/assign", Some(&token), Some(assign.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let project = ProjectRepository::get(&*app.store, project_id).await.unwrap().unwrap();
+    assert_eq!(project.member_ids, Some(vec![member_id]));
+    let (status, _) = app.send(Method::POST, "/projects/remove", Some(&token), Some(assign)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let grant = json!({ "user_id": member_id.to_hex(), "amount": 5, "reason": "Workshop" });
+    let (status, _) = app.send(Method::POST, "/coins/manage", Some(&token), Some(grant)).await;
+    assert_eq!(status, StatusCode::OK);
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!(user.coins, 5);
+
+    // Errors are marked too; the versioned routes are not
+    let response = app.request(Method::GET, "/projects/user", None, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["deprecation"], "@1792281600");
+    assert_eq!(response.headers()[header::LINK], "</docs>; rel=\"deprecation\"; type=\"text/html\"");
+    let response = app.request(Method::GET, "/projects", None, None).await;
+    assert!(response.headers().contains_key("deprecation"));
+    let response = app.request(Method::GET, "/api/v1/projects", None, None).await;
+    assert!(!response.headers().contains_key("deprecation"));
+}
+
+#[tokio::test]
+async fn invalid_bodies_are_rejected_field_by_field() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap();
+    let project_id = app.project("Rover", admin.id.unwrap()).await;
+    let token = app.token(&admin).await;
+
+    let project = json!({ "name": "  ", "description": "Arm", "status": "archived", "github_link": "javascript:alert(1)" });
+    let (status, body) = app.send(Method::POST, "/api/v1/projects", Some(&token), Some(project)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"]["fields"]["name"], json!(["must not be blank"]));
+    assert_eq!(body["details"]["fields"]["status"], json!(["must be one of: Active, Completed, OnHold"]));
+    assert_eq!(body["details"]["fields"]["github_link"], json!(["must be an http or https URL"]));
+    let project = json!({ "name": "Arm", "description": "Arm", "project_lead_id": ObjectId::new().to_hex() });
+    let (status, body) = app.send(Method::POST, "/api/v1/projects", Some(&token), Some(project)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"]["project_lead_id"], json!(["must be an existing user"]));
+    let (_, page) = app.send(Method::GET, "/api/v1/projects", None, None).await;
+    assert_eq!(page["total"], 1);
+
+    // A lead who exists starts out as a member
+    let project = json!({ "name": "Arm", "description": "Arm", "status": "onhold", "project_lead_id": member_id.to_hex() });
+    let (status, _) = app.send(Method::POST, "/api/v1/projects", Some(&token), Some(project)).await;
+    assert_eq!(status, StatusCode::OK);
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    let arm = ProjectRepository::get(&*app.store, user.project_ids.unwrap()[0]).await.unwrap().unwrap();
+    assert_eq!((arm.project_lead_id, arm.member_ids), (Some(member_id), Some(vec![member_id])));
+    let uri = format!("/api/v1/projects/{}/lead", project_id.to_hex());
+    let (status, body) = app.send(Method::PUT, &uri, Some(&token), Some(json!({ "member_id": ObjectId::new().to_hex() }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"]["member_id"], json!(["must be an existing user"]));
+    let (status, _) = app.send(Method::PUT, &uri, Some(&token), Some(json!({ "member_id": member_id.to_hex() }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let rover = ProjectRepository::get(&*app.store, project_id).await.unwrap().unwrap();
+    assert_eq!((rover.project_lead_id, rover.member_ids), (Some(member_id), Some(vec![member_id])));
+    // Enum names match in any case, in bodies and in list filters alike
+    assert_eq!(arm.status, ProjectStatus::OnHold);
+    let (_, page) = app.send(Method::GET, "/api/v1/projects?status=ONHOLD", None, None).await;
+    assert_eq!(page["total"], 1);
+
+    // Unknown enum values are refused, not mapped to a default
+    let role = json!({ "role": "Owner" });
+    let uri = format!("/api/v1/users/{}/role", member_id.to_hex());
+    let (status, body) = app.send(Method::PUT, &uri, Some(&token), Some(role)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["fields"]["role"].is_array());
+    let event = json!({
+        "title": "Launch party", "starts_at": "2030-01-01T18:00:00Z", "location": "Lab",
+        "event_type": "Party", "status": "Upcoming", "description": "Cake"
+    });
+    let (status, body) = app.send(Method::POST, "/api/v1/events", Some(&token), Some(event)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"].as_object().unwrap().keys().collect::<Vec<_>>(), ["event_type"]);
+    let event = json!({
+        "title": "Launch party", "starts_at": "2030-01-01T18:00", "ends_at": "2030-01-01T17:00", "location": "Lab",
+        "event_type": "Meetup", "status": "Upcoming", "description": "Cake"
+    });
+    let (status, body) = app.send(Method::POST, "/api/v1/events", Some(&token), Some(event)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"]["ends_at"], json!(["must not be before starts_at"]));
+    let event = json!({
+        "title": "Launch party", "starts_at": "2030-01-01T18:00", "timezone": "Mars/Olympus_Mons", "location": "Lab",
+        "event_type": "Meetup", "status": "Upcoming", "description": "Cake"
+    });
+    let (status, body) = app.send(Method::POST, "/api/v1/events", Some(&token), Some(event)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["fields"]["timezone"].is_array());
+
+    let file = json!({ "name": "arm.exe", "url": "https://example.com/arm.exe", "file_type": "exe", "size": -1 });
+    let uri = format!("/api/v1/projects/{}/files", project_id.to_hex());
+    let (status, body) = app.send(Method::POST, &uri, Some(&token), Some(file)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"]["size"], json!(["must be at least 0"]));
+    assert_eq!(body["details"]["fields"]["file_type"], json!(["must be one of: stl, dxf"]));
+
+    // Values of the wrong type are named the same way
+    let uri = format!("/api/v1/users/{}/coin-transactions", member_id.to_hex());
+    for (grant, field) in [
+        (json!({ "amount": 1_000_000, "reason": "Oops" }), "amount"),
+        (json!({ "amount": "ten", "reason": "Oops" }), "amount"),
+        (json!({ "amount": 10 }), "body"),
+    ] {
+        let (status, body) = app.send(Method::POST, &uri, Some(&token), Some(grant.clone())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", grant);
+        assert!(body["details"]["fields"][field].is_array(), "{}: {}", grant, body);
+    }
+
+    // Legacy routes check the same rules on their flattened bodies
+    let grant = json!({ "user_id": member_id.to_hex(), "amount": 0, "reason": "Nothing" });
+    let (status, body) = app.send(Method::POST, "/coins/manage", Some(&token), Some(grant)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["fields"]["amount"], json!(["must not be zero"]));
+    let user = UserRepository::get(&*app.store, member_id).await.unwrap().unwrap();
+    assert_eq!((user.role, user.coins), (Role::Member, 0));
+}
+
+#[tokio::test]
+async fn outreach_is_limited_per_user() {
+    let mut config = Config::default();
+    config.rate_limit.outreach = RateLimitPolicy { requests: 2, per_seconds: 3600 };
+    let app = TestApp::with_config(config);
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let other = app.user("other", Role::Member).await;
+    let member_token = app.token(&member).await;
+    let message = json!({
+        "recipient_ids": [admin.id.unwrap().to_hex()],
+        "subject": "Hi",
+        "content": "Hello",
+        "message_type": "individual"
+    });
+
+    // The legacy alias draws from the same bucket
+    let (status, _) = app.send(Method::POST, "/api/v1/messages", Some(&member_token), Some(message.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::POST, "/messages/send", Some(&member_token), Some(message.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let response = app.request(Method::POST, "/api/v1/messages", Some(&member_token), Some(message.clone())).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1800");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    let (status, _) = app.send(Method::POST, "/api/v1/events/proposals", Some(&member_token), Some(json!({}))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Other members and other routes are unaffected
+    let (status, _) = app.send(Method::POST, "/api/v1/messages", Some(&app.token(&other).await), Some(message)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::GET, "/api/v1/users/me/messages", Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn github_logins_claim_only_unresolved_legacy_accounts() {
+    let app = TestApp::new();
+    // Same verified emails as the GitHub login, but not a legacy GitHub account
+    let oidc_only = User {
+        linked_identities: vec![LinkedIdentity::new("college", "ada-sub", None, Some("ada@example.com".to_string()))],
+        ..TestApp::account("ada", Role::Member)
+    };
+    UserRepository::insert(&*app.store, &oidc_only).await.unwrap();
+    UserRepository::insert(&*app.store, &TestApp::account("unlinked", Role::Member)).await.unwrap();
+    let emails = vec!["ada@example.com".to_string(), "unlinked@example.com".to_string()];
+    assert!(app.store.find_unlinked_github_account(&emails).await.unwrap().is_none());
+
+    let legacy = User { legacy_github_unresolved: true, ..TestApp::account("grace", Role::Member) };
+    let legacy_id = UserRepository::insert(&*app.store, &legacy).await.unwrap();
+    let claimed = app.store.find_unlinked_github_account(&["grace@example.com".to_string()]).await.unwrap();
+    assert_eq!(claimed.and_then(|user| user.id), Some(legacy_id));
+}
+
+#[tokio::test]
+async fn failed_logins_lock_the_account() {
+    let app = TestApp::new();
+    let password_hash = hash_password("correct horse 42".to_string()).await.unwrap();
+    let user = User { password_hash, ..TestApp::account("jane", Role::Member) };
+    let user_id = UserRepository::insert(&*app.store, &user).await.unwrap();
+    let login = |login: &str, password: &str| Some(json!({ "login": login, "password": password }));
+
+    // Parallel guesses share one count
+    let guesses = (0..5).map(|_| app.send(Method::POST, "/api/v1/auth/login", None, login("jane@example.com", "wrong")));
+    for (status, body) in futures_util::future::join_all(guesses).await {
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "invalid_credentials");
+    }
+    let user = UserRepository::get(&*app.store, user_id).await.unwrap().unwrap();
+    assert!(user.locked_until.is_some());
+
+    // Locked, every attempt is refused before the password is looked at
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/login", None, login("jane@example.com", "wrong")).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::LOCKED, Some("account_locked")));
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/login", None, login("jane@example.com", "correct horse 42")).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::LOCKED, Some("account_locked")));
+}
+
+#[tokio::test]
+async fn each_email_is_invited_once() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let token = app.token(&admin).await;
+
+    let invite = json!({ "email": "Guest@Example.com" });
+    let (status, _) = app.send(Method::POST, "/api/v1/invites", Some(&token), Some(invite.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = app.send(Method::POST, "/api/v1/invites", Some(&token), Some(invite)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, invites) = app.send(Method::GET, "/api/v1/invites", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invites["items"][0]["email"], "guest@example.com");
+    assert_eq!(invites["total"], 1);
+}
+
+#[tokio::test]
+async fn scoped_lists_page_within_their_scope() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let other = app.user("other", Role::Member).await;
+    let admin_token = app.token(&admin).await;
+    let pending = User { status: AccountStatus::PendingApproval, ..TestApp::account("pending", Role::Member) };
+    UserRepository::insert(&*app.store, &pending).await.unwrap();
+
+    for (subject, recipient) in [("First", &member), ("Second", &member), ("Elsewhere", &other), ("Third", &member)] {
+        let message = json!({
+            "recipient_ids": [recipient.id.unwrap().to_hex()],
+            "subject": subject,
+            "content": "Hello",
+            "message_type": "individual",
+        });
+        let (status, _) = app.send(Method::POST, "/api/v1/messages", Some(&admin_token), Some(message)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // The inbox holds only the caller's messages, newest first
+    let member_token = app.token(&member).await;
+    let (status, inbox) = app.send(Method::GET, "/api/v1/users/me/messages?limit=2", Some(&member_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(inbox["total"], 3);
+    assert_eq!(inbox["items"][0]["subject"], "Third");
+    assert_eq!(inbox["items"][1]["subject"], "Second");
+    let uri = format!("/api/v1/users/me/messages?limit=2&cursor={}", inbox["next_cursor"].as_str().unwrap());
+    let (_, rest) = app.send(Method::GET, &uri, Some(&member_token), None).await;
+    assert_eq!(rest["items"][0]["subject"], "First");
+    assert!(rest["next_cursor"].is_null());
+
+    let (status, users) = app.send(Method::GET, "/api/v1/users/pending", Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users["total"], 1);
+    assert_eq!(users["items"][0]["username"], "pending");
+    let (_, members) = app.send(Method::GET, "/api/v1/members?status=Active&limit=1", Some(&admin_token), None).await;
+    assert_eq!(members["total"], 3);
+    assert!(members["next_cursor"].is_string());
+}
+
+#[tokio::test]
+async fn sign_in_steps_are_used_once() {
+    let app = TestApp::new();
+    let password_hash = hash_password("correct horse 42".to_string()).await.unwrap();
+    let two_factor = TwoFactor {
+        secret: "JBSWY3DPEHPK3PXP".to_string(),
+        enabled: true,
+        recovery_code_hashes: vec![hash_token("a1b2c-3d4e5")],
+        last_used_step: None,
+        enabled_at: Some(DateTime::now()),
+    };
+    let user = User { password_hash, two_factor: Some(two_factor), ..TestApp::account("jane", Role::Member) };
+    let user_id = UserRepository::insert(&*app.store, &user).await.unwrap();
+
+    // Password first, then a second factor against the challenge
+    let login = json!({ "login": "jane", "password": "correct horse 42" });
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/login", None, Some(login)).await;
+    assert_eq!((status, body["two_factor_required"].as_bool()), (StatusCode::OK, Some(true)));
+    let verify = json!({ "challenge_token": body["challenge_token"], "code": "A1B2C-3D4E5" });
+    let (status, _) = app.send(Method::POST, "/api/v1/auth/2fa/verify", None, Some(verify.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/2fa/verify", None, Some(verify)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("invalid_challenge")));
+
+    let now = DateTime::now().timestamp_millis();
+    let reset = PasswordReset {
+        id: None,
+        user_id,
+        token_hash: hash_token("reset-token"),
+        created_at: DateTime::from_millis(now),
+        expires_at: DateTime::from_millis(now + 60_000),
+    };
+    PasswordResetRepository::insert(&*app.store, &reset).await.unwrap();
+    let reset = json!({ "token": "reset-token", "new_password": "battery staple 7" });
+    let (status, _) = app.send(Method::POST, "/api/v1/auth/password/reset", None, Some(reset.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/password/reset", None, Some(reset)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_reset_token")));
+
+    let pending = OAuthState {
+        id: None,
+        state: "csrf".to_string(),
+        provider: "github".to_string(),
+        pkce_verifier: "verifier".to_string(),
+        nonce: None,
+        link_user_id: None,
+        created_at: DateTime::from_millis(now),
+        expires_at: DateTime::from_millis(now + 60_000),
+    };
+    OAuthStateRepository::insert(&*app.store, &pending).await.unwrap();
+    assert!(consume_oauth_state(&app.state, "college", "csrf").await.is_err());
+    OAuthStateRepository::insert(&*app.store, &pending).await.unwrap();
+    assert_eq!(consume_oauth_state(&app.state, "github", "csrf").await.unwrap().pkce_verifier, "verifier");
+    assert!(consume_oauth_state(&app.state, "github", "csrf").await.is_err());
+}
+
+#[tokio::test]
+async fn tokens_and_impersonation_leave_an_audit_trail() {
+    let app = TestApp::new();
+    let admin = app.user("admin", Role::Admin).await;
+    let member = app.user("member", Role::Member).await;
+    let member_id = member.id.unwrap().to_hex();
+    let admin_token = app.token(&admin).await;
+
+    let request = json!({ "name": "leaderboard bot", "scopes": ["coins:grant"] });
+    let (status, body) = app.send(Method::POST, "/api/v1/auth/tokens", Some(&admin_token), Some(request)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let secret = body["token"].as_str().unwrap().to_string();
+    let token_id = body["access_token"]["id"].as_str().unwrap().to_string();
+    let grant = json!({ "amount": 10, "reason": "Weekly top score" });
+    let uri = format!("/api/v1/users/{}/coin-transactions", member_id);
+    let (status, _) = app.send(Method::POST, &uri, Some(&secret), Some(grant)).await;
+    assert!(status.is_success());
+
+    let uri = format!("/api/v1/auth/tokens/{}", token_id);
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.send(Method::DELETE, &uri, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.send(Method::GET, "/api/v1/users/me/messages", Some(&secret), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (_, activity) = app.send(Method::GET, &format!("{}/activity", uri), Some(&admin_token), None).await;
+    let actions: Vec<&str> = activity.as_array().unwrap().iter().filter_map(|entry| entry["action"].as_str()).collect();
+    assert_eq!(actions, ["access_token.revoke", "access_token.request", "access_token.create"]);
+
+    let uri = format!("/api/v1/users/{}/impersonate", member_id);
+    let (status, body) = app.send(Method::POST, &uri, Some(&admin_token), Some(json!({ "reason": "Ticket #123" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, log) = app.send(Method::GET, "/api/v1/audit-log?action=user.impersonate", Some(&admin_token), None).await;
+    assert_eq!(log.as_array().map(Vec::len), Some(1));
+    assert_eq!(log[0]["details"]["session_id"], body["impersonation"]["session_id"]);
+    assert_eq!(log[0]["details"]["reason"], "Ticket #123");
+}
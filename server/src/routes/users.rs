@@ -1,212 +1,332 @@
-use axum::{extract::{State, Path}, Json, http::StatusCode, response::IntoResponse};
-use futures_util::stream::TryStreamExt;
-use mongodb::bson::doc;
+use axum::{extract::{State, Path}, Json, http::StatusCode};
+use mongodb::bson::{doc, DateTime};
 use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
-use crate::{db::AppState, models::{User, Role}};
+use crate::{db::AppState, models::{AccountStatus, Invite, User, Role, UserResponse}};
+use crate::repo::list::Sort;
+use crate::repo::{Page, RepoError};
+use crate::routes::list::{Filter, ListQuery, ListSpec};
+use crate::auth::password::{hash_password, password_policy_violations, policy_error};
+use crate::middleware::auth::AuthUser;
+use crate::error::{found, parse_id, AppError, AppResult, ErrorBody};
+use crate::validate::{invalid_field, not_blank, not_one_of, ValidJson};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateUserRequest {
+    #[validate(length(max = 39), custom(function = "not_blank"))]
     pub username: String,
+    #[validate(length(max = 100), custom(function = "not_blank"))]
     pub full_name: String,
+    #[validate(email)]
     pub email: String,
-    pub password_hash: String,
-    pub role: String, // "Admin" or "Member"
+    pub password: Option<String>, // Optional initial password; GitHub-only accounts leave it out
+    #[validate(custom(function = "known_role"))]
+    pub role: String, // "Admin", "EventCoordinator", "Editor", "Treasurer" or "Member"
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateRoleRequest {
-    pub user_id: String,
-    pub role: String, // "Admin" or "Member"
+    #[validate(custom(function = "known_role"))]
+    pub role: String, // "Admin", "EventCoordinator", "Editor", "Treasurer" or "Member"
 }
 
-#[derive(Deserialize)]
-pub struct DeleteUserRequest {
-    pub user_id: String,
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateInviteRequest {
+    #[validate(email)]
+    pub email: String,
 }
 
-// Get all users (admin)
-pub async fn get_users(State(state): State<AppState>) -> Json<Vec<User>> {
-    let mut cursor = state.users.find(doc! {}).await.unwrap();
-    let mut users = Vec::new();
+const ROLES: &[&str] = &["Admin", "EventCoordinator", "Editor", "Treasurer", "Member"];
 
-    while let Some(user) = cursor.try_next().await.unwrap() {
-        users.push(user);
-    }
+fn parse_role(s: &str) -> Result<Role, ValidationError> {
+    ROLES.iter().find(|role| role.eq_ignore_ascii_case(s)).and_then(|role| Role::parse(role)).ok_or_else(|| not_one_of(ROLES))
+}
 
-    Json(users)
+fn known_role(value: &str) -> Result<(), ValidationError> {
+    parse_role(value).map(drop)
 }
 
-// Get all members only (admin)
-pub async fn get_members(State(state): State<AppState>) -> Json<Vec<User>> {
-    let mut cursor = state.users
-        .find(doc! {})
-        .await
-        .unwrap();
-    
-    let mut members = Vec::new();
-    while let Some(user) = cursor.try_next().await.unwrap() {
-        members.push(user);
-    }
+pub struct UserList;
+
+impl ListSpec for UserList {
+    const SORTS: &'static [&'static str] = &["created_at", "username", "coins"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[
+        ("role", Filter::OneOf(ROLES)),
+        ("status", Filter::OneOf(&["Active", "PendingApproval"])),
+    ];
+}
 
-    Json(members)
+// Get all users (admin)
+#[utoipa::path(
+    get, path = "/api/v1/users", tag = "users", security(("bearer" = [])),
+    params(ListQuery<UserList>),
+    responses((status = 200, body = Page<UserResponse>))
+)]
+pub async fn get_users(
+    State(state): State<AppState>,
+    query: ListQuery<UserList>,
+) -> AppResult<Json<Page<UserResponse>>> {
+    let users = state.users.page(&query.params).await?;
+    Ok(Json(users.map(UserResponse::from)))
+}
+
+// Get all members only (admin)
+#[utoipa::path(
+    get, path = "/api/v1/members", tag = "users", security(("bearer" = [])),
+    params(ListQuery<UserList>),
+    responses((status = 200, body = Page<UserResponse>))
+)]
+pub async fn get_members(
+    State(state): State<AppState>,
+    query: ListQuery<UserList>,
+) -> AppResult<Json<Page<UserResponse>>> {
+    let members = state.users.page(&query.params).await?;
+    Ok(Json(members.map(UserResponse::from)))
 }
 
 // Get user by ID (protected - any authenticated user can access)
+#[utoipa::path(
+    get, path = "/api/v1/users/{id}", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({
+            "id": {"$oid": "507f1f77bcf86cd799439011"},
+            "username": "johndoe",
+            "full_name": "John Doe",
+            "email": "john@example.com",
+            "role": "Member",
+            "coins": 150
+        })),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn get_user_by_id(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
-) -> impl IntoResponse {
-    let oid = match mongodb::bson::oid::ObjectId::parse_str(&user_id) {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": "Invalid user ID format"
-                }))
-            ).into_response();
-        }
-    };
+) -> AppResult<Json<serde_json::Value>> {
+    let oid = parse_id(&user_id, "user ID")?;
+    let user = found(state.users.get(oid).await?, "User")?;
 
-    match state.users.find_one(doc! { "_id": oid }).await {
-        Ok(Some(user)) => {
-            // Return only safe user information (no password hash)
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "id": user.id,
-                    "username": user.username,
-                    "full_name": user.full_name,
-                    "email": user.email,
-                    "role": user.role,
-                    "coins": user.coins
-                }))
-            ).into_response()
-        }
-        Ok(None) => {
-            (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({
-                    "error": "User not found"
-                }))
-            ).into_response()
-        }
-        Err(e) => {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Database error",
-                    "message": e.to_string()
-                }))
-            ).into_response()
-        }
-    }
+    // Return only safe user information (no password hash)
+    Ok(Json(serde_json::json!({
+        "id": user.id,
+        "username": user.username,
+        "full_name": user.full_name,
+        "email": user.email,
+        "role": user.role,
+        "coins": user.coins
+    })))
 }
 
 // Add user/member (admin)
+#[utoipa::path(
+    post, path = "/api/v1/users", tag = "users", security(("bearer" = [])),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, body = String, example = json!("User added successfully")),
+        (status = 409, description = "Email already taken", body = ErrorBody)
+    )
+)]
 pub async fn add_user(
     State(state): State<AppState>,
-    Json(payload): Json<CreateUserRequest>,
-) -> Json<String> {
-    let role = match payload.role.as_str() {
-        "Admin" => Role::Admin,
-        _ => Role::Member,
+    ValidJson(payload): ValidJson<CreateUserRequest>,
+) -> AppResult<Json<String>> {
+    let role = parse_role(&payload.role).map_err(|e| invalid_field("role", e))?;
+
+    let password_hash = match payload.password {
+        Some(password) => {
+            let violations = password_policy_violations(&password, &[&payload.username, &payload.email]);
+            if !violations.is_empty() {
+                return Err(policy_error(violations));
+            }
+            hash_password(password).await.map_err(AppError::Internal)?
+        }
+        None => String::new(),
     };
 
     let new_user = User {
         id: None,
         username: payload.username,
         full_name: payload.full_name,
-        email: payload.email,
-        password_hash: payload.password_hash,
+        email: payload.email.trim().to_lowercase(),
+        password_hash,
         role,
+        status: AccountStatus::Active,
         coins: 0,
         project_ids: Some(Vec::new()),
-        created_at: chrono::Utc::now().to_rfc3339(),
-        updated_at: chrono::Utc::now().to_rfc3339(),
+        failed_login_attempts: 0,
+        locked_until: None,
+        linked_identities: Vec::new(),
+        legacy_github_unresolved: false,
+        two_factor: None,
+        created_at: DateTime::now(),
+        updated_at: DateTime::now(),
     };
 
-    state.users.insert_one(new_user).await.unwrap();
-    Json("User added successfully".to_string())
+    match state.users.insert(&new_user).await {
+        Ok(_) => Ok(Json("User added successfully".to_string())),
+        Err(RepoError::Duplicate) => Err(AppError::Conflict("A user with this email already exists".to_string()).with_code("email_taken")),
+        Err(e) => Err(e.into()),
+    }
 }
 
 // Update user role (admin)
+#[utoipa::path(
+    put, path = "/api/v1/users/{id}/role", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "User role updated successfully"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn update_user_role(
     State(state): State<AppState>,
-    Json(payload): Json<UpdateRoleRequest>,
-) -> impl IntoResponse {
-    let user_id = match mongodb::bson::oid::ObjectId::parse_str(&payload.user_id) {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": "Invalid user ID format"
-                }))
-            ).into_response();
-        }
-    };
-    
-    let role = match payload.role.as_str() {
-        "Admin" => "Admin",
-        _ => "Member",
-    };
+    Path(user_id): Path<String>,
+    ValidJson(payload): ValidJson<UpdateRoleRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let user_id = parse_id(&user_id, "user ID")?;
+    let role = parse_role(&payload.role).map_err(|e| invalid_field("role", e))?;
 
-    match state.users
-        .update_one(
-            doc! { "_id": user_id },
-            doc! { 
-                "$set": { 
-                    "role": role,
-                    "updated_at": chrono::Utc::now().to_rfc3339()
-                }
-            },
-        )
-        .await
-    {
-        Ok(_) => {
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": true,
-                    "message": "User role updated successfully"
-                }))
-            ).into_response()
-        }
-        Err(e) => {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Database error",
-                    "message": e.to_string()
-                }))
-            ).into_response()
-        }
+    if !state.users.set_role(user_id, role).await? {
+        return Err(AppError::NotFound("User not found".to_string()));
     }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "User role updated successfully"
+    })))
 }
 
 // Delete/Remove user (admin)
+#[utoipa::path(
+    delete, path = "/api/v1/users/{id}", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = String, example = json!("User deleted successfully")),
+        (status = 404, body = ErrorBody)
+    )
+)]
 pub async fn delete_user(
     State(state): State<AppState>,
-    Json(payload): Json<DeleteUserRequest>,
-) -> Json<String> {
-    let user_id = mongodb::bson::oid::ObjectId::parse_str(&payload.user_id).unwrap();
-
-    // Remove user from all projects
-    state.projects
-        .update_many(
-            doc! {},
-            doc! { "$pull": { "member_ids": user_id } },
-        )
-        .await
-        .unwrap();
-
-    // Delete the user
-    state.users
-        .delete_one(doc! { "_id": user_id })
-        .await
-        .unwrap();
-
-    Json("User deleted successfully".to_string())
-}
\ No newline at end of file
+    Path(user_id): Path<String>,
+) -> AppResult<Json<String>> {
+    let user_id = parse_id(&user_id, "user ID")?;
+
+    if !state.users.delete(user_id).await? {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    Ok(Json("User deleted successfully".to_string()))
+}
+
+pub struct PendingUserList;
+
+impl ListSpec for PendingUserList {
+    const SORTS: &'static [&'static str] = UserList::SORTS;
+    const DEFAULT_SORT: Sort = UserList::DEFAULT_SORT;
+    const FILTERS: &'static [(&'static str, Filter)] = &[("role", Filter::OneOf(ROLES))];
+}
+
+// Get accounts waiting for approval (admin)
+#[utoipa::path(
+    get, path = "/api/v1/users/pending", tag = "users", security(("bearer" = [])),
+    params(ListQuery<PendingUserList>),
+    responses((status = 200, body = Page<UserResponse>))
+)]
+pub async fn get_pending_users(
+    State(state): State<AppState>,
+    query: ListQuery<PendingUserList>,
+) -> AppResult<Json<Page<UserResponse>>> {
+    let users = state.users.page_by_status(AccountStatus::PendingApproval, &query.params).await?;
+    Ok(Json(users.map(UserResponse::from)))
+}
+
+// Approve a pending account so it can sign in (admin)
+#[utoipa::path(
+    post, path = "/api/v1/users/{id}/approve", tag = "users", security(("bearer" = [])),
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Object, example = json!({"success": true, "message": "User approved successfully"})),
+        (status = 404, body = ErrorBody)
+    )
+)]
+pub async fn approve_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let user_id = parse_id(&user_id, "user ID")?;
+
+    let approved = state.users
+        .set_status(user_id, Some(AccountStatus::PendingApproval), AccountStatus::Active)
+        .await?;
+    if !approved {
+        return Err(AppError::NotFound("No pending user with this ID".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "User approved successfully"
+    })))
+}
+
+pub struct InviteList;
+
+impl ListSpec for InviteList {
+    const SORTS: &'static [&'static str] = &["created_at", "email"];
+    const DEFAULT_SORT: Sort = Sort { field: "created_at", descending: false };
+    const FILTERS: &'static [(&'static str, Filter)] = &[("invited_by", Filter::Id)];
+}
+
+// Get all signup invites (admin)
+#[utoipa::path(
+    get, path = "/api/v1/invites", tag = "users", security(("bearer" = [])),
+    params(ListQuery<InviteList>),
+    responses((status = 200, body = Page<Invite>))
+)]
+pub async fn get_invites(
+    State(state): State<AppState>,
+    query: ListQuery<InviteList>,
+) -> AppResult<Json<Page<Invite>>> {
+    Ok(Json(state.invites.page(&query.params).await?))
+}
+
+// Invite an email address to sign up while the signup policy is restricted (admin)
+#[utoipa::path(
+    post, path = "/api/v1/invites", tag = "users", security(("bearer" = [])),
+    request_body = CreateInviteRequest,
+    responses(
+        (status = 201, body = Object, example = json!({"success": true, "invite_id": {"$oid": "507f1f77bcf86cd799439011"}})),
+        (status = 409, body = ErrorBody)
+    )
+)]
+pub async fn create_invite(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    ValidJson(payload): ValidJson<CreateInviteRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let email = payload.email.trim().to_lowercase();
+
+    let invite = Invite {
+        id: None,
+        email,
+        invited_by: auth_user.id,
+        created_at: mongodb::bson::DateTime::now(),
+        accepted_at: None,
+    };
+
+    match state.invites.insert(&invite).await {
+        Ok(invite_id) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({
+                "success": true,
+                "invite_id": invite_id
+            }))
+        )),
+        Err(RepoError::Duplicate) => Err(AppError::Conflict("This email has already been invited".to_string())),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,168 @@
+use axum::{Router, routing::{delete, get, patch, post, put}, middleware};
+
+use crate::db::AppState;
+use crate::middleware::{auth_middleware, rate_limit, require_permission};
+use crate::models::user::Permission;
+use crate::rate_limit::Policies;
+
+use crate::routes::users::{
+    get_users, get_members, add_user, update_user_role, delete_user, get_user_by_id,
+    get_pending_users, approve_user, get_invites, create_invite,
+};
+use crate::routes::projects::{
+    get_all_projects, get_user_projects, create_project,
+    assign_member_to_project, remove_member_from_project, delete_project,
+    set_project_lead, update_project, add_file_to_project, delete_file_from_project
+};
+use crate::routes::project_join_requests::{
+    create_join_request, get_project_join_requests, update_join_request_status
+};
+use crate::routes::coins::{manage_coins, get_coin_transactions, get_weekly_leaderboard, save_weekly_leaderboard};
+use crate::routes::messages::{send_message, get_user_messages, get_all_messages};
+use crate::routes::gallery::{get_all_gallery, create_gallery_item, update_gallery_item, delete_gallery_item};
+use crate::routes::events::{get_all_events, create_event, update_event, delete_event, propose_event};
+use crate::routes::stats::get_stats;
+use crate::routes::blogs::{get_all_blogs, get_blog_by_slug, create_blog, delete_blog};
+
+use crate::auth::{github_login, github_callback, link_provider, get_providers};
+#[cfg(feature = "dev-login")]
+use crate::auth::test_login;
+use crate::auth::impersonation::impersonate_user;
+use crate::routes::audit::get_audit_log;
+use crate::auth::oidc::{oidc_login, oidc_callback};
+use crate::auth::two_factor::{
+    verify_challenge, get_two_factor_status, enroll, confirm, regenerate_recovery_codes, disable,
+};
+use crate::auth::identities::{get_identities, unlink_identity};
+use crate::auth::access_tokens::{
+    get_access_tokens, create_access_token, revoke_access_token, get_access_token_activity,
+};
+use crate::auth::sessions::{refresh_session, get_sessions, logout, force_logout_user};
+use crate::auth::password::{register, login, forgot_password, reset_password};
+
+// The versioned API, nested under /api/v1: resources addressed by path, one
+// method per action. Paths shared by several permission groups (`/users/{id}`,
+// `/messages`) get each method from the group that guards it.
+pub fn routes(state: &AppState, limits: &Policies) -> Router<AppState> {
+    // Sign-in, signup and account recovery, limited per IP
+    let auth_routes = Router::new()
+        .route("/auth/github", get(github_login))
+        .route("/auth/github/callback", get(github_callback))
+        .route("/auth/2fa/verify", post(verify_challenge))
+        .route("/auth/{provider}", get(oidc_login))
+        .route("/auth/{provider}/callback", get(oidc_callback))
+        .route("/auth/register", post(register))
+        .route("/auth/login", post(login))
+        .route("/auth/password/forgot", post(forgot_password))
+        .route("/auth/password/reset", post(reset_password));
+
+    // Credential-free sign-in for local development only
+    #[cfg(feature = "dev-login")]
+    let auth_routes = auth_routes.route("/auth/test-login", post(test_login));
+
+    let auth_routes = auth_routes.layer(middleware::from_fn_with_state(state.clone(), rate_limit(limits.auth)));
+
+    // Public routes
+    let public_routes = Router::new()
+        .route("/auth/providers", get(get_providers))
+        .route("/auth/refresh", post(refresh_session))
+        .route("/coins/leaderboard", get(get_weekly_leaderboard))
+        .route("/projects", get(get_all_projects))
+        .route("/gallery", get(get_all_gallery))
+        .route("/events", get(get_all_events))
+        .route("/stats", get(get_stats))
+        .route("/blogs", get(get_all_blogs))
+        .route("/blogs/{slug}", get(get_blog_by_slug));
+
+    // Requests that reach other members (messages, proposals to the event
+    // admins, join requests to project leads), limited per user
+    let outreach_routes = Router::new()
+        .route("/projects/{id}/join-requests", post(create_join_request))
+        .route("/messages", post(send_message))
+        .route("/events/proposals", post(propose_event))
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit(limits.outreach)));
+
+    // Protected routes
+    let protected_routes = Router::new()
+        .route("/auth/sessions", get(get_sessions))
+        .route("/auth/logout", post(logout))
+        .route("/auth/link/{provider}", post(link_provider))
+        .route("/auth/identities", get(get_identities))
+        .route("/auth/identities/{provider}", delete(unlink_identity))
+        .route("/auth/2fa", get(get_two_factor_status))
+        .route("/auth/2fa/enroll", post(enroll))
+        .route("/auth/2fa/confirm", post(confirm))
+        .route("/auth/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/auth/2fa/disable", post(disable))
+        .route("/auth/tokens", get(get_access_tokens).post(create_access_token))
+        .route("/auth/tokens/{id}", delete(revoke_access_token))
+        .route("/auth/tokens/{id}/activity", get(get_access_token_activity))
+        .route("/users/me/projects", get(get_user_projects))
+        .route("/users/me/messages", get(get_user_messages))
+        .route("/users/me/coin-transactions", get(get_coin_transactions))
+        .route("/users/{id}", get(get_user_by_id))
+        .route("/projects/{id}/join-requests", get(get_project_join_requests))
+        .route("/join-requests/{id}", patch(update_join_request_status))
+        .route("/projects/{id}/members/{member_id}", delete(remove_member_from_project))
+        .route("/projects/{id}/files", post(add_file_to_project))
+        .route("/projects/{id}/files/{file_id}", delete(delete_file_from_project))
+        .route("/blogs", post(create_blog))
+        .route("/blogs/{slug}", delete(delete_blog))
+        .merge(outreach_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    // Privileged routes, each group gated by the permission it needs
+    let user_admin_routes = Router::new()
+        .route("/users", get(get_users).post(add_user))
+        .route("/users/{id}", delete(delete_user))
+        .route("/users/{id}/role", put(update_user_role))
+        .route("/users/{id}/approve", post(approve_user))
+        .route("/users/{id}/sessions", delete(force_logout_user))
+        .route("/users/{id}/impersonate", post(impersonate_user))
+        .route("/users/pending", get(get_pending_users))
+        .route("/members", get(get_members))
+        .route("/invites", get(get_invites).post(create_invite))
+        .route("/audit-log", get(get_audit_log))
+        .layer(middleware::from_fn(require_permission(Permission::UsersManage)));
+
+    let project_admin_routes = Router::new()
+        .route("/projects", post(create_project))
+        .route("/projects/{id}", patch(update_project).delete(delete_project))
+        .route("/projects/{id}/members/{member_id}", put(assign_member_to_project))
+        .route("/projects/{id}/lead", put(set_project_lead))
+        .layer(middleware::from_fn(require_permission(Permission::ProjectsManage)));
+
+    let coin_admin_routes = Router::new()
+        .route("/users/{id}/coin-transactions", post(manage_coins))
+        .route("/coins/leaderboard/snapshots", post(save_weekly_leaderboard))
+        .layer(middleware::from_fn(require_permission(Permission::CoinsGrant)));
+
+    let message_admin_routes = Router::new()
+        .route("/messages", get(get_all_messages))
+        .layer(middleware::from_fn(require_permission(Permission::MessagesReadAll)));
+
+    let gallery_admin_routes = Router::new()
+        .route("/gallery", post(create_gallery_item))
+        .route("/gallery/{id}", patch(update_gallery_item).delete(delete_gallery_item))
+        .layer(middleware::from_fn(require_permission(Permission::GalleryWrite)));
+
+    let event_admin_routes = Router::new()
+        .route("/events", post(create_event))
+        .route("/events/{id}", patch(update_event).delete(delete_event))
+        .layer(middleware::from_fn(require_permission(Permission::EventsWrite)));
+
+    let admin_routes = Router::new()
+        .merge(user_admin_routes)
+        .merge(project_admin_routes)
+        .merge(coin_admin_routes)
+        .merge(message_admin_routes)
+        .merge(gallery_admin_routes)
+        .merge(event_admin_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    Router::new()
+        .merge(auth_routes)
+        .merge(public_routes)
+        .merge(protected_routes)
+        .merge(admin_routes)
+}
@@ -0,0 +1,186 @@
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, OptionalFromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::error::Error;
+use validator::{Validate, ValidateUrl, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::error::AppError;
+
+// `Json<T>` that also runs `T`'s `#[validate(...)]` rules. A body that doesn't
+// fit the type or breaks a rule is a 422 naming every bad field:
+// `{ "code": "validation_failed", "details": { "fields": { "title": ["must not be blank"] } } }`.
+// Malformed JSON and a missing content type are rejected as `Json` does.
+#[derive(Debug)]
+pub struct ValidJson<T>(pub T);
+
+impl<T: DeserializeOwned + Validate, S: Send + Sync> FromRequest<S> for ValidJson<T> {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = <Json<T> as FromRequest<S>>::from_request(req, state).await.map_err(rejected)?;
+        value.validate().map_err(|errors| invalid(&errors).into_response())?;
+        Ok(ValidJson(value))
+    }
+}
+
+// `Option<ValidJson<T>>`: `None` when there's no JSON body at all
+impl<T: DeserializeOwned + Validate, S: Send + Sync> OptionalFromRequest<S> for ValidJson<T> {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let Some(Json(value)) = <Json<T> as OptionalFromRequest<S>>::from_request(req, state).await.map_err(rejected)?
+        else {
+            return Ok(None);
+        };
+        value.validate().map_err(|errors| invalid(&errors).into_response())?;
+        Ok(Some(ValidJson(value)))
+    }
+}
+
+fn rejected(rejection: JsonRejection) -> Response {
+    let JsonRejection::JsonDataError(error) = rejection else {
+        return rejection.into_response();
+    };
+    // axum keeps serde's path to the offending value, e.g. "speakers[0].name"
+    let path_error = error
+        .source()
+        .and_then(|source| source.source())
+        .and_then(|source| source.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>());
+    let (field, message) = match path_error {
+        Some(e) if e.path().to_string() != "." => (e.path().to_string(), e.inner().to_string()),
+        Some(e) => ("body".to_string(), e.inner().to_string()),
+        None => ("body".to_string(), error.body_text()),
+    };
+    failed(BTreeMap::from([(field, vec![message])])).into_response()
+}
+
+fn failed(fields: BTreeMap<String, Vec<String>>) -> AppError {
+    AppError::Unprocessable("Request body failed validation".to_string())
+        .with_code("validation_failed")
+        .with_details(serde_json::json!({ "fields": fields }))
+}
+
+// The 422 for a body that broke its `#[validate(...)]` rules
+pub fn invalid(errors: &ValidationErrors) -> AppError {
+    let mut fields = BTreeMap::new();
+    collect(errors, "", &mut fields);
+    failed(fields)
+}
+
+// The 422 for one field a handler checks itself, in the same shape
+pub fn invalid_field(field: &str, error: ValidationError) -> AppError {
+    failed(BTreeMap::from([(field.to_string(), vec![message(&error)])]))
+}
+
+fn collect(errors: &ValidationErrors, prefix: &str, fields: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields.entry(path).or_default().extend(errors.iter().map(message));
+            }
+            ValidationErrorsKind::Struct(errors) => collect(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect(errors, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
+    }
+}
+
+// Rules set their own message; the built-in ones are worded from their params
+fn message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let (min, max) = (error.params.get("min"), error.params.get("max"));
+    let unit = if error.code == "length" { " characters" } else { "" };
+    match (error.code.as_ref(), min, max) {
+        ("length" | "range", Some(min), Some(max)) => format!("must be between {} and {}{}", min, max, unit),
+        ("length" | "range", Some(min), None) => format!("must be at least {}{}", min, unit),
+        ("length" | "range", None, Some(max)) => format!("must be at most {}{}", max, unit),
+        ("email", _, _) => "must be an email address".to_string(),
+        (code, _, _) => format!("failed the {} check", code),
+    }
+}
+
+// Text fields that must say something; `length(min = 1)` would let "   " through
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message("must not be blank".into()));
+    }
+    Ok(())
+}
+
+// Links shown to other users: only http(s), so no `javascript:` or `data:` URLs
+pub fn http_url(value: &str) -> Result<(), ValidationError> {
+    let scheme = value.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+    if !value.validate_url() || !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err(ValidationError::new("url").with_message("must be an http or https URL".into()));
+    }
+    Ok(())
+}
+
+// For enum fields: a name outside `allowed` is rejected, never mapped to a default
+pub fn not_one_of(allowed: &[&str]) -> ValidationError {
+    ValidationError::new("one_of").with_message(format!("must be one of: {}", allowed.join(", ")).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Validate)]
+    struct Speaker {
+        #[validate(custom(function = "not_blank"))]
+        name: String,
+    }
+
+    #[derive(Validate)]
+    struct Talk {
+        #[validate(length(max = 5), custom(function = "not_blank"))]
+        title: String,
+        #[validate(range(min = 0))]
+        size: i64,
+        #[validate(custom(function = "http_url"))]
+        link: Option<String>,
+        #[validate(nested)]
+        speakers: Vec<Speaker>,
+    }
+
+    fn fields(talk: Talk) -> serde_json::Value {
+        let AppError::Detailed { details, .. } = invalid(&talk.validate().unwrap_err()) else { unreachable!() };
+        details.unwrap()["fields"].clone()
+    }
+
+    #[test]
+    fn names_every_broken_field() {
+        let talk = Talk {
+            title: "      ".to_string(),
+            size: -1,
+            link: Some("javascript:alert(1)".to_string()),
+            speakers: vec![Speaker { name: "Ada".to_string() }, Speaker { name: String::new() }],
+        };
+        assert_eq!(
+            fields(talk),
+            serde_json::json!({
+                "title": ["must be at most 5 characters", "must not be blank"],
+                "size": ["must be at least 0"],
+                "link": ["must be an http or https URL"],
+                "speakers[1].name": ["must not be blank"],
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_a_valid_body() {
+        let talk = Talk { title: "Rust".to_string(), size: 0, link: Some("https://example.com/x".to_string()), speakers: vec![] };
+        assert!(talk.validate().is_ok());
+        assert!(http_url("HTTP://example.com").is_ok());
+        assert!(http_url("ftp://example.com").is_err());
+    }
+}